@@ -1,6 +1,12 @@
 use crate::config::{Config, ShortcutCommand};
+use crate::formatter::{GroupBy, TimeBucket};
+use crate::sorter::SortKey;
+use crate::utils::quoting::QuotingStyle;
+use crate::utils::size::parse_size;
+use crate::utils::time::{TimeField, TimeStyle};
 use clap::{App, Arg, ArgMatches, SubCommand};
 use clap_complete::Shell;
+use lla_plugin_interface::size::SizeStyle;
 use std::path::PathBuf;
 
 pub struct Args {
@@ -11,10 +17,31 @@ pub struct Args {
     pub table_format: bool,
     pub grid_format: bool,
     pub sizemap_format: bool,
+    pub treemap: bool,
     pub timeline_format: bool,
+    pub timeline_bucket: TimeBucket,
+    pub timeline_histogram: bool,
+    pub timeline_git_dates: bool,
     pub git_format: bool,
+    pub repos_format: bool,
     pub fuzzy_format: bool,
     pub recursive_format: bool,
+    pub json_format: bool,
+    pub ndjson_format: bool,
+    pub csv_format: bool,
+    pub tsv_format: bool,
+    pub print0: bool,
+    pub paths_only: bool,
+    pub columns: Option<String>,
+    pub format_string: Option<String>,
+    pub time_field: TimeField,
+    pub time_style: Option<TimeStyle>,
+    pub size_style: SizeStyle,
+    pub disk_usage: bool,
+    pub dereference: bool,
+    pub classify: bool,
+    pub quoting_style: QuotingStyle,
+    pub group_by: Option<GroupBy>,
     pub show_icons: bool,
     pub no_color: bool,
     pub sort_by: String,
@@ -28,6 +55,13 @@ pub struct Args {
     pub disable_plugin: Vec<String>,
     pub plugins_dir: PathBuf,
     pub include_dirs: bool,
+    pub tree_sizes: bool,
+    pub tree_percent: bool,
+    pub min_size: Option<u64>,
+    pub tree_compact: bool,
+    pub max_children: Option<usize>,
+    pub prune: bool,
+    pub highlight: bool,
     pub dirs_only: bool,
     pub files_only: bool,
     pub symlinks_only: bool,
@@ -35,6 +69,8 @@ pub struct Args {
     pub no_files: bool,
     pub no_symlinks: bool,
     pub no_dotfiles: bool,
+    pub git_ignore: bool,
+    pub ignore_files: Vec<PathBuf>,
     pub dotfiles_only: bool,
     pub command: Option<Command>,
 }
@@ -120,23 +156,144 @@ impl Args {
                     .long("sizemap")
                     .help("Show visual representation of file sizes (overrides config format)"),
             )
+            .arg(
+                Arg::with_name("treemap")
+                    .long("treemap")
+                    .help("Show file sizes as nested rectangles, recursing into directories up to --depth (overrides config format)"),
+            )
             .arg(
                 Arg::with_name("timeline")
                     .long("timeline")
                     .help("Group files by time periods (overrides config format)"),
             )
+            .arg(
+                Arg::with_name("bucket")
+                    .long("bucket")
+                    .takes_value(true)
+                    .value_name("BUCKET")
+                    .validator(|value| TimeBucket::parse(value).map(|_| ()))
+                    .help("Timeline sections: relative, hour, day, week, month, year, or ages such as 15m,1h,1d (implies --timeline)"),
+            )
+            .arg(
+                Arg::with_name("git-dates")
+                    .long("git-dates")
+                    .help("Place files in the timeline by the date of their last git commit (implies --timeline)"),
+            )
+            .arg(
+                Arg::with_name("no-histogram")
+                    .long("no-histogram")
+                    .help("Hide the activity histogram above the timeline"),
+            )
             .arg(
                 Arg::with_name("git")
                     .short('G')
                     .long("git")
                     .help("Show git status and information (overrides config format)"),
             )
+            .arg(
+                Arg::with_name("repos")
+                    .long("repos")
+                    .help("Find git repositories under the directory, including nested ones and submodules, and show one status row per repository (overrides config format)"),
+            )
             .arg(
                 Arg::with_name("fuzzy")
                     .short('F')
                     .long("fuzzy")
                     .help("Use interactive fuzzy finder"),
             )
+            .arg(
+                Arg::with_name("json")
+                    .long("json")
+                    .help("Output entries as a JSON array (overrides config format)"),
+            )
+            .arg(
+                Arg::with_name("ndjson")
+                    .long("ndjson")
+                    .help("Output entries as newline-delimited JSON, one object per line (overrides config format)"),
+            )
+            .arg(
+                Arg::with_name("csv")
+                    .long("csv")
+                    .help("Output entries as comma-separated values with a header row (overrides config format)"),
+            )
+            .arg(
+                Arg::with_name("tsv")
+                    .long("tsv")
+                    .help("Output entries as tab-separated values with a header row (overrides config format)"),
+            )
+            .arg(
+                Arg::with_name("print0")
+                    .short('0')
+                    .long("print0")
+                    .help("Print only the paths of matching entries, each terminated by a NUL byte (for xargs -0)"),
+            )
+            .arg(
+                Arg::with_name("paths-only")
+                    .long("paths-only")
+                    .help("Print only the paths of matching entries, one per line, without colors or icons"),
+            )
+            .arg(
+                Arg::with_name("columns")
+                    .long("columns")
+                    .takes_value(true)
+                    .help("Comma-separated list of columns for long, table, csv, tsv and git output (e.g., permissions,size,inode,name,field:git_status; commit,author,churn,lines,status in the git view)"),
+            )
+            .arg(
+                Arg::with_name("format-string")
+                    .long("format-string")
+                    .takes_value(true)
+                    .help("Format each entry with a template, e.g. \"{perms} {size:>8} {mtime:%Y-%m-%d} {name}\" (overrides config format)"),
+            )
+            .arg(
+                Arg::with_name("time")
+                    .long("time")
+                    .takes_value(true)
+                    .possible_values(TimeField::VALUES)
+                    .help("Timestamp to display and sort by (default from config)"),
+            )
+            .arg(
+                Arg::with_name("time-style")
+                    .long("time-style")
+                    .takes_value(true)
+                    .validator(|value| TimeStyle::parse(value).map(|_| ()))
+                    .help("How to render timestamps: relative, iso, long-iso, full or +FORMAT (default from config)"),
+            )
+            .arg(
+                Arg::with_name("size-style")
+                    .long("size-style")
+                    .takes_value(true)
+                    .possible_values(SizeStyle::VALUES)
+                    .help("How to render sizes: iec (1024-based), si (1000-based), bytes or blocks (default from config)"),
+            )
+            .arg(
+                Arg::with_name("disk-usage")
+                    .long("disk-usage")
+                    .conflicts_with("apparent-size")
+                    .help("Report the space allocated on disk instead of the file length, like du"),
+            )
+            .arg(
+                Arg::with_name("apparent-size")
+                    .long("apparent-size")
+                    .help("Report the file length rather than disk usage (overrides config setting)"),
+            )
+            .arg(
+                Arg::with_name("dereference")
+                    .short('L')
+                    .long("dereference")
+                    .help("Show information about the files symlinks point to instead of the links themselves"),
+            )
+            .arg(
+                Arg::with_name("classify")
+                    .long("classify")
+                    .help("Append an indicator to names: / for directories, @ for symlinks, * for executables, | for FIFOs, = for sockets"),
+            )
+            .arg(
+                Arg::with_name("quoting-style")
+                    .long("quoting-style")
+                    .takes_value(true)
+                    .possible_values(QuotingStyle::VALUES)
+                    .help("How to print unusual file names: literal, shell, escape or c [default: literal]"),
+            )
             .arg(
                 Arg::with_name("icons")
                     .long("icons")
@@ -156,9 +313,10 @@ impl Args {
                 Arg::with_name("sort")
                     .short('s')
                     .long("sort")
-                    .help("Sort files by name, size, or date")
+                    .help("Sort by comma-separated keys, e.g. ext,size:desc,name (see README for all keys)")
                     .takes_value(true)
-                    .possible_values(["name", "size", "date"])
+                    .value_name("KEYS")
+                    .validator(|value| SortKey::parse_list(value).map(|_| ()))
                     .default_value(&config.default_sort),
             )
             .arg(
@@ -182,6 +340,14 @@ impl Args {
                     .long("sort-natural")
                     .help("Use natural sorting for numbers (overrides config setting)"),
             )
+            .arg(
+                Arg::with_name("group-by")
+                    .long("group-by")
+                    .takes_value(true)
+                    .value_name("KEY")
+                    .validator(|value| GroupBy::parse(value).map(|_| ()))
+                    .help("Split the default, long, grid and table views into sections by extension, type, owner, category, git-status, date-bucket or field:NAME"),
+            )
             .arg(
                 Arg::with_name("filter")
                     .short('f')
@@ -226,6 +392,52 @@ impl Args {
                     .long("include-dirs")
                     .help("Include directory sizes in the metadata"),
             )
+            .arg(
+                Arg::with_name("tree-sizes")
+                    .long("tree-sizes")
+                    .help("Show sizes in tree view, with directories totalling their contents and file counts"),
+            )
+            .arg(
+                Arg::with_name("percent")
+                    .long("percent")
+                    .help("Show each entry's share of its parent directory's size in tree view"),
+            )
+            .arg(
+                Arg::with_name("min-size")
+                    .long("min-size")
+                    .takes_value(true)
+                    .value_name("SIZE")
+                    .validator(|value| parse_size(value).map(|_| ()))
+                    .help("Hide tree entries smaller than SIZE, e.g. 10M (K, M, G are 1024-based; KB, MB, GB 1000-based)"),
+            )
+            .arg(
+                Arg::with_name("compact")
+                    .long("compact")
+                    .help("Show chains of directories holding a single directory as one tree node (overrides config setting)"),
+            )
+            .arg(
+                Arg::with_name("max-children")
+                    .long("max-children")
+                    .takes_value(true)
+                    .value_name("N")
+                    .validator(|value| {
+                        value
+                            .parse::<usize>()
+                            .map(|_| ())
+                            .map_err(|_| "must be a number".to_string())
+                    })
+                    .help("Show at most N entries per directory in tree view, 0 for all (overrides config setting)"),
+            )
+            .arg(
+                Arg::with_name("prune")
+                    .long("prune")
+                    .help("With --filter in tree view, hide directories that contain no matches"),
+            )
+            .arg(
+                Arg::with_name("highlight")
+                    .long("highlight")
+                    .help("With --filter in tree view, highlight the part of each name that matched"),
+            )
             .arg(
                 Arg::with_name("dirs-only")
                     .long("dirs-only")
@@ -261,6 +473,26 @@ impl Args {
                     .long("no-dotfiles")
                     .help("Hide dot files and directories (those starting with a dot)"),
             )
+            .arg(
+                Arg::with_name("git-ignore")
+                    .long("git-ignore")
+                    .help("Skip files ignored by .gitignore, .ignore, .git/info/exclude and the global gitignore"),
+            )
+            .arg(
+                Arg::with_name("ignore-file")
+                    .long("ignore-file")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .value_name("PATH")
+                    .validator(|value| {
+                        if std::path::Path::new(value).is_file() {
+                            Ok(())
+                        } else {
+                            Err(format!("no such file: {}", value))
+                        }
+                    })
+                    .help("Skip files matching the gitignore-style patterns in PATH (can be repeated)"),
+            )
             .arg(
                 Arg::with_name("dotfiles-only")
                     .long("dotfiles-only")
@@ -422,10 +654,31 @@ impl Args {
                     table_format: config.default_format == "table",
                     grid_format: config.default_format == "grid",
                     sizemap_format: config.default_format == "sizemap",
+                    treemap: config.formatters.sizemap.treemap,
                     timeline_format: config.default_format == "timeline",
+                    timeline_bucket: Self::config_time_bucket(config),
+                    timeline_histogram: config.formatters.timeline.histogram,
+                    timeline_git_dates: config.formatters.timeline.git_dates,
                     git_format: config.default_format == "git",
+                    repos_format: config.default_format == "repos",
                     fuzzy_format: false,
                     recursive_format: false,
+                    json_format: false,
+                    ndjson_format: false,
+                    csv_format: false,
+                    tsv_format: false,
+                    print0: false,
+                    paths_only: false,
+                    columns: None,
+                    format_string: config.formats.get(&config.default_format).cloned(),
+                    time_field: Self::config_time_field(config),
+                    time_style: Self::config_time_style(config),
+                    size_style: config.size.style,
+                    disk_usage: config.size.disk_usage,
+                    dereference: false,
+                    classify: false,
+                    quoting_style: QuotingStyle::default(),
+                    group_by: None,
                     show_icons: config.show_icons,
                     no_color: false,
                     sort_by: config.default_sort.clone(),
@@ -439,6 +692,13 @@ impl Args {
                     disable_plugin: Vec::new(),
                     plugins_dir: config.plugins_dir.clone(),
                     include_dirs: false,
+                    tree_sizes: false,
+                    tree_percent: false,
+                    min_size: None,
+                    tree_compact: config.formatters.tree.compact,
+                    max_children: config.formatters.tree.max_children.filter(|&max| max > 0),
+                    prune: false,
+                    highlight: false,
                     dirs_only: false,
                     files_only: false,
                     symlinks_only: false,
@@ -446,6 +706,8 @@ impl Args {
                     no_files: false,
                     no_symlinks: false,
                     no_dotfiles: config.filter.no_dotfiles,
+                    git_ignore: false,
+                    ignore_files: Vec::new(),
                     dotfiles_only: false,
                     command: Some(Command::Shortcut(ShortcutAction::Run(
                         potential_shortcut.clone(),
@@ -546,10 +808,21 @@ impl Args {
             || matches.is_present("table")
             || matches.is_present("grid")
             || matches.is_present("sizemap")
+            || matches.is_present("treemap")
+            || matches.is_present("bucket")
+            || matches.is_present("git-dates")
             || matches.is_present("timeline")
             || matches.is_present("git")
+            || matches.is_present("repos")
             || matches.is_present("fuzzy")
-            || matches.is_present("recursive");
+            || matches.is_present("recursive")
+            || matches.is_present("json")
+            || matches.is_present("ndjson")
+            || matches.is_present("csv")
+            || matches.is_present("tsv")
+            || matches.is_present("print0")
+            || matches.is_present("paths-only")
+            || matches.is_present("format-string");
 
         Args {
             directory: matches.value_of("directory").unwrap_or(".").to_string(),
@@ -566,14 +839,68 @@ impl Args {
             grid_format: matches.is_present("grid")
                 || (!has_format_flag && config.default_format == "grid"),
             sizemap_format: matches.is_present("sizemap")
+                || matches.is_present("treemap")
                 || (!has_format_flag && config.default_format == "sizemap"),
+            treemap: matches.is_present("treemap") || config.formatters.sizemap.treemap,
             timeline_format: matches.is_present("timeline")
+                || matches.is_present("bucket")
+                || matches.is_present("git-dates")
                 || (!has_format_flag && config.default_format == "timeline"),
+            timeline_bucket: matches
+                .value_of("bucket")
+                .and_then(|value| TimeBucket::parse(value).ok())
+                .unwrap_or_else(|| Self::config_time_bucket(config)),
+            timeline_histogram: !matches.is_present("no-histogram")
+                && config.formatters.timeline.histogram,
+            timeline_git_dates: matches.is_present("git-dates")
+                || config.formatters.timeline.git_dates,
             git_format: matches.is_present("git")
                 || (!has_format_flag && config.default_format == "git"),
+            repos_format: matches.is_present("repos")
+                || (!has_format_flag && config.default_format == "repos"),
             fuzzy_format: matches.is_present("fuzzy"),
             recursive_format: matches.is_present("recursive")
                 || (!has_format_flag && config.default_format == "recursive"),
+            json_format: matches.is_present("json"),
+            ndjson_format: matches.is_present("ndjson"),
+            csv_format: matches.is_present("csv"),
+            tsv_format: matches.is_present("tsv"),
+            print0: matches.is_present("print0"),
+            paths_only: matches.is_present("paths-only"),
+            columns: matches.value_of("columns").map(String::from),
+            format_string: matches
+                .value_of("format-string")
+                .map(String::from)
+                .or_else(|| {
+                    if has_format_flag {
+                        None
+                    } else {
+                        config.formats.get(&config.default_format).cloned()
+                    }
+                }),
+            time_field: matches
+                .value_of("time")
+                .and_then(TimeField::parse)
+                .unwrap_or_else(|| Self::config_time_field(config)),
+            time_style: matches
+                .value_of("time-style")
+                .and_then(|value| TimeStyle::parse(value).ok())
+                .or_else(|| Self::config_time_style(config)),
+            size_style: matches
+                .value_of("size-style")
+                .and_then(SizeStyle::parse)
+                .unwrap_or(config.size.style),
+            disk_usage: matches.is_present("disk-usage")
+                || (!matches.is_present("apparent-size") && config.size.disk_usage),
+            dereference: matches.is_present("dereference"),
+            classify: matches.is_present("classify"),
+            quoting_style: matches
+                .value_of("quoting-style")
+                .and_then(QuotingStyle::parse)
+                .unwrap_or_default(),
+            group_by: matches
+                .value_of("group-by")
+                .and_then(|value| GroupBy::parse(value).ok()),
             show_icons: matches.is_present("icons")
                 || (!matches.is_present("no-icons") && config.show_icons),
             no_color: matches.is_present("no-color"),
@@ -601,6 +928,19 @@ impl Args {
                 .map(PathBuf::from)
                 .unwrap_or_else(|| config.plugins_dir.clone()),
             include_dirs: matches.is_present("include-dirs") || config.include_dirs,
+            tree_sizes: matches.is_present("tree-sizes"),
+            tree_percent: matches.is_present("percent"),
+            min_size: matches
+                .value_of("min-size")
+                .and_then(|value| parse_size(value).ok()),
+            tree_compact: matches.is_present("compact") || config.formatters.tree.compact,
+            max_children: matches
+                .value_of("max-children")
+                .and_then(|value| value.parse().ok())
+                .or(config.formatters.tree.max_children)
+                .filter(|&max| max > 0),
+            prune: matches.is_present("prune"),
+            highlight: matches.is_present("highlight"),
             dirs_only: matches.is_present("dirs-only"),
             files_only: matches.is_present("files-only"),
             symlinks_only: matches.is_present("symlinks-only"),
@@ -608,8 +948,29 @@ impl Args {
             no_files: matches.is_present("no-files"),
             no_symlinks: matches.is_present("no-symlinks"),
             no_dotfiles: matches.is_present("no-dotfiles") || config.filter.no_dotfiles,
+            git_ignore: matches.is_present("git-ignore"),
+            ignore_files: matches
+                .values_of("ignore-file")
+                .map(|v| v.map(PathBuf::from).collect())
+                .unwrap_or_default(),
             dotfiles_only: matches.is_present("dotfiles-only"),
             command,
         }
     }
+
+    fn config_time_field(config: &Config) -> TimeField {
+        TimeField::parse(&config.time.field).unwrap_or_default()
+    }
+
+    fn config_time_style(config: &Config) -> Option<TimeStyle> {
+        config
+            .time
+            .style
+            .as_deref()
+            .and_then(|style| TimeStyle::parse(style).ok())
+    }
+
+    fn config_time_bucket(config: &Config) -> TimeBucket {
+        TimeBucket::parse(&config.formatters.timeline.bucket).unwrap_or_default()
+    }
 }
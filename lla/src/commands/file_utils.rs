@@ -1,23 +1,33 @@
 use crate::commands::args::Args;
 use crate::config::Config;
-use crate::error::Result;
+use crate::error::{LlaError, Result};
 use crate::filter::{
-    CaseInsensitiveFilter, CompositeFilter, ExtensionFilter, FileFilter, FilterOperation,
-    GlobFilter, PatternFilter, RegexFilter,
+    CaseInsensitiveFilter, ExtensionFilter, FieldFilter, FileFilter, FilterExpr, GlobFilter,
+    MetadataFilter, PatternFilter, RegexFilter,
 };
 use crate::formatter::{
-    DefaultFormatter, FileFormatter, FuzzyFormatter, GitFormatter, GridFormatter, LongFormatter,
-    RecursiveFormatter, SizeMapFormatter, TableFormatter, TimelineFormatter, TreeFormatter,
+    Column, CsvFormatter, DefaultFormatter, FileFormatter, FuzzyFormatter, GitColumn, GitFormatter,
+    GridFormatter, GroupedFormatter, JsonFormatter, LongFormatter, PathsFormatter,
+    RecursiveFormatter, ReposFormatter, SizeMapFormatter, TableFormatter, Template,
+    TemplateFormatter, TimelineFormatter, TimelineOptions, TreeFormatter, TreeOptions,
+    FILE_COUNT_FIELD,
+};
+use crate::lister::{
+    is_filtering, walk_builder, BasicLister, FileLister, FuzzyLister, RecursiveLister, RepoLister,
 };
-use crate::lister::{BasicLister, FileLister, FuzzyLister, RecursiveLister};
 use crate::plugin::PluginManager;
-use crate::sorter::{AlphabeticalSorter, DateSorter, FileSorter, SizeSorter, SortOptions};
-use lla_plugin_interface::proto::{DecoratedEntry, EntryMetadata};
+use crate::sorter::{
+    AlphabeticalSorter, DateSorter, FileSorter, MultiSorter, SizeSorter, SortField, SortKey,
+    SortOptions,
+};
+use crate::utils::metadata::convert_metadata;
+use crate::utils::size::{disk_usage, file_size};
+use lla_plugin_interface::proto::DecoratedEntry;
 use rayon::prelude::*;
-use std::os::unix::fs::MetadataExt;
-use std::path::PathBuf;
-use std::sync::Arc;
-use std::time::UNIX_EPOCH;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 pub fn list_directory(
     args: &Args,
@@ -40,18 +50,20 @@ pub fn list_directory(
     }
 
     let lister = create_lister(args);
-    let sorter = create_sorter(args);
-    let filter = create_filter(args);
-    let formatter = create_formatter(args);
+    let sorter = create_sorter(args)?;
+    let filter = create_filter(args)?;
     let format = get_format(args);
 
     let decorated_files = list_and_decorate_files(args, &lister, &filter, plugin_manager, format)?;
 
-    let decorated_files = if !args.tree_format && !args.recursive_format {
-        sort_files(decorated_files, &sorter, args)?
-    } else {
-        decorated_files
-    };
+    let decorated_files = sort_files(decorated_files, &sorter, args)?;
+
+    if args.print0 || args.paths_only {
+        return PathsFormatter::new(args.print0)
+            .write_paths(&decorated_files, &mut std::io::stdout().lock());
+    }
+
+    let formatter = create_formatter(args)?;
 
     let formatted_output =
         formatter.format_files(decorated_files.as_slice(), plugin_manager, args.depth)?;
@@ -62,7 +74,13 @@ pub fn list_directory(
 pub fn get_format(args: &Args) -> &'static str {
     if args.fuzzy_format {
         "fuzzy"
-    } else if args.long_format {
+    } else if args.long_format
+        || args.json_format
+        || args.ndjson_format
+        || args.csv_format
+        || args.tsv_format
+        || args.format_string.is_some()
+    {
         "long"
     } else if args.tree_format {
         "tree"
@@ -77,36 +95,15 @@ pub fn get_format(args: &Args) -> &'static str {
     }
 }
 
-pub fn convert_metadata(metadata: &std::fs::Metadata) -> EntryMetadata {
-    EntryMetadata {
-        size: metadata.len(),
-        modified: metadata
-            .modified()
-            .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())
-            .unwrap_or(0),
-        accessed: metadata
-            .accessed()
-            .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())
-            .unwrap_or(0),
-        created: metadata
-            .created()
-            .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())
-            .unwrap_or(0),
-        is_dir: metadata.is_dir(),
-        is_file: metadata.is_file(),
-        is_symlink: metadata.is_symlink(),
-        permissions: metadata.mode(),
-        uid: metadata.uid(),
-        gid: metadata.gid(),
-    }
-}
-
 fn calculate_dir_size(path: &std::path::Path) -> std::io::Result<u64> {
     use rayon::prelude::*;
 
     if !path.is_dir() {
         return Ok(0);
     }
+    if is_filtering() {
+        return Ok(calculate_filtered_dir_size(path));
+    }
 
     let entries: Vec<_> = std::fs::read_dir(path)?.collect::<std::io::Result<_>>()?;
 
@@ -122,9 +119,9 @@ fn calculate_dir_size(path: &std::path::Path) -> std::io::Result<u64> {
 
                 let path = entry.path();
                 let size = if metadata.is_dir() {
-                    calculate_dir_size(&path)?
+                    calculate_dir_size(&path)? + own_dir_size(&metadata)
                 } else {
-                    metadata.len()
+                    file_size(&metadata)
                 };
 
                 Ok(acc + size)
@@ -133,6 +130,56 @@ fn calculate_dir_size(path: &std::path::Path) -> std::io::Result<u64> {
         .try_reduce(|| 0, |a, b| Ok(a + b))
 }
 
+/// Like `calculate_dir_size`, but walks with the active ignore rules so that
+/// excluded files and directories don't count towards the total.
+fn calculate_filtered_dir_size(path: &std::path::Path) -> u64 {
+    let total = AtomicU64::new(0);
+    walk_builder(path)
+        .follow_links(false)
+        .build_parallel()
+        .run(|| {
+            let total = &total;
+            Box::new(move |entry| {
+                if let Ok(entry) = entry {
+                    if let Ok(metadata) = entry.metadata() {
+                        let size = if entry.depth() == 0 || metadata.is_symlink() {
+                            0
+                        } else if metadata.is_dir() {
+                            own_dir_size(&metadata)
+                        } else {
+                            file_size(&metadata)
+                        };
+                        total.fetch_add(size, Ordering::Relaxed);
+                    }
+                }
+                ignore::WalkState::Continue
+            })
+        });
+    total.into_inner()
+}
+
+/// Directories only count towards a total when measuring disk usage, like `du`.
+fn own_dir_size(metadata: &std::fs::Metadata) -> u64 {
+    if disk_usage() {
+        file_size(metadata)
+    } else {
+        0
+    }
+}
+
+/// Whether the view shows directory totals, as the tree view with sizes and
+/// the treemap do. Those cover the whole subtree of every directory rather
+/// than just the levels being displayed.
+fn rolls_up_dir_sizes(args: &Args) -> bool {
+    let tree_sizes =
+        args.tree_format && (args.tree_sizes || args.tree_percent || args.min_size.is_some());
+    tree_sizes || (args.sizemap_format && args.treemap)
+}
+
+fn lists_recursively(args: &Args) -> bool {
+    args.tree_format || args.recursive_format || (args.sizemap_format && args.treemap)
+}
+
 pub fn list_and_decorate_files(
     args: &Args,
     lister: &Arc<dyn FileLister + Send + Sync>,
@@ -140,21 +187,23 @@ pub fn list_and_decorate_files(
     plugin_manager: &mut PluginManager,
     format: &str,
 ) -> Result<Vec<DecoratedEntry>> {
+    let filter_after_decoration = filter.needs_decoration();
+    let filter_as_tree = args.tree_format && args.filter.is_some();
     let mut entries: Vec<DecoratedEntry> = lister
-        .list_files(
-            &args.directory,
-            args.tree_format || args.recursive_format,
-            args.depth,
-        )?
+        .list_files(&args.directory, lists_recursively(args), args.depth)?
         .into_par_iter()
         .filter_map(|path| {
-            let fs_metadata = path.metadata().ok()?;
+            let fs_metadata = if args.dereference {
+                path.metadata().or_else(|_| path.symlink_metadata())
+            } else {
+                path.symlink_metadata()
+            }
+            .ok()?;
             let mut metadata = convert_metadata(&fs_metadata);
 
             let is_dotfile = path
                 .file_name()
-                .and_then(|n| n.to_str())
-                .map(|n| n.starts_with('.'))
+                .map(|n| n.to_string_lossy().starts_with('.'))
                 .unwrap_or(false);
 
             if args.dotfiles_only && !is_dotfile {
@@ -173,35 +222,30 @@ pub fn list_and_decorate_files(
                 let include_dirs = !args.no_dirs;
                 let include_files = !args.no_files;
                 let include_symlinks = !args.no_symlinks;
+                let is_special = !metadata.is_dir && !metadata.is_file && !metadata.is_symlink;
 
                 (metadata.is_dir && include_dirs)
                     || (metadata.is_file && include_files)
                     || (metadata.is_symlink && include_symlinks)
+                    || is_special
             };
 
             if !should_include {
                 return None;
             }
 
-            if args.include_dirs && metadata.is_dir {
+            if args.include_dirs && metadata.is_dir && !rolls_up_dir_sizes(args) {
                 if let Ok(dir_size) = calculate_dir_size(&path) {
-                    metadata.size = dir_size;
+                    metadata.size = dir_size + own_dir_size(&fs_metadata);
                 }
             }
 
-            if !filter
-                .filter_files(std::slice::from_ref(&path))
-                .map(|v| !v.is_empty())
-                .unwrap_or(false)
-            {
+            let entry = DecoratedEntry::new(&path, Some(metadata), Default::default());
+            if !filter_after_decoration && !filter_as_tree && !matches_filter(filter, &entry) {
                 return None;
             }
 
-            Some(DecoratedEntry {
-                path: path.to_string_lossy().into_owned(),
-                metadata: Some(metadata),
-                custom_fields: Default::default(),
-            })
+            Some(entry)
         })
         .collect();
 
@@ -209,27 +253,75 @@ pub fn list_and_decorate_files(
         plugin_manager.decorate_entry(entry, format);
     }
 
+    if filter_as_tree {
+        retain_tree_matches(&mut entries, filter, args.prune);
+    } else if filter_after_decoration {
+        entries.retain(|entry| matches_filter(filter, entry));
+    }
+
     Ok(entries)
 }
 
-pub fn sort_files(
-    files: Vec<DecoratedEntry>,
-    sorter: &Arc<dyn FileSorter + Send + Sync>,
-    args: &Args,
-) -> Result<Vec<DecoratedEntry>> {
-    let mut entries_with_paths: Vec<(PathBuf, &DecoratedEntry)> = files
-        .iter()
-        .map(|entry| (PathBuf::from(&entry.path), entry))
+/// Keeps the entries that match together with every directory above them, so
+/// matches keep their place in the tree. Other directories are kept as well
+/// unless `prune` is set.
+fn retain_tree_matches(
+    entries: &mut Vec<DecoratedEntry>,
+    filter: &Arc<dyn FileFilter + Send + Sync>,
+    prune: bool,
+) {
+    let matched: Vec<bool> = entries
+        .par_iter()
+        .map(|entry| matches_filter(filter, entry))
         .collect();
 
-    let options = SortOptions {
+    let mut keep: HashSet<PathBuf> = HashSet::new();
+    for (entry, _) in entries.iter().zip(&matched).filter(|(_, &m)| m) {
+        for ancestor in entry.fs_path().ancestors() {
+            if !keep.insert(ancestor.to_path_buf()) {
+                break;
+            }
+        }
+    }
+
+    entries.retain(|entry| {
+        let is_dir = entry.metadata.as_ref().map_or(false, |m| m.is_dir);
+        keep.contains(&entry.fs_path()) || (is_dir && !prune)
+    });
+}
+
+fn matches_filter(filter: &Arc<dyn FileFilter + Send + Sync>, entry: &DecoratedEntry) -> bool {
+    filter
+        .filter_entries(std::slice::from_ref(entry))
+        .map(|v| !v.is_empty())
+        .unwrap_or(false)
+}
+
+fn sort_options(args: &Args) -> SortOptions {
+    SortOptions {
         reverse: args.sort_reverse,
         dirs_first: args.sort_dirs_first,
         case_sensitive: args.sort_case_sensitive,
         natural: args.sort_natural,
-    };
+    }
+}
+
+pub fn sort_files(
+    mut files: Vec<DecoratedEntry>,
+    sorter: &Arc<dyn FileSorter + Send + Sync>,
+    args: &Args,
+) -> Result<Vec<DecoratedEntry>> {
+    if args.tree_format || args.recursive_format {
+        return sort_tree_files(files, sorter, args);
+    }
+    if rolls_up_dir_sizes(args) {
+        aggregate_dir_sizes(&mut files, Path::new(&args.directory), args.depth);
+    }
 
-    sorter.sort_files_with_metadata(&mut entries_with_paths, options)?;
+    let mut entries_with_paths: Vec<(PathBuf, &DecoratedEntry)> =
+        files.iter().map(|entry| (entry.fs_path(), entry)).collect();
+
+    sorter.sort_files_with_metadata(&mut entries_with_paths, sort_options(args))?;
 
     let sorted_files = entries_with_paths
         .into_iter()
@@ -240,11 +332,167 @@ pub fn sort_files(
     Ok(sorted_files)
 }
 
+/// Orders a recursive listing for the tree and recursive views: the children
+/// of each directory are sorted among themselves and follow their directory.
+fn sort_tree_files(
+    mut files: Vec<DecoratedEntry>,
+    sorter: &Arc<dyn FileSorter + Send + Sync>,
+    args: &Args,
+) -> Result<Vec<DecoratedEntry>> {
+    let sorts_by_size = SortKey::parse_list(&args.sort_by).map_or(false, |keys| {
+        keys.iter().any(|key| key.field == SortField::Size)
+    });
+    if rolls_up_dir_sizes(args) || (sorts_by_size && !args.include_dirs) {
+        aggregate_dir_sizes(&mut files, Path::new(&args.directory), args.depth);
+    }
+
+    let paths: Vec<PathBuf> = files.iter().map(|entry| entry.fs_path()).collect();
+    let index: HashMap<&Path, usize> = paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| (path.as_path(), i))
+        .collect();
+
+    let mut roots = Vec::new();
+    let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, path) in paths.iter().enumerate() {
+        match path.parent().and_then(|parent| index.get(parent)) {
+            Some(&parent) if parent != i => children.entry(parent).or_default().push(i),
+            _ => roots.push(i),
+        }
+    }
+
+    let options = sort_options(args);
+    let sort_level = |level: Vec<usize>| -> Result<Vec<usize>> {
+        let mut entries: Vec<(PathBuf, &DecoratedEntry)> = level
+            .iter()
+            .map(|&i| (paths[i].clone(), &files[i]))
+            .collect();
+        sorter.sort_files_with_metadata(&mut entries, options)?;
+        Ok(entries
+            .iter()
+            .map(|(path, _)| index[path.as_path()])
+            .collect())
+    };
+
+    let mut order = Vec::with_capacity(files.len());
+    let mut stack: Vec<usize> = sort_level(roots)?.into_iter().rev().collect();
+    while let Some(i) = stack.pop() {
+        order.push(i);
+        if let Some(level) = children.remove(&i) {
+            stack.extend(sort_level(level)?.into_iter().rev());
+        }
+    }
+
+    let mut files: Vec<Option<DecoratedEntry>> = files.into_iter().map(Some).collect();
+    Ok(order.into_iter().filter_map(|i| files[i].take()).collect())
+}
+
+/// Sets the size of every listed directory to the total of its subtree and
+/// records how many files it holds. The totals come from `measure_dir_totals`
+/// rather than from the listing itself, which stops at `--depth` and at
+/// `listers.recursive.max_entries`.
+fn aggregate_dir_sizes(files: &mut [DecoratedEntry], root: &Path, depth: Option<usize>) {
+    let totals = measure_dir_totals(root, depth);
+    for entry in files.iter_mut() {
+        let path = entry.fs_path();
+        let metadata = match entry.metadata.as_mut() {
+            Some(metadata) if metadata.is_dir && !metadata.is_symlink => metadata,
+            _ => continue,
+        };
+        let total = totals.get(&path).copied().unwrap_or_default();
+        let own_size = if disk_usage() { metadata.size } else { 0 };
+        metadata.size = own_size + total.size;
+        entry
+            .custom_fields
+            .insert(FILE_COUNT_FIELD.to_string(), total.files.to_string());
+    }
+}
+
+/// The size of everything beneath a directory and the number of files in it.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct DirTotals {
+    size: u64,
+    files: u64,
+}
+
+/// Walks `root` once, reading only metadata, and totals the subtree of every
+/// directory up to `depth` levels below it. Files count the way
+/// `calculate_dir_size` counts them, and the active ignore rules apply.
+fn measure_dir_totals(root: &Path, depth: Option<usize>) -> HashMap<PathBuf, DirTotals> {
+    /// Totals gathered by one walker thread, merged when the thread is done.
+    struct Tally<'a> {
+        totals: HashMap<PathBuf, DirTotals>,
+        merged: &'a Mutex<HashMap<PathBuf, DirTotals>>,
+    }
+
+    impl Drop for Tally<'_> {
+        fn drop(&mut self) {
+            let mut merged = self.merged.lock().unwrap_or_else(|e| e.into_inner());
+            for (path, total) in self.totals.drain() {
+                let merged = merged.entry(path).or_default();
+                merged.size += total.size;
+                merged.files += total.files;
+            }
+        }
+    }
+
+    let merged = Mutex::new(HashMap::new());
+    walk_builder(root)
+        .follow_links(false)
+        .build_parallel()
+        .run(|| {
+            let mut tally = Tally {
+                totals: HashMap::new(),
+                merged: &merged,
+            };
+            Box::new(move |entry| {
+                let entry = match entry {
+                    Ok(entry) if entry.depth() > 0 => entry,
+                    _ => return ignore::WalkState::Continue,
+                };
+                let metadata = match entry.metadata() {
+                    Ok(metadata) if !metadata.is_symlink() => metadata,
+                    _ => return ignore::WalkState::Continue,
+                };
+                let total = if metadata.is_dir() {
+                    DirTotals {
+                        size: own_dir_size(&metadata),
+                        files: 0,
+                    }
+                } else {
+                    DirTotals {
+                        size: file_size(&metadata),
+                        files: metadata.is_file() as u64,
+                    }
+                };
+
+                // Only directories that can be listed need a total.
+                let levels = entry.depth();
+                let skipped = depth.map_or(0, |depth| levels.saturating_sub(depth + 1));
+                for ancestor in entry
+                    .path()
+                    .ancestors()
+                    .skip(1 + skipped)
+                    .take(levels - skipped)
+                {
+                    let sum = tally.totals.entry(ancestor.to_path_buf()).or_default();
+                    sum.size += total.size;
+                    sum.files += total.files;
+                }
+                ignore::WalkState::Continue
+            })
+        });
+    merged.into_inner().unwrap_or_else(|e| e.into_inner())
+}
+
 pub fn create_lister(args: &Args) -> Arc<dyn FileLister + Send + Sync> {
     if args.fuzzy_format {
         let config = Config::load(&Config::get_config_path()).unwrap_or_default();
         Arc::new(FuzzyLister::new(config))
-    } else if args.tree_format || args.recursive_format {
+    } else if args.repos_format {
+        Arc::new(RepoLister)
+    } else if lists_recursively(args) {
         let config = Config::load(&Config::get_config_path()).unwrap_or_default();
         Arc::new(RecursiveLister::new(config))
     } else {
@@ -252,50 +500,52 @@ pub fn create_lister(args: &Args) -> Arc<dyn FileLister + Send + Sync> {
     }
 }
 
-pub fn create_sorter(args: &Args) -> Arc<dyn FileSorter + Send + Sync> {
+pub fn create_sorter(args: &Args) -> Result<Arc<dyn FileSorter + Send + Sync>> {
     let sorter: Arc<dyn FileSorter + Send + Sync> = match args.sort_by.as_str() {
         "name" => Arc::new(AlphabeticalSorter),
         "size" => Arc::new(SizeSorter),
         "date" => Arc::new(DateSorter),
-        _ => Arc::new(AlphabeticalSorter),
+        keys => {
+            let keys = SortKey::parse_list(keys)
+                .map_err(|e| LlaError::Parse(format!("invalid sort '{}': {}", keys, e)))?;
+            Arc::new(MultiSorter::new(keys))
+        }
     };
 
-    sorter
+    Ok(sorter)
 }
 
-pub fn create_filter(args: &Args) -> Arc<dyn FileFilter + Send + Sync> {
+pub fn create_filter(args: &Args) -> Result<Arc<dyn FileFilter + Send + Sync>> {
     match &args.filter {
         Some(filter_str) => {
-            if filter_str.contains(" AND ") {
-                let mut composite = CompositeFilter::new(FilterOperation::And);
-                for part in filter_str.split(" AND ") {
-                    composite.add_filter(create_base_filter(part.trim(), !args.case_sensitive));
-                }
-                Arc::new(composite)
-            } else if filter_str.contains(" OR ") {
-                let mut composite = CompositeFilter::new(FilterOperation::Or);
-                for part in filter_str.split(" OR ") {
-                    composite.add_filter(create_base_filter(part.trim(), !args.case_sensitive));
-                }
-                Arc::new(composite)
-            } else if filter_str.starts_with("NOT ") {
-                let mut composite = CompositeFilter::new(FilterOperation::Not);
-                composite.add_filter(create_base_filter(&filter_str[4..], !args.case_sensitive));
-                Arc::new(composite)
-            } else if filter_str.starts_with("XOR ") {
-                let mut composite = CompositeFilter::new(FilterOperation::Xor);
-                composite.add_filter(create_base_filter(&filter_str[4..], !args.case_sensitive));
-                Arc::new(composite)
-            } else {
-                Arc::from(create_base_filter(filter_str, !args.case_sensitive))
-            }
+            let make_term =
+                |term: &str, quoted: bool| create_base_filter(term, quoted, !args.case_sensitive);
+            let filter = FilterExpr::parse(filter_str)?.into_filter(filter_str, &make_term)?;
+            Ok(Arc::from(filter))
         }
-        None => Arc::new(PatternFilter::new("".to_string())),
+        None => Ok(Arc::new(PatternFilter::new("".to_string()))),
     }
 }
 
-fn create_base_filter(pattern: &str, case_insensitive: bool) -> Box<dyn FileFilter + Send + Sync> {
-    let base_filter: Box<dyn FileFilter + Send + Sync> = if pattern.starts_with("regex:") {
+/// Builds the filter for one term of a filter expression. A quoted term is
+/// always a plain name pattern, whatever prefix it starts with.
+fn create_base_filter(
+    pattern: &str,
+    quoted: bool,
+    case_insensitive: bool,
+) -> std::result::Result<Box<dyn FileFilter + Send + Sync>, String> {
+    if !quoted {
+        if let Some(filter) = MetadataFilter::parse(pattern) {
+            return Ok(Box::new(filter?));
+        }
+        if let Some(filter) = FieldFilter::parse(pattern, case_insensitive) {
+            return Ok(Box::new(filter?));
+        }
+    }
+
+    let base_filter: Box<dyn FileFilter + Send + Sync> = if quoted {
+        Box::new(PatternFilter::new(pattern.to_string()))
+    } else if pattern.starts_with("regex:") {
         Box::new(RegexFilter::new(pattern[6..].to_string()))
     } else if pattern.starts_with("glob:") {
         Box::new(GlobFilter::new(pattern[5..].to_string()))
@@ -306,32 +556,217 @@ fn create_base_filter(pattern: &str, case_insensitive: bool) -> Box<dyn FileFilt
     };
 
     if case_insensitive {
-        Box::new(CaseInsensitiveFilter::new(base_filter))
+        Ok(Box::new(CaseInsensitiveFilter::new(base_filter)))
     } else {
-        base_filter
+        Ok(base_filter)
     }
 }
 
-pub fn create_formatter(args: &Args) -> Box<dyn FileFormatter> {
-    if args.fuzzy_format {
+pub fn create_formatter(args: &Args) -> Result<Box<dyn FileFormatter>> {
+    let columns = |configured: &[String]| {
+        match args.columns.as_deref() {
+            Some(spec) => Column::parse_list(spec).map(Some),
+            None => Column::from_names(configured),
+        }
+        .map_err(LlaError::Parse)
+    };
+    let formatter: Box<dyn FileFormatter> = if let Some(template) = &args.format_string {
+        Box::new(TemplateFormatter::new(
+            args.show_icons,
+            Template::parse(template)?,
+        ))
+    } else if args.json_format || args.ndjson_format {
+        Box::new(JsonFormatter::new(args.ndjson_format))
+    } else if args.csv_format || args.tsv_format {
+        let delimiter = if args.tsv_format { '\t' } else { ',' };
+        Box::new(CsvFormatter::new(delimiter, columns(&[])?))
+    } else if args.fuzzy_format {
         Box::new(FuzzyFormatter::new(args.show_icons))
     } else if args.long_format {
-        Box::new(LongFormatter::new(args.show_icons))
+        let config = Config::load(&Config::get_config_path()).unwrap_or_default();
+        let columns = columns(&config.formatters.long.columns)?;
+        Box::new(LongFormatter::new(args.show_icons, columns))
     } else if args.tree_format {
-        Box::new(TreeFormatter::new(args.show_icons))
+        let tree = TreeFormatter::new(
+            args.show_icons,
+            TreeOptions {
+                sizes: args.tree_sizes,
+                min_size: args.min_size,
+                percent: args.tree_percent,
+                compact: args.tree_compact,
+                max_children: args.max_children,
+            },
+        );
+        if args.highlight && args.filter.is_some() {
+            Box::new(tree.with_highlight(create_filter(args)?))
+        } else {
+            Box::new(tree)
+        }
     } else if args.table_format {
-        Box::new(TableFormatter::new(args.show_icons))
+        let config = Config::load(&Config::get_config_path()).unwrap_or_default();
+        let columns = columns(&config.formatters.table.columns)?;
+        Box::new(TableFormatter::new(args.show_icons, columns))
     } else if args.grid_format {
         Box::new(GridFormatter::new(args.show_icons))
     } else if args.sizemap_format {
-        Box::new(SizeMapFormatter::new(args.show_icons))
+        Box::new(SizeMapFormatter::new(args.show_icons, args.treemap))
     } else if args.timeline_format {
-        Box::new(TimelineFormatter::new(args.show_icons))
+        Box::new(TimelineFormatter::new(
+            args.show_icons,
+            TimelineOptions {
+                bucket: args.timeline_bucket.clone(),
+                histogram: args.timeline_histogram,
+                git_dates: args.timeline_git_dates,
+            },
+        ))
     } else if args.git_format {
-        Box::new(GitFormatter::new(args.show_icons))
+        let config = Config::load(&Config::get_config_path()).unwrap_or_default();
+        let columns = match args.columns.as_deref() {
+            Some(spec) => GitColumn::parse_list(spec).map(Some),
+            None => GitColumn::from_names(&config.formatters.git.columns),
+        }
+        .map_err(LlaError::Parse)?;
+        Box::new(GitFormatter::new(args.show_icons, columns))
+    } else if args.repos_format {
+        Box::new(ReposFormatter)
     } else if args.recursive_format {
         Box::new(RecursiveFormatter::new(args.show_icons))
     } else {
         Box::new(DefaultFormatter::new(args.show_icons))
+    };
+
+    let groupable = !(args.format_string.is_some()
+        || args.json_format
+        || args.ndjson_format
+        || args.csv_format
+        || args.tsv_format
+        || args.fuzzy_format
+        || args.tree_format
+        || args.sizemap_format
+        || args.timeline_format
+        || args.git_format
+        || args.repos_format
+        || args.recursive_format);
+    let formatter = match &args.group_by {
+        Some(group_by) if groupable => Box::new(GroupedFormatter::new(formatter, group_by.clone())),
+        _ => formatter,
+    };
+
+    Ok(formatter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn entry(path: &Path) -> DecoratedEntry {
+        let metadata = fs::symlink_metadata(path).unwrap();
+        DecoratedEntry::new(path, Some(convert_metadata(&metadata)), Default::default())
+    }
+
+    /// `root/a/b/c.txt` (30 bytes), `root/a/x.txt` (10), `root/y.txt` (5)
+    /// and `root/.z` (1).
+    fn sample_tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("a/b/c.txt"), [0; 30]).unwrap();
+        fs::write(root.join("a/x.txt"), [0; 10]).unwrap();
+        fs::write(root.join("y.txt"), [0; 5]).unwrap();
+        fs::write(root.join(".z"), [0; 1]).unwrap();
+        dir
+    }
+
+    fn totals(files: &[DecoratedEntry]) -> Vec<(u64, Option<&str>)> {
+        files
+            .iter()
+            .map(|entry| {
+                let size = entry.metadata.as_ref().unwrap().size;
+                let count = entry.custom_fields.get(FILE_COUNT_FIELD);
+                (size, count.map(String::as_str))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn measures_whole_subtrees_up_to_the_listing_depth() {
+        let dir = sample_tree();
+        let root = dir.path();
+
+        let totals = measure_dir_totals(root, Some(1));
+        assert_eq!(totals[root], DirTotals { size: 46, files: 4 });
+        assert_eq!(totals[&root.join("a")], DirTotals { size: 40, files: 2 });
+        assert!(!totals.contains_key(&root.join("a/b")));
+
+        let totals = measure_dir_totals(root, None);
+        assert_eq!(totals[&root.join("a/b")], DirTotals { size: 30, files: 1 });
+    }
+
+    #[test]
+    fn rolls_up_sizes_past_the_depth_limit() {
+        let dir = sample_tree();
+        let root = dir.path();
+        let mut files = vec![
+            entry(root),
+            entry(&root.join("a")),
+            entry(&root.join("y.txt")),
+        ];
+
+        aggregate_dir_sizes(&mut files, root, Some(1));
+        assert_eq!(
+            totals(&files),
+            [(46, Some("4")), (40, Some("2")), (5, None)]
+        );
+    }
+
+    #[test]
+    fn rolls_up_sizes_of_a_truncated_listing() {
+        let dir = sample_tree();
+        let root = dir.path();
+        let mut files = vec![
+            entry(root),
+            entry(&root.join("a")),
+            entry(&root.join("a/b")),
+        ];
+
+        aggregate_dir_sizes(&mut files, root, None);
+        assert_eq!(
+            totals(&files),
+            [(46, Some("4")), (40, Some("2")), (30, Some("1"))]
+        );
+    }
+
+    fn listed(path: &str, is_dir: bool) -> DecoratedEntry {
+        let metadata = lla_plugin_interface::proto::EntryMetadata {
+            is_dir,
+            is_file: !is_dir,
+            ..Default::default()
+        };
+        DecoratedEntry::new(Path::new(path), Some(metadata), Default::default())
+    }
+
+    fn retained(prune: bool) -> Vec<String> {
+        let mut entries = vec![
+            listed("a", true),
+            listed("a/b", true),
+            listed("a/b/c.rs", false),
+            listed("a/d", true),
+            listed("a/d/e.txt", false),
+        ];
+        let filter: Arc<dyn FileFilter + Send + Sync> =
+            Arc::new(PatternFilter::new("c.rs".to_string()));
+        retain_tree_matches(&mut entries, &filter, prune);
+        entries.into_iter().map(|entry| entry.path).collect()
+    }
+
+    #[test]
+    fn keeps_matches_with_their_ancestors_and_other_directories() {
+        assert_eq!(retained(false), ["a", "a/b", "a/b/c.rs", "a/d"]);
+    }
+
+    #[test]
+    fn prunes_directories_without_matches() {
+        assert_eq!(retained(true), ["a", "a/b", "a/b/c.rs"]);
     }
 }
@@ -1,15 +1,28 @@
 use crate::commands::args::ConfigAction;
 use crate::error::{ConfigErrorKind, LlaError, Result};
+use crate::formatter::{Column, GitColumn, TimeBucket};
+use crate::lister::validate_exclude_pattern;
+use crate::sorter::SortKey;
 use crate::theme::{load_theme, Theme};
+use crate::utils::time::{TimeField, TimeStyle};
+use lla_plugin_interface::size::SizeStyle;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+const VALID_FORMATS: [&str; 10] = [
+    "default", "long", "tree", "grid", "git", "repos", "timeline", "sizemap", "table", "fuzzy",
+];
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TreeFormatterConfig {
     #[serde(default)]
     pub max_lines: Option<usize>,
+    #[serde(default)]
+    pub compact: bool,
+    #[serde(default)]
+    pub max_children: Option<usize>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -30,12 +43,63 @@ impl Default for TreeFormatterConfig {
     fn default() -> Self {
         Self {
             max_lines: Some(20_000),
+            compact: false,
+            max_children: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SizeMapConfig {
+    #[serde(default)]
+    pub treemap: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimelineConfig {
+    #[serde(default = "default_timeline_bucket")]
+    pub bucket: String,
+    #[serde(default = "default_true")]
+    pub histogram: bool,
+    #[serde(default)]
+    pub git_dates: bool,
+}
+
+impl Default for TimelineConfig {
+    fn default() -> Self {
+        Self {
+            bucket: default_timeline_bucket(),
+            histogram: true,
+            git_dates: false,
         }
     }
 }
 
+fn default_timeline_bucket() -> String {
+    String::from("relative")
+}
+
+fn default_true() -> bool {
+    true
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct SizeMapConfig {}
+pub struct LongFormatterConfig {
+    #[serde(default)]
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TableFormatterConfig {
+    #[serde(default)]
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct GitFormatterConfig {
+    #[serde(default)]
+    pub columns: Vec<String>,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct FormatterConfig {
@@ -43,6 +107,14 @@ pub struct FormatterConfig {
     pub tree: TreeFormatterConfig,
     #[serde(default)]
     pub sizemap: SizeMapConfig,
+    #[serde(default)]
+    pub timeline: TimelineConfig,
+    #[serde(default)]
+    pub long: LongFormatterConfig,
+    #[serde(default)]
+    pub table: TableFormatterConfig,
+    #[serde(default)]
+    pub git: GitFormatterConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -103,6 +175,37 @@ pub struct FilterConfig {
     pub case_sensitive: bool,
     #[serde(default)]
     pub no_dotfiles: bool,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimeConfig {
+    #[serde(default = "default_time_field")]
+    pub field: String,
+    #[serde(default)]
+    pub style: Option<String>,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        Self {
+            field: default_time_field(),
+            style: None,
+        }
+    }
+}
+
+fn default_time_field() -> String {
+    String::from("modified")
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SizeConfig {
+    #[serde(default)]
+    pub style: SizeStyle,
+    #[serde(default)]
+    pub disk_usage: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -121,11 +224,17 @@ pub struct Config {
     #[serde(default)]
     pub filter: FilterConfig,
     #[serde(default)]
+    pub time: TimeConfig,
+    #[serde(default)]
+    pub size: SizeConfig,
+    #[serde(default)]
     pub formatters: FormatterConfig,
     #[serde(default)]
     pub listers: ListerConfig,
     #[serde(default)]
     pub shortcuts: HashMap<String, ShortcutCommand>,
+    #[serde(default)]
+    pub formats: HashMap<String, String>,
     #[serde(default = "default_theme_name")]
     pub theme: String,
 }
@@ -175,6 +284,9 @@ impl Config {
 #   - "name": Sort alphabetically by filename (default)
 #   - "size": Sort by file size, largest first
 #   - "date": Sort by modification time, newest first
+# Several comma-separated keys sort by each in turn, e.g. "ext,size:desc,name".
+# Other keys: extension, type, version, depth, owner, group, git, modified,
+# accessed, created, changed and field:NAME for plugin fields.
 default_sort = "{}"
 
 # Default format for displaying files
@@ -185,9 +297,11 @@ default_sort = "{}"
 #   - "fuzzy": Interactive fuzzy search
 #   - "grid": Organized grid layout for better readability
 #   - "git": Git-aware view with repository status
+#   - "repos": One status row per git repository found under the directory
 #   - "timeline": Group files by time periods
 #   - "sizemap": Visual representation of file sizes
 #   - "table": Structured data display
+#   - any format name defined in the [formats] section
 default_format = "{}"
 
 # Whether to show icons by default
@@ -248,6 +362,44 @@ case_sensitive = {}
 # Default: false
 no_dotfiles = {}
 
+# Gitignore-style patterns to skip in every listing and in directory sizes
+# Example: ["target", "node_modules", "*.log"]
+# Default: []
+exclude = {}
+
+# Timestamp configuration
+[time]
+# Which timestamp to display and sort by
+# Possible values: "modified", "accessed", "created", "changed"
+# Default: "modified"
+field = "{}"
+
+# How timestamps are rendered
+# Possible values:
+#   - "relative": Human-friendly relative times (e.g., "3 hours ago")
+#   - "iso": ISO 8601 with timezone (e.g., 2024-01-31T14:05:00+01:00)
+#   - "long-iso": Date and time to the minute (e.g., 2024-01-31 14:05)
+#   - "full": Full date, time and timezone offset
+#   - "+FORMAT": Custom strftime format (e.g., "+%d/%m/%Y")
+# Leave empty to use each view's own default style
+style = "{}"
+
+# Size configuration
+[size]
+# How file sizes are rendered
+# Possible values:
+#   - "iec": Powers of 1024 (e.g., 4.0K, 1.5M)
+#   - "si": Powers of 1000 (e.g., 4.1k, 1.6M)
+#   - "bytes": Exact byte count
+#   - "blocks": Number of 1K blocks, like du -k
+# Default: "iec"
+style = "{}"
+
+# Report the space allocated on disk instead of the file length
+# Sizes then match du; use --apparent-size to override
+# Default: false
+disk_usage = {}
+
 # Formatter-specific configurations
 [formatters.tree]
 # Maximum number of entries to display in tree view
@@ -256,6 +408,64 @@ no_dotfiles = {}
 # Default: 20000 entries
 max_lines = {}
 
+# Render chains of single-directory directories (src/main/java) as one node
+# Default: false
+compact = {}
+
+# Maximum number of entries to show per directory before "… N more entries"
+# Set to 0 to show all entries
+# Default: 0
+max_children = {}
+
+[formatters.sizemap]
+# Draw the size map as a treemap of nested rectangles instead of bars
+# Default: false
+treemap = {}
+
+[formatters.timeline]
+# How to split the timeline into sections
+# Possible values:
+#   - "relative": Today, Yesterday, Last Week, Last Month, Older
+#   - "hour", "day", "week", "month", "year": One section per calendar period
+#   - A list of ages such as "15m,1h,6h,1d": Last 15m, 15m – 1h ago, ...
+# Default: "relative"
+bucket = "{}"
+
+# Show a histogram of activity per section above the timeline
+# Default: true
+histogram = {}
+
+# Place files by the date of their last git commit instead of their file time
+# Default: false
+git_dates = {}
+
+[formatters.long]
+# Columns to display in long view, in order
+# Available columns: permissions, size, time, modified, accessed, created,
+#   changed, user, group, uid, gid, mode, inode, nlink, dev, rdev, blocks,
+#   blksize, type, path, name, plugins
+# Plugin fields take a "field:" prefix (e.g., "field:git_status")
+# Empty list uses the default layout
+# Default: []
+columns = {}
+
+[formatters.table]
+# Columns to display in table view, in order (same names as formatters.long)
+# Empty list uses the default layout
+# Default: []
+columns = {}
+
+[formatters.git]
+# Columns to display after the name in the git view, in order
+# Available columns: commit, author, date, churn (number of commits touching
+#   the path), lines (lines added and removed in the working tree), status
+# Plugin fields take a "field:" prefix
+# Directories show the latest commit, churn and line changes of their subtree
+# Empty list uses commit, date, author, status; churn and lines are opt-in,
+#   and churn reads the whole history of HEAD
+# Default: []
+columns = {}
+
 # Lister-specific configurations
 [listers.recursive]
 # Maximum number of entries to process in recursive listing
@@ -289,11 +499,40 @@ ignore_patterns = {}"#,
             self.sort.natural,
             self.filter.case_sensitive,
             self.filter.no_dotfiles,
+            serde_json::to_string(&self.filter.exclude).unwrap(),
+            self.time.field,
+            self.time.style.as_deref().unwrap_or(""),
+            self.size.style.as_str(),
+            self.size.disk_usage,
             self.formatters.tree.max_lines.unwrap_or(0),
+            self.formatters.tree.compact,
+            self.formatters.tree.max_children.unwrap_or(0),
+            self.formatters.sizemap.treemap,
+            self.formatters.timeline.bucket,
+            self.formatters.timeline.histogram,
+            self.formatters.timeline.git_dates,
+            serde_json::to_string(&self.formatters.long.columns).unwrap(),
+            serde_json::to_string(&self.formatters.table.columns).unwrap(),
+            serde_json::to_string(&self.formatters.git.columns).unwrap(),
             self.listers.recursive.max_entries.unwrap_or(0),
             serde_json::to_string(&self.listers.fuzzy.ignore_patterns).unwrap(),
         );
 
+        if !self.formats.is_empty() {
+            content.push_str("\n\n# Custom output formats\n");
+            content.push_str("# Named format strings that can be selected with default_format\n");
+            content.push_str("[formats]\n");
+            let mut formats: Vec<_> = self.formats.iter().collect();
+            formats.sort();
+            for (name, template) in formats {
+                content.push_str(&format!(
+                    "{} = {}\n",
+                    name,
+                    serde_json::to_string(template).unwrap()
+                ));
+            }
+        }
+
         if !self.shortcuts.is_empty() {
             content.push_str("\n\n# Command shortcuts\n");
             content.push_str("# Define custom shortcuts for frequently used plugin commands\n");
@@ -392,30 +631,61 @@ ignore_patterns = {}"#,
     }
 
     pub fn validate(&self) -> Result<()> {
-        if !["name", "size", "date"].contains(&self.default_sort.as_str()) {
+        if let Err(e) = SortKey::parse_list(&self.default_sort) {
             return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
                 "default_sort".to_string(),
-                format!(
-                    "Invalid sort value: {}. Must be one of: name, size, date",
-                    self.default_sort
-                ),
+                format!("Invalid sort value: {}. {}", self.default_sort, e),
             )));
         }
 
-        let valid_formats = [
-            "default", "long", "tree", "grid", "git", "timeline", "sizemap", "table", "fuzzy",
-        ];
-        if !valid_formats.contains(&self.default_format.as_str()) {
+        if !VALID_FORMATS.contains(&self.default_format.as_str())
+            && !self.formats.contains_key(&self.default_format)
+        {
             return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
                 "default_format".to_string(),
                 format!(
-                    "Invalid format value: {}. Must be one of: {}",
+                    "Invalid format value: {}. Must be one of: {} or a name from [formats]",
                     self.default_format,
-                    valid_formats.join(", ")
+                    VALID_FORMATS.join(", ")
                 ),
             )));
         }
 
+        for (name, template) in &self.formats {
+            if VALID_FORMATS.contains(&name.as_str()) {
+                return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
+                    format!("formats.{}", name),
+                    "custom format names cannot shadow built-in formats".to_string(),
+                )));
+            }
+            crate::formatter::Template::parse(template).map_err(|e| {
+                LlaError::Config(ConfigErrorKind::InvalidValue(
+                    format!("formats.{}", name),
+                    e.to_string(),
+                ))
+            })?;
+        }
+
+        if TimeField::parse(&self.time.field).is_none() {
+            return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
+                "time.field".to_string(),
+                format!(
+                    "Invalid time field: {}. Must be one of: {}",
+                    self.time.field,
+                    TimeField::VALUES.join(", ")
+                ),
+            )));
+        }
+
+        if let Some(style) = self.time.style.as_deref().filter(|s| !s.is_empty()) {
+            if let Err(e) = TimeStyle::parse(style) {
+                return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
+                    "time.style".to_string(),
+                    format!("Invalid time style: {}: {}", style, e),
+                )));
+            }
+        }
+
         if !self.plugins_dir.exists() {
             return Err(LlaError::Config(ConfigErrorKind::InvalidPath(format!(
                 "Plugins directory does not exist: {}",
@@ -432,6 +702,32 @@ ignore_patterns = {}"#,
             }
         }
 
+        for (key, columns) in [
+            ("formatters.long.columns", &self.formatters.long.columns),
+            ("formatters.table.columns", &self.formatters.table.columns),
+        ] {
+            if let Err(e) = Column::from_names(columns) {
+                return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
+                    key.to_string(),
+                    e,
+                )));
+            }
+        }
+
+        if let Err(e) = GitColumn::from_names(&self.formatters.git.columns) {
+            return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
+                "formatters.git.columns".to_string(),
+                e,
+            )));
+        }
+
+        if let Err(e) = TimeBucket::parse(&self.formatters.timeline.bucket) {
+            return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
+                "formatters.timeline.bucket".to_string(),
+                e,
+            )));
+        }
+
         if let Some(max_lines) = self.formatters.tree.max_lines {
             if max_lines > 100_000 {
                 return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
@@ -472,6 +768,15 @@ ignore_patterns = {}"#,
             }
         }
 
+        for pattern in &self.filter.exclude {
+            validate_exclude_pattern(pattern).map_err(|e| {
+                LlaError::Config(ConfigErrorKind::InvalidValue(
+                    "filter.exclude".to_string(),
+                    e,
+                ))
+            })?;
+        }
+
         for (name, cmd) in &self.shortcuts {
             if name.is_empty() {
                 return Err(LlaError::Config(ConfigErrorKind::ValidationError(
@@ -508,23 +813,19 @@ ignore_patterns = {}"#,
                 self.plugins_dir = new_dir;
             }
             ["default_sort"] => {
-                if !["name", "size", "date"].contains(&value) {
-                    return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
-                        key.to_string(),
-                        "must be one of: name, size, date".to_string(),
-                    )));
-                }
+                SortKey::parse_list(value).map_err(|e| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(key.to_string(), e))
+                })?;
                 self.default_sort = value.to_string();
             }
             ["default_format"] => {
-                let valid_formats = [
-                    "default", "long", "tree", "grid", "git", "timeline", "sizemap", "table",
-                    "fuzzy",
-                ];
-                if !valid_formats.contains(&value) {
+                if !VALID_FORMATS.contains(&value) && !self.formats.contains_key(value) {
                     return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
                         key.to_string(),
-                        format!("must be one of: {}", valid_formats.join(", ")),
+                        format!(
+                            "must be one of: {} or a name from [formats]",
+                            VALID_FORMATS.join(", ")
+                        ),
                     )));
                 }
                 self.default_format = value.to_string();
@@ -604,6 +905,50 @@ ignore_patterns = {}"#,
                     ))
                 })?;
             }
+            ["filter", "exclude"] => {
+                let patterns = parse_list_value(value);
+                for pattern in &patterns {
+                    validate_exclude_pattern(pattern).map_err(|e| {
+                        LlaError::Config(ConfigErrorKind::InvalidValue(key.to_string(), e))
+                    })?;
+                }
+                self.filter.exclude = patterns;
+            }
+            ["time", "field"] => {
+                if TimeField::parse(value).is_none() {
+                    return Err(LlaError::Config(ConfigErrorKind::InvalidValue(
+                        key.to_string(),
+                        format!("must be one of: {}", TimeField::VALUES.join(", ")),
+                    )));
+                }
+                self.time.field = value.to_string();
+            }
+            ["time", "style"] => {
+                if value.is_empty() {
+                    self.time.style = None;
+                } else {
+                    TimeStyle::parse(value).map_err(|e| {
+                        LlaError::Config(ConfigErrorKind::InvalidValue(key.to_string(), e))
+                    })?;
+                    self.time.style = Some(value.to_string());
+                }
+            }
+            ["size", "style"] => {
+                self.size.style = SizeStyle::parse(value).ok_or_else(|| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(
+                        key.to_string(),
+                        format!("must be one of: {}", SizeStyle::VALUES.join(", ")),
+                    ))
+                })?;
+            }
+            ["size", "disk_usage"] => {
+                self.size.disk_usage = value.parse().map_err(|_| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(
+                        key.to_string(),
+                        "must be true or false".to_string(),
+                    ))
+                })?;
+            }
             ["formatters", "tree", "max_lines"] => {
                 let max_lines = value.parse().map_err(|_| {
                     LlaError::Config(ConfigErrorKind::InvalidValue(
@@ -619,7 +964,74 @@ ignore_patterns = {}"#,
                 }
                 self.formatters.tree.max_lines = Some(max_lines);
             }
-
+            ["formatters", "tree", "compact"] => {
+                self.formatters.tree.compact = value.parse().map_err(|_| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(
+                        key.to_string(),
+                        "must be true or false".to_string(),
+                    ))
+                })?;
+            }
+            ["formatters", "tree", "max_children"] => {
+                let max_children: usize = value.parse().map_err(|_| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(
+                        key.to_string(),
+                        "must be a number".to_string(),
+                    ))
+                })?;
+                self.formatters.tree.max_children = Some(max_children).filter(|&max| max > 0);
+            }
+            ["formatters", "sizemap", "treemap"] => {
+                self.formatters.sizemap.treemap = value.parse().map_err(|_| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(
+                        key.to_string(),
+                        "must be true or false".to_string(),
+                    ))
+                })?;
+            }
+            ["formatters", "timeline", "bucket"] => {
+                TimeBucket::parse(value).map_err(|e| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(key.to_string(), e))
+                })?;
+                self.formatters.timeline.bucket = value.to_string();
+            }
+            ["formatters", "timeline", "histogram"] => {
+                self.formatters.timeline.histogram = value.parse().map_err(|_| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(
+                        key.to_string(),
+                        "must be true or false".to_string(),
+                    ))
+                })?;
+            }
+            ["formatters", "timeline", "git_dates"] => {
+                self.formatters.timeline.git_dates = value.parse().map_err(|_| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(
+                        key.to_string(),
+                        "must be true or false".to_string(),
+                    ))
+                })?;
+            }
+            ["formatters", "long", "columns"] => {
+                let columns = parse_list_value(value);
+                Column::from_names(&columns).map_err(|e| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(key.to_string(), e))
+                })?;
+                self.formatters.long.columns = columns;
+            }
+            ["formatters", "table", "columns"] => {
+                let columns = parse_list_value(value);
+                Column::from_names(&columns).map_err(|e| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(key.to_string(), e))
+                })?;
+                self.formatters.table.columns = columns;
+            }
+            ["formatters", "git", "columns"] => {
+                let columns = parse_list_value(value);
+                GitColumn::from_names(&columns).map_err(|e| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(key.to_string(), e))
+                })?;
+                self.formatters.git.columns = columns;
+            }
             ["listers", "recursive", "max_entries"] => {
                 let max_entries = value.parse().map_err(|_| {
                     LlaError::Config(ConfigErrorKind::InvalidValue(
@@ -635,6 +1047,15 @@ ignore_patterns = {}"#,
                 }
                 self.listers.recursive.max_entries = Some(max_entries);
             }
+            ["formats", name] => {
+                crate::formatter::Template::parse(value).map_err(|e| {
+                    LlaError::Config(ConfigErrorKind::InvalidValue(
+                        key.to_string(),
+                        e.to_string(),
+                    ))
+                })?;
+                self.formats.insert(name.to_string(), value.to_string());
+            }
             ["theme"] => {
                 if let Ok(themes) = crate::theme::list_themes() {
                     if !themes.contains(&value.to_string()) {
@@ -681,11 +1102,15 @@ impl Default for Config {
             include_dirs: false,
             sort: SortConfig::default(),
             filter: FilterConfig::default(),
+            time: TimeConfig::default(),
+            size: SizeConfig::default(),
             formatters: FormatterConfig {
-                tree: TreeFormatterConfig {
-                    max_lines: Some(20_000),
-                },
+                tree: TreeFormatterConfig::default(),
                 sizemap: SizeMapConfig::default(),
+                timeline: TimelineConfig::default(),
+                long: LongFormatterConfig::default(),
+                table: TableFormatterConfig::default(),
+                git: GitFormatterConfig::default(),
             },
             listers: ListerConfig {
                 recursive: RecursiveConfig {
@@ -696,11 +1121,20 @@ impl Default for Config {
                 },
             },
             shortcuts: HashMap::new(),
+            formats: HashMap::new(),
             theme: default_theme_name(),
         }
     }
 }
 
+fn parse_list_value(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
 pub fn initialize_config() -> Result<()> {
     let config_path = Config::get_config_path();
     let config_dir = config_path.parent().unwrap();
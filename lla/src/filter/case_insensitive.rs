@@ -1,5 +1,6 @@
 use super::FileFilter;
 use crate::error::Result;
+use std::ops::Range;
 use std::path::PathBuf;
 
 pub struct CaseInsensitiveFilter {
@@ -15,8 +16,7 @@ impl CaseInsensitiveFilter {
         let parent = path.parent().unwrap_or_else(|| path.as_ref());
         let filename = path
             .file_name()
-            .and_then(|name| name.to_str())
-            .map(|name| name.to_lowercase())
+            .map(|name| name.to_string_lossy().to_lowercase())
             .unwrap_or_default();
         parent.join(filename)
     }
@@ -40,4 +40,15 @@ impl FileFilter for CaseInsensitiveFilter {
             .map(|(_, path)| path.clone())
             .collect())
     }
+
+    /// Only highlights names whose lowercase form has the same byte layout,
+    /// so the range found in it also fits the original name.
+    fn highlight(&self, name: &str) -> Option<Range<usize>> {
+        let lowercase = name.to_lowercase();
+        let range = self.inner.highlight(&lowercase)?;
+        let aligned = lowercase.len() == name.len()
+            && name.is_char_boundary(range.start)
+            && name.is_char_boundary(range.end);
+        aligned.then_some(range)
+    }
 }
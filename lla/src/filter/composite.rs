@@ -1,5 +1,7 @@
 use super::FileFilter;
 use crate::error::{LlaError, Result};
+use lla_plugin_interface::proto::DecoratedEntry;
+use std::ops::Range;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy)]
@@ -28,18 +30,23 @@ impl CompositeFilter {
     }
 }
 
-impl FileFilter for CompositeFilter {
-    fn filter_files(&self, files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+impl CompositeFilter {
+    /// Applies the operation to `items`, running each child filter with
+    /// `apply`. Shared by path and entry filtering so both combine the same way.
+    fn combine<T: Clone + PartialEq>(
+        &self,
+        items: &[T],
+        apply: impl Fn(&dyn FileFilter, &[T]) -> Result<Vec<T>>,
+    ) -> Result<Vec<T>> {
         if self.filters.is_empty() {
-            return Ok(files.to_vec());
+            return Ok(items.to_vec());
         }
 
         match self.operation {
             FilterOperation::And => {
-                let mut result = files.to_vec();
+                let mut result = items.to_vec();
                 for filter in &self.filters {
-                    result = filter
-                        .filter_files(&result)
+                    result = apply(filter.as_ref(), &result)
                         .map_err(|e| LlaError::Filter(format!("AND operation failed: {}", e)))?;
                 }
                 Ok(result)
@@ -47,12 +54,11 @@ impl FileFilter for CompositeFilter {
             FilterOperation::Or => {
                 let mut result = Vec::new();
                 for filter in &self.filters {
-                    let filtered = filter
-                        .filter_files(files)
+                    let filtered = apply(filter.as_ref(), items)
                         .map_err(|e| LlaError::Filter(format!("OR operation failed: {}", e)))?;
-                    for file in filtered {
-                        if !result.contains(&file) {
-                            result.push(file);
+                    for item in filtered {
+                        if !result.contains(&item) {
+                            result.push(item);
                         }
                     }
                 }
@@ -64,12 +70,11 @@ impl FileFilter for CompositeFilter {
                         "NOT operation requires exactly one filter".to_string(),
                     ));
                 }
-                let filtered = self.filters[0]
-                    .filter_files(files)
+                let filtered = apply(self.filters[0].as_ref(), items)
                     .map_err(|e| LlaError::Filter(format!("NOT operation failed: {}", e)))?;
-                Ok(files
+                Ok(items
                     .iter()
-                    .filter(|file| !filtered.contains(file))
+                    .filter(|item| !filtered.contains(item))
                     .cloned()
                     .collect())
             }
@@ -79,18 +84,18 @@ impl FileFilter for CompositeFilter {
                         "XOR operation requires exactly two filters".to_string(),
                     ));
                 }
-                let first = self.filters[0].filter_files(files).map_err(|e| {
+                let first = apply(self.filters[0].as_ref(), items).map_err(|e| {
                     LlaError::Filter(format!("XOR operation failed on first filter: {}", e))
                 })?;
-                let second = self.filters[1].filter_files(files).map_err(|e| {
+                let second = apply(self.filters[1].as_ref(), items).map_err(|e| {
                     LlaError::Filter(format!("XOR operation failed on second filter: {}", e))
                 })?;
 
-                Ok(files
+                Ok(items
                     .iter()
-                    .filter(|file| {
-                        let in_first = first.contains(file);
-                        let in_second = second.contains(file);
+                    .filter(|item| {
+                        let in_first = first.contains(item);
+                        let in_second = second.contains(item);
                         in_first ^ in_second
                     })
                     .cloned()
@@ -99,3 +104,27 @@ impl FileFilter for CompositeFilter {
         }
     }
 }
+
+impl FileFilter for CompositeFilter {
+    fn filter_files(&self, files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        self.combine(files, |filter, files| filter.filter_files(files))
+    }
+
+    fn filter_entries(&self, entries: &[DecoratedEntry]) -> Result<Vec<DecoratedEntry>> {
+        self.combine(entries, |filter, entries| filter.filter_entries(entries))
+    }
+
+    fn needs_decoration(&self) -> bool {
+        self.filters.iter().any(|filter| filter.needs_decoration())
+    }
+
+    fn highlight(&self, name: &str) -> Option<Range<usize>> {
+        match self.operation {
+            FilterOperation::Not => None,
+            _ => self
+                .filters
+                .iter()
+                .find_map(|filter| filter.highlight(name)),
+        }
+    }
+}
@@ -0,0 +1,419 @@
+use super::{CompositeFilter, FileFilter, FilterOperation};
+use crate::error::{LlaError, Result};
+
+/// A parsed `--filter` expression.
+///
+/// Precedence from tightest to loosest is `NOT`, `AND`, `XOR`, `OR`, and
+/// parentheses group. Operators must be written in upper case; anything else
+/// is a term, and adjacent words form a single term so `-f "my file"` still
+/// matches names containing a space. Single or double quotes at the start of a
+/// term or right after a `prefix:` or comparison make operators, parentheses
+/// and whitespace literal. A term that starts with a quote is always a name
+/// pattern, so `"size:big"` matches names rather than sizes.
+#[derive(Debug, Clone)]
+pub enum FilterExpr {
+    Term {
+        text: String,
+        pos: usize,
+        /// Whether the term starts with a quote.
+        quoted: bool,
+    },
+    Not(Box<FilterExpr>),
+    And(Vec<FilterExpr>),
+    Xor(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Vec<FilterExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    And,
+    Or,
+    Xor,
+    Not,
+    LParen,
+    RParen,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+    /// Whether the word starts with a quote.
+    quoted: bool,
+}
+
+impl FilterExpr {
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            source,
+            tokens,
+            pos: 0,
+        };
+
+        if parser.tokens.is_empty() {
+            return Err(parser.error("empty filter expression", source.len()));
+        }
+
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            let message = match token.kind {
+                TokenKind::RParen => "unexpected ')'".to_string(),
+                _ => format!(
+                    "expected AND, OR or XOR before '{}'",
+                    &source[token.start..token.end]
+                ),
+            };
+            return Err(parser.error(&message, token.start));
+        }
+        Ok(expr)
+    }
+
+    /// Turns the expression into a filter tree, building each term with
+    /// `make_term`, which is told whether the term was quoted. Errors from
+    /// `make_term` are reported at the term's position.
+    pub fn into_filter<F>(
+        self,
+        source: &str,
+        make_term: &F,
+    ) -> Result<Box<dyn FileFilter + Send + Sync>>
+    where
+        F: Fn(&str, bool) -> std::result::Result<Box<dyn FileFilter + Send + Sync>, String>,
+    {
+        let composite =
+            |operation, children: Vec<FilterExpr>| -> Result<Box<dyn FileFilter + Send + Sync>> {
+                let mut filter = CompositeFilter::new(operation);
+                for child in children {
+                    filter.add_filter(child.into_filter(source, make_term)?);
+                }
+                Ok(Box::new(filter))
+            };
+
+        match self {
+            FilterExpr::Term { text, pos, quoted } => {
+                make_term(&text, quoted).map_err(|message| error_at(source, &message, pos))
+            }
+            FilterExpr::Not(inner) => composite(FilterOperation::Not, vec![*inner]),
+            FilterExpr::And(children) => composite(FilterOperation::And, children),
+            FilterExpr::Xor(left, right) => composite(FilterOperation::Xor, vec![*left, *right]),
+            FilterExpr::Or(children) => composite(FilterOperation::Or, children),
+        }
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next_if(&mut self, kind: &TokenKind) -> Option<Token> {
+        match self.peek() {
+            Some(token) if &token.kind == kind => {
+                self.pos += 1;
+                Some(self.tokens[self.pos - 1].clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn error(&self, message: &str, pos: usize) -> LlaError {
+        error_at(self.source, message, pos)
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr> {
+        let mut children = vec![self.parse_xor()?];
+        while self.next_if(&TokenKind::Or).is_some() {
+            children.push(self.parse_xor()?);
+        }
+        Ok(flatten(children, FilterExpr::Or))
+    }
+
+    fn parse_xor(&mut self) -> Result<FilterExpr> {
+        let mut expr = self.parse_and()?;
+        while self.next_if(&TokenKind::Xor).is_some() {
+            let right = self.parse_and()?;
+            expr = FilterExpr::Xor(Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr> {
+        let mut children = vec![self.parse_unary()?];
+        while self.next_if(&TokenKind::And).is_some() {
+            children.push(self.parse_unary()?);
+        }
+        Ok(flatten(children, FilterExpr::And))
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpr> {
+        if self.next_if(&TokenKind::Not).is_some() {
+            return Ok(FilterExpr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<FilterExpr> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => {
+                let operator = self.pos.checked_sub(1).map(|i| &self.tokens[i]);
+                let message = match operator {
+                    Some(op) => format!(
+                        "expected a pattern after '{}'",
+                        &self.source[op.start..op.end]
+                    ),
+                    None => "expected a pattern".to_string(),
+                };
+                return Err(self.error(&message, self.source.len()));
+            }
+        };
+
+        match &token.kind {
+            TokenKind::LParen => {
+                self.pos += 1;
+                if self.next_if(&TokenKind::RParen).is_some() {
+                    return Err(self.error("empty parentheses", token.start));
+                }
+                let expr = self.parse_or()?;
+                if self.next_if(&TokenKind::RParen).is_none() {
+                    return Err(self.error("unclosed '('", token.start));
+                }
+                Ok(expr)
+            }
+            TokenKind::Word(_) => Ok(self.parse_term()),
+            _ => Err(self.error(
+                &format!(
+                    "expected a pattern, found '{}'",
+                    &self.source[token.start..token.end]
+                ),
+                token.start,
+            )),
+        }
+    }
+
+    /// Joins a run of adjacent words into one term, keeping the whitespace
+    /// that separated them in the original expression.
+    fn parse_term(&mut self) -> FilterExpr {
+        let start = self.tokens[self.pos].start;
+        let quoted = self.tokens[self.pos].quoted;
+        let mut text = String::new();
+        let mut last_end = None;
+
+        while let Some(Token {
+            kind: TokenKind::Word(word),
+            start: word_start,
+            end,
+            ..
+        }) = self.peek().cloned()
+        {
+            if let Some(last_end) = last_end {
+                text.push_str(&self.source[last_end..word_start]);
+            }
+            text.push_str(&word);
+            last_end = Some(end);
+            self.pos += 1;
+        }
+
+        FilterExpr::Term {
+            text,
+            pos: start,
+            quoted,
+        }
+    }
+}
+
+fn flatten(mut children: Vec<FilterExpr>, make: fn(Vec<FilterExpr>) -> FilterExpr) -> FilterExpr {
+    if children.len() == 1 {
+        children.remove(0)
+    } else {
+        make(children)
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            chars.next();
+            let kind = if c == '(' {
+                TokenKind::LParen
+            } else {
+                TokenKind::RParen
+            };
+            tokens.push(Token {
+                kind,
+                start,
+                end: start + 1,
+                quoted: false,
+            });
+            continue;
+        }
+
+        let mut word = String::new();
+        let mut quoted = false;
+        let mut starts_quoted = false;
+        // Parentheses inside a word (e.g. `regex:^(a|b)$`) belong to it as
+        // long as they balance; an unmatched `)` closes an enclosing group.
+        let mut depth = 0usize;
+        let mut end = start;
+
+        while let Some(&(i, c)) = chars.peek() {
+            match c {
+                c if c.is_whitespace() => break,
+                ')' if depth == 0 => break,
+                '"' | '\'' if word.is_empty() || word.ends_with([':', '=', '~', '<', '>']) => {
+                    chars.next();
+                    starts_quoted |= word.is_empty();
+                    quoted = true;
+                    let mut closed = false;
+                    while let Some((j, inner)) = chars.next() {
+                        if inner == c {
+                            closed = true;
+                            end = j + 1;
+                            break;
+                        }
+                        if inner == '\\' && c == '"' {
+                            if let Some(&(_, escaped @ ('"' | '\\'))) = chars.peek() {
+                                chars.next();
+                                word.push(escaped);
+                                continue;
+                            }
+                        }
+                        word.push(inner);
+                    }
+                    if !closed {
+                        return Err(error_at(source, "unterminated quote", i));
+                    }
+                    continue;
+                }
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            chars.next();
+            word.push(c);
+            end = i + c.len_utf8();
+        }
+
+        let kind = match word.as_str() {
+            "AND" if !quoted => TokenKind::And,
+            "OR" if !quoted => TokenKind::Or,
+            "XOR" if !quoted => TokenKind::Xor,
+            "NOT" if !quoted => TokenKind::Not,
+            _ => TokenKind::Word(word),
+        };
+        tokens.push(Token {
+            kind,
+            start,
+            end,
+            quoted: starts_quoted,
+        });
+    }
+
+    Ok(tokens)
+}
+
+fn error_at(source: &str, message: &str, pos: usize) -> LlaError {
+    let column = source[..pos.min(source.len())].chars().count();
+    LlaError::Filter(format!(
+        "invalid filter '{}': {} at position {}",
+        source, message, column
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders the tree in prefix form so tests can compare its shape.
+    fn shape(expr: &FilterExpr) -> String {
+        let list = |op: &str, children: &[FilterExpr]| {
+            let children: Vec<String> = children.iter().map(shape).collect();
+            format!("({} {})", op, children.join(" "))
+        };
+        match expr {
+            FilterExpr::Term { text, .. } => text.clone(),
+            FilterExpr::Not(inner) => format!("(not {})", shape(inner)),
+            FilterExpr::And(children) => list("and", children),
+            FilterExpr::Xor(left, right) => format!("(xor {} {})", shape(left), shape(right)),
+            FilterExpr::Or(children) => list("or", children),
+        }
+    }
+
+    fn parse(source: &str) -> String {
+        shape(&FilterExpr::parse(source).unwrap())
+    }
+
+    fn error(source: &str) -> String {
+        FilterExpr::parse(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(
+            parse("a OR b XOR c AND NOT d"),
+            "(or a (xor b (and c (not d))))"
+        );
+        assert_eq!(
+            parse("NOT a AND b XOR c OR d"),
+            "(or (xor (and (not a) b) c) d)"
+        );
+        assert_eq!(parse("a XOR b XOR c"), "(xor (xor a b) c)");
+        assert_eq!(parse("(a OR b) AND c"), "(and (or a b) c)");
+        assert_eq!(parse("NOT NOT a"), "(not (not a))");
+    }
+
+    #[test]
+    fn quoted_operators_are_terms() {
+        assert_eq!(parse("\"AND\""), "AND");
+        assert_eq!(parse("a AND 'AND'"), "(and a AND)");
+        assert_eq!(parse("\"a OR b\" OR c"), "(or a OR b c)");
+        assert_eq!(parse("and"), "and");
+    }
+
+    #[test]
+    fn quoted_terms_are_marked() {
+        let quoted = |source: &str| match FilterExpr::parse(source).unwrap() {
+            FilterExpr::Term { quoted, .. } => quoted,
+            other => panic!("expected a term, got {}", shape(&other)),
+        };
+        assert!(quoted("\"size:>1M\""));
+        assert!(quoted("'type:l' x"));
+        assert!(!quoted("size:\">1M\""));
+        assert!(!quoted("size:>1M"));
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!(
+            error("(a AND b"),
+            "invalid filter '(a AND b': unclosed '(' at position 0"
+        );
+        assert_eq!(
+            error("a OR (b AND (c)"),
+            "invalid filter 'a OR (b AND (c)': unclosed '(' at position 5"
+        );
+        assert_eq!(
+            error("a AND NOT"),
+            "invalid filter 'a AND NOT': expected a pattern after 'NOT' at position 9"
+        );
+        assert_eq!(
+            error("NOT"),
+            "invalid filter 'NOT': expected a pattern after 'NOT' at position 3"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use super::FileFilter;
 use crate::error::Result;
+use std::ops::Range;
 use std::path::PathBuf;
 
 pub struct ExtensionFilter {
@@ -18,11 +19,16 @@ impl FileFilter for ExtensionFilter {
             .iter()
             .filter(|file| {
                 file.extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| ext == self.extension)
+                    .map(|ext| ext.to_string_lossy() == self.extension.as_str())
                     .unwrap_or(false)
             })
             .cloned()
             .collect())
     }
+
+    fn highlight(&self, name: &str) -> Option<Range<usize>> {
+        let start = name.len().checked_sub(self.extension.len() + 1)?;
+        let suffix = name.get(start..)?;
+        (suffix.strip_prefix('.')? == self.extension).then_some(start..name.len())
+    }
 }
@@ -0,0 +1,137 @@
+use super::FileFilter;
+use crate::error::Result;
+use lla_plugin_interface::proto::DecoratedEntry;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FieldOperation {
+    Present,
+    Equal,
+    NotEqual,
+    Contains,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+}
+
+/// Matches plugin-provided `custom_fields`, e.g. `field:category=Image`,
+/// `field:git_status~modified` or `field:complexity>20`. A bare
+/// `field:name` matches entries where the field is set and non-empty.
+pub struct FieldFilter {
+    name: String,
+    operation: FieldOperation,
+    value: String,
+    case_insensitive: bool,
+}
+
+impl FieldFilter {
+    pub fn parse(term: &str, case_insensitive: bool) -> Option<std::result::Result<Self, String>> {
+        let spec = term.strip_prefix("field:")?;
+        Some(Self::parse_spec(spec, case_insensitive))
+    }
+
+    fn parse_spec(spec: &str, case_insensitive: bool) -> std::result::Result<Self, String> {
+        let operators = [
+            ("!=", FieldOperation::NotEqual),
+            (">=", FieldOperation::GreaterEq),
+            ("<=", FieldOperation::LessEq),
+            ("=", FieldOperation::Equal),
+            ("~", FieldOperation::Contains),
+            (">", FieldOperation::Greater),
+            ("<", FieldOperation::Less),
+        ];
+
+        let split = spec
+            .find(['!', '=', '~', '<', '>'])
+            .map(|pos| (&spec[..pos], &spec[pos..]));
+        let (name, operation, value) = match split {
+            None => (spec, FieldOperation::Present, ""),
+            Some((name, rest)) => {
+                let (symbol, operation) = operators
+                    .iter()
+                    .find(|(symbol, _)| rest.starts_with(symbol))
+                    .ok_or_else(|| format!("unknown operator in 'field:{}'", spec))?;
+                (name, *operation, &rest[symbol.len()..])
+            }
+        };
+
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("expected a field name after 'field:'".to_string());
+        }
+
+        let value = value.trim();
+        let numeric = matches!(
+            operation,
+            FieldOperation::Less
+                | FieldOperation::LessEq
+                | FieldOperation::Greater
+                | FieldOperation::GreaterEq
+        );
+        if numeric && value.parse::<f64>().is_err() {
+            return Err(format!(
+                "expected a number to compare field '{}' with, found '{}'",
+                name, value
+            ));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            operation,
+            value: if case_insensitive {
+                value.to_lowercase()
+            } else {
+                value.to_string()
+            },
+            case_insensitive,
+        })
+    }
+
+    fn matches(&self, entry: &DecoratedEntry) -> bool {
+        let field = match entry.custom_fields.get(&self.name) {
+            Some(field) if self.case_insensitive => field.to_lowercase(),
+            Some(field) => field.clone(),
+            None => return self.operation == FieldOperation::NotEqual,
+        };
+
+        let compare = |check: fn(f64, f64) -> bool| match (
+            field.trim().parse::<f64>(),
+            self.value.parse::<f64>(),
+        ) {
+            (Ok(actual), Ok(expected)) => check(actual, expected),
+            _ => false,
+        };
+
+        match self.operation {
+            FieldOperation::Present => !field.is_empty(),
+            FieldOperation::Equal => field == self.value,
+            FieldOperation::NotEqual => field != self.value,
+            FieldOperation::Contains => field.contains(&self.value),
+            FieldOperation::Less => compare(|a, b| a < b),
+            FieldOperation::LessEq => compare(|a, b| a <= b),
+            FieldOperation::Greater => compare(|a, b| a > b),
+            FieldOperation::GreaterEq => compare(|a, b| a >= b),
+        }
+    }
+}
+
+impl FileFilter for FieldFilter {
+    /// Bare paths carry no plugin fields, so nothing matches here; field
+    /// filters are applied to decorated entries instead.
+    fn filter_files(&self, _files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    fn filter_entries(&self, entries: &[DecoratedEntry]) -> Result<Vec<DecoratedEntry>> {
+        Ok(entries
+            .iter()
+            .filter(|entry| self.matches(entry))
+            .cloned()
+            .collect())
+    }
+
+    fn needs_decoration(&self) -> bool {
+        true
+    }
+}
@@ -21,8 +21,7 @@ impl FileFilter for GlobFilter {
             .iter()
             .filter(|file| {
                 file.file_name()
-                    .and_then(|name| name.to_str())
-                    .map(|name| self.pattern.matches(name))
+                    .map(|name| self.pattern.matches(&name.to_string_lossy()))
                     .unwrap_or(false)
             })
             .cloned()
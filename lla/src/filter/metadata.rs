@@ -0,0 +1,495 @@
+use super::FileFilter;
+use crate::error::Result;
+use crate::utils::metadata::convert_metadata;
+use crate::utils::size::parse_size;
+use crate::utils::time::TimeField;
+use chrono::{Local, NaiveDate, TimeZone};
+use lla_plugin_interface::proto::DecoratedEntry;
+use lla_plugin_interface::FileType;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use users::{get_group_by_name, get_user_by_name};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Less,
+    LessEq,
+    Equal,
+    GreaterEq,
+    Greater,
+}
+
+impl Comparison {
+    /// Splits a leading `<`, `<=`, `=`, `>=` or `>` off `value`.
+    fn split(value: &str) -> (Option<Self>, &str) {
+        let operators = [
+            (">=", Comparison::GreaterEq),
+            ("<=", Comparison::LessEq),
+            (">", Comparison::Greater),
+            ("<", Comparison::Less),
+            ("=", Comparison::Equal),
+        ];
+        for (prefix, comparison) in operators {
+            if let Some(rest) = value.strip_prefix(prefix) {
+                return (Some(comparison), rest.trim());
+            }
+        }
+        (None, value.trim())
+    }
+
+    fn matches(&self, actual: u64, expected: u64) -> bool {
+        match self {
+            Comparison::Less => actual < expected,
+            Comparison::LessEq => actual <= expected,
+            Comparison::Equal => actual == expected,
+            Comparison::GreaterEq => actual >= expected,
+            Comparison::Greater => actual > expected,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Predicate {
+    Size(Comparison, u64),
+    /// An inclusive range of sizes.
+    SizeRange(u64, u64),
+    /// Seconds elapsed since the timestamp.
+    Age(TimeField, Comparison, u64),
+    /// A calendar day, as the half-open range of timestamps it covers.
+    Date(TimeField, Comparison, u64, u64),
+    Owner(u32),
+    Group(u32),
+    Mode(u32),
+    /// With `+` every mask needs at least one bit set, with `-` none.
+    Permission {
+        masks: Vec<u32>,
+        set: bool,
+    },
+    Type(FileType),
+    Empty(bool),
+}
+
+/// Matches entries on their metadata rather than their name, e.g.
+/// `size:>10M`, `size:10K..1M`, `modified:<7d`, `owner:alice`, `perm:+x`,
+/// `type:symlink` or `empty:`.
+pub struct MetadataFilter {
+    predicate: Predicate,
+}
+
+impl MetadataFilter {
+    /// Parses a `key:value` predicate. Returns `None` when `term` doesn't use a
+    /// metadata key, so it can be treated as a name pattern instead.
+    pub fn parse(term: &str) -> Option<std::result::Result<Self, String>> {
+        let (key, value) = term.split_once(':')?;
+        let predicate = match key {
+            "size" => parse_size_predicate(value),
+            "owner" | "user" => parse_owner(value),
+            "group" => parse_group(value),
+            "perm" => parse_permission(value),
+            "type" => parse_type(value),
+            "empty" => parse_empty(value),
+            key => parse_time_predicate(TimeField::parse(key)?, value),
+        };
+        Some(predicate.map(|predicate| Self { predicate }))
+    }
+
+    fn matches(&self, entry: &DecoratedEntry) -> bool {
+        let metadata = match &entry.metadata {
+            Some(metadata) => metadata,
+            None => return false,
+        };
+
+        match &self.predicate {
+            Predicate::Size(comparison, size) => comparison.matches(metadata.size, *size),
+            Predicate::SizeRange(low, high) => (*low..=*high).contains(&metadata.size),
+            Predicate::Age(field, comparison, age) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                comparison.matches(now.saturating_sub(field.timestamp(entry)), *age)
+            }
+            Predicate::Date(field, comparison, start, end) => {
+                let timestamp = field.timestamp(entry);
+                match comparison {
+                    Comparison::Less => timestamp < *start,
+                    Comparison::LessEq => timestamp < *end,
+                    Comparison::Equal => (*start..*end).contains(&timestamp),
+                    Comparison::GreaterEq => timestamp >= *start,
+                    Comparison::Greater => timestamp >= *end,
+                }
+            }
+            Predicate::Owner(uid) => metadata.uid == *uid,
+            Predicate::Group(gid) => metadata.gid == *gid,
+            Predicate::Mode(mode) => metadata.permissions & 0o7777 == *mode,
+            Predicate::Permission { masks, set } => masks.iter().all(|mask| {
+                let bits = metadata.permissions & mask;
+                if *set {
+                    bits != 0
+                } else {
+                    bits == 0
+                }
+            }),
+            Predicate::Type(file_type) => metadata.kind() == *file_type,
+            Predicate::Empty(empty) => {
+                let is_empty = match metadata.kind() {
+                    FileType::Regular => metadata.size == 0,
+                    FileType::Directory => fs::read_dir(entry.fs_path())
+                        .map(|mut entries| entries.next().is_none())
+                        .unwrap_or(false),
+                    _ => false,
+                };
+                is_empty == *empty
+            }
+        }
+    }
+}
+
+impl FileFilter for MetadataFilter {
+    fn filter_files(&self, files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        Ok(files
+            .iter()
+            .filter(|path| {
+                fs::symlink_metadata(path)
+                    .map(|metadata| {
+                        let metadata = convert_metadata(&metadata);
+                        let entry = DecoratedEntry::new(path, Some(metadata), Default::default());
+                        self.matches(&entry)
+                    })
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect())
+    }
+
+    fn filter_entries(&self, entries: &[DecoratedEntry]) -> Result<Vec<DecoratedEntry>> {
+        Ok(entries
+            .iter()
+            .filter(|entry| self.matches(entry))
+            .cloned()
+            .collect())
+    }
+}
+
+/// Splits `value` into its leading number and the unit that follows it.
+fn split_number(value: &str) -> std::result::Result<(f64, String), String> {
+    let end = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let number = value[..end]
+        .parse::<f64>()
+        .map_err(|_| format!("expected a number in '{}'", value))?;
+    Ok((number, value[end..].trim().to_lowercase()))
+}
+
+/// `size:>10M` compares with a single size and `size:10K..1M` matches sizes
+/// between two, both included.
+fn parse_size_predicate(value: &str) -> std::result::Result<Predicate, String> {
+    if let Some((low, high)) = value.split_once("..") {
+        let (low, high) = (parse_size(low)?, parse_size(high)?);
+        if low > high {
+            return Err(format!("empty size range '{}'", value));
+        }
+        return Ok(Predicate::SizeRange(low, high));
+    }
+
+    let (comparison, value) = Comparison::split(value);
+    Ok(Predicate::Size(
+        comparison.unwrap_or(Comparison::Equal),
+        parse_size(value)?,
+    ))
+}
+
+/// `modified:<7d` compares the age of the timestamp, so `<` means "newer
+/// than"; without an operator it means "within". A `YYYY-MM-DD` date compares
+/// the timestamp itself, so `>` means "after that day".
+fn parse_time_predicate(field: TimeField, value: &str) -> std::result::Result<Predicate, String> {
+    let (comparison, value) = Comparison::split(value);
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let start = date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+            .map(|midnight| midnight.timestamp().max(0) as u64)
+            .ok_or_else(|| format!("invalid date '{}'", value))?;
+        return Ok(Predicate::Date(
+            field,
+            comparison.unwrap_or(Comparison::Equal),
+            start,
+            start + SECONDS_PER_DAY,
+        ));
+    }
+
+    let (number, unit) = split_number(value)?;
+    let seconds: u64 = match unit.as_str() {
+        "s" | "sec" => 1,
+        "m" | "min" => 60,
+        "h" => 60 * 60,
+        "d" | "" => SECONDS_PER_DAY,
+        "w" => 7 * SECONDS_PER_DAY,
+        "y" => 365 * SECONDS_PER_DAY,
+        _ => {
+            return Err(format!(
+                "unknown time unit '{}' (use s, m, h, d, w, y or a YYYY-MM-DD date)",
+                unit
+            ))
+        }
+    };
+    let age = number * seconds as f64;
+    // `u64::MAX as f64` rounds up to 2^64, which no age may reach.
+    if age >= u64::MAX as f64 {
+        return Err(format!("duration '{}' is too long", value));
+    }
+    Ok(Predicate::Age(
+        field,
+        comparison.unwrap_or(Comparison::LessEq),
+        age as u64,
+    ))
+}
+
+fn parse_owner(value: &str) -> std::result::Result<Predicate, String> {
+    match value.parse() {
+        Ok(uid) => Ok(Predicate::Owner(uid)),
+        Err(_) => get_user_by_name(value)
+            .map(|user| Predicate::Owner(user.uid()))
+            .ok_or_else(|| format!("unknown user '{}'", value)),
+    }
+}
+
+fn parse_group(value: &str) -> std::result::Result<Predicate, String> {
+    match value.parse() {
+        Ok(gid) => Ok(Predicate::Group(gid)),
+        Err(_) => get_group_by_name(value)
+            .map(|group| Predicate::Group(group.gid()))
+            .ok_or_else(|| format!("unknown group '{}'", value)),
+    }
+}
+
+/// Accepts an exact octal mode (`644`) or a symbolic check such as `+x`,
+/// `u+w` or `go-w`. Without a class, `+x` matches if anyone may execute.
+fn parse_permission(value: &str) -> std::result::Result<Predicate, String> {
+    if !value.is_empty() && value.len() <= 4 && value.chars().all(|c| ('0'..='7').contains(&c)) {
+        return u32::from_str_radix(value, 8)
+            .map(Predicate::Mode)
+            .map_err(|e| e.to_string());
+    }
+
+    let invalid = || {
+        format!(
+            "invalid permission '{}' (use an octal mode like 755 or a check like u+x)",
+            value
+        )
+    };
+    let op = value.find(['+', '-']).ok_or_else(invalid)?;
+    let (classes, bits) = (&value[..op], &value[op + 1..]);
+    if bits.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut shifts = Vec::new();
+    for class in classes.chars() {
+        match class {
+            'u' => shifts.push(6),
+            'g' => shifts.push(3),
+            'o' => shifts.push(0),
+            'a' => shifts.extend([6, 3, 0]),
+            _ => return Err(invalid()),
+        }
+    }
+
+    let mut masks = Vec::new();
+    for bit in bits.chars() {
+        let bit = match bit {
+            'r' => 0o4,
+            'w' => 0o2,
+            'x' => 0o1,
+            _ => return Err(invalid()),
+        };
+        if shifts.is_empty() {
+            masks.push((bit << 6) | (bit << 3) | bit);
+        } else {
+            masks.extend(shifts.iter().map(|shift| bit << shift));
+        }
+    }
+
+    Ok(Predicate::Permission {
+        masks,
+        set: &value[op..op + 1] == "+",
+    })
+}
+
+fn parse_type(value: &str) -> std::result::Result<Predicate, String> {
+    let file_type = match value {
+        "file" | "f" | "regular" => FileType::Regular,
+        "dir" | "directory" | "d" => FileType::Directory,
+        "symlink" | "link" | "l" => FileType::Symlink,
+        "fifo" | "pipe" | "p" => FileType::Fifo,
+        "socket" | "s" => FileType::Socket,
+        "block" | "block_device" | "b" => FileType::BlockDevice,
+        "char" | "char_device" | "c" => FileType::CharDevice,
+        _ => {
+            return Err(format!(
+                "unknown file type '{}' (use file, dir, symlink, fifo, socket, block or char)",
+                value
+            ))
+        }
+    };
+    Ok(Predicate::Type(file_type))
+}
+
+fn parse_empty(value: &str) -> std::result::Result<Predicate, String> {
+    match value {
+        "" | "true" | "yes" => Ok(Predicate::Empty(true)),
+        "false" | "no" => Ok(Predicate::Empty(false)),
+        _ => Err(format!("expected true or false, found '{}'", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(term: &str) -> Predicate {
+        MetadataFilter::parse(term).unwrap().unwrap().predicate
+    }
+
+    fn error(term: &str) -> String {
+        match MetadataFilter::parse(term) {
+            Some(Err(message)) => message,
+            Some(Ok(filter)) => panic!("expected an error, got {:?}", filter.predicate),
+            None => panic!("'{}' is not a metadata predicate", term),
+        }
+    }
+
+    #[test]
+    fn other_keys_are_not_predicates() {
+        assert!(MetadataFilter::parse("main.rs").is_none());
+        assert!(MetadataFilter::parse("regex:^a").is_none());
+        assert!(MetadataFilter::parse("sizes:1").is_none());
+    }
+
+    #[test]
+    fn parses_sizes() {
+        use Comparison::*;
+        assert_eq!(parse("size:>10M"), Predicate::Size(Greater, 10 << 20));
+        assert_eq!(parse("size:<=1k"), Predicate::Size(LessEq, 1024));
+        assert_eq!(parse("size:0"), Predicate::Size(Equal, 0));
+        assert_eq!(parse("size:1MB"), Predicate::Size(Equal, 1_000_000));
+        assert_eq!(
+            parse("size:10K..1M"),
+            Predicate::SizeRange(10 << 10, 1 << 20)
+        );
+        assert_eq!(parse("size:1K..1K"), Predicate::SizeRange(1024, 1024));
+    }
+
+    #[test]
+    fn rejects_bad_sizes() {
+        assert!(error("size:").contains("expected a number"));
+        assert!(error("size:>10Q").contains("unknown size unit 'q'"));
+        assert!(error("size:1M..10K").contains("empty size range"));
+        assert!(error("size:..1M").contains("expected a number"));
+        assert!(error("size:>99999999999T").contains("too large"));
+    }
+
+    #[test]
+    fn parses_ages_and_dates() {
+        use Comparison::*;
+        let day = SECONDS_PER_DAY;
+        assert_eq!(
+            parse("modified:<2d"),
+            Predicate::Age(TimeField::Modified, Less, 2 * day)
+        );
+        assert_eq!(
+            parse("mtime:3"),
+            Predicate::Age(TimeField::Modified, LessEq, 3 * day)
+        );
+        assert_eq!(
+            parse("accessed:>=1.5h"),
+            Predicate::Age(TimeField::Accessed, GreaterEq, 90 * 60)
+        );
+        assert_eq!(
+            parse("created:>2w"),
+            Predicate::Age(TimeField::Created, Greater, 14 * day)
+        );
+        match parse("changed:>2024-03-01") {
+            Predicate::Date(TimeField::Changed, Greater, start, end) => {
+                assert_eq!(end - start, day)
+            }
+            other => panic!("expected a date, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_bad_ages() {
+        assert!(error("modified:").contains("expected a number"));
+        assert!(error("modified:<3x").contains("unknown time unit 'x'"));
+        assert!(error("modified:2024-13-01").contains("unknown time unit"));
+        assert!(error("modified:<99999999999999999y").contains("too long"));
+    }
+
+    #[test]
+    fn parses_permissions() {
+        assert_eq!(parse("perm:755"), Predicate::Mode(0o755));
+        assert_eq!(parse("perm:4755"), Predicate::Mode(0o4755));
+        assert_eq!(
+            parse("perm:u+x"),
+            Predicate::Permission {
+                masks: vec![0o100],
+                set: true
+            }
+        );
+        assert_eq!(
+            parse("perm:+x"),
+            Predicate::Permission {
+                masks: vec![0o111],
+                set: true
+            }
+        );
+        assert_eq!(
+            parse("perm:go-w"),
+            Predicate::Permission {
+                masks: vec![0o020, 0o002],
+                set: false
+            }
+        );
+        assert_eq!(
+            parse("perm:a+r"),
+            Predicate::Permission {
+                masks: vec![0o400, 0o040, 0o004],
+                set: true
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_bad_permissions() {
+        for term in [
+            "perm:",
+            "perm:u+",
+            "perm:z+x",
+            "perm:u+q",
+            "perm:888",
+            "perm:77777",
+        ] {
+            assert!(error(term).contains("invalid permission"), "{}", term);
+        }
+    }
+
+    #[test]
+    fn parses_types_owners_and_emptiness() {
+        assert_eq!(parse("type:l"), Predicate::Type(FileType::Symlink));
+        assert_eq!(parse("type:dir"), Predicate::Type(FileType::Directory));
+        assert_eq!(parse("type:f"), Predicate::Type(FileType::Regular));
+        assert!(error("type:door").contains("unknown file type 'door'"));
+
+        assert_eq!(parse("owner:0"), Predicate::Owner(0));
+        assert_eq!(parse("group:0"), Predicate::Group(0));
+        assert!(error("owner:no-such-user-lla").contains("unknown user"));
+
+        assert_eq!(parse("empty:"), Predicate::Empty(true));
+        assert_eq!(parse("empty:no"), Predicate::Empty(false));
+        assert!(error("empty:maybe").contains("expected true or false"));
+    }
+}
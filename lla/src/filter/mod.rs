@@ -1,20 +1,53 @@
 use crate::error::Result;
+use lla_plugin_interface::proto::DecoratedEntry;
+use std::collections::HashSet;
+use std::ops::Range;
 use std::path::PathBuf;
 
 pub trait FileFilter: Send + Sync {
     fn filter_files(&self, files: &[PathBuf]) -> Result<Vec<PathBuf>>;
+
+    /// Filters listed entries. Filters that only look at names can rely on the
+    /// default, which matches on paths; filters that inspect metadata override it.
+    fn filter_entries(&self, entries: &[DecoratedEntry]) -> Result<Vec<DecoratedEntry>> {
+        let paths: Vec<PathBuf> = entries.iter().map(|entry| entry.fs_path()).collect();
+        let kept: HashSet<PathBuf> = self.filter_files(&paths)?.into_iter().collect();
+        Ok(entries
+            .iter()
+            .filter(|entry| kept.contains(&entry.fs_path()))
+            .cloned()
+            .collect())
+    }
+
+    /// Whether the filter looks at plugin fields, and so has to run after
+    /// entries are decorated rather than while they are listed.
+    fn needs_decoration(&self) -> bool {
+        false
+    }
+
+    /// The part of a file name this filter matched on, for highlighting.
+    /// Filters that don't match on a substring of the name return `None`.
+    fn highlight(&self, _name: &str) -> Option<Range<usize>> {
+        None
+    }
 }
 
 mod case_insensitive;
 mod composite;
+mod expression;
 mod extension;
+mod field;
 mod glob_filter;
+mod metadata;
 mod pattern;
 mod regex_filter;
 
 pub use case_insensitive::CaseInsensitiveFilter;
 pub use composite::{CompositeFilter, FilterOperation};
+pub use expression::FilterExpr;
 pub use extension::ExtensionFilter;
+pub use field::FieldFilter;
 pub use glob_filter::GlobFilter;
+pub use metadata::MetadataFilter;
 pub use pattern::PatternFilter;
 pub use regex_filter::RegexFilter;
@@ -1,5 +1,6 @@
 use super::FileFilter;
 use crate::error::Result;
+use std::ops::Range;
 use std::path::PathBuf;
 
 pub struct PatternFilter {
@@ -38,7 +39,7 @@ impl PatternFilter {
     }
 
     fn matches_pattern(&self, path: &PathBuf) -> bool {
-        if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+        if let Some(name) = path.file_name().map(|name| name.to_string_lossy()) {
             if self.match_all {
                 return self.patterns.iter().all(|pattern| name.contains(pattern));
             } else if self.patterns.iter().any(|pattern| name.contains(pattern)) {
@@ -46,18 +47,15 @@ impl PatternFilter {
             }
         }
 
-        if let Some(path_str) = path.to_str() {
-            if self.match_all {
-                self.patterns
-                    .iter()
-                    .all(|pattern| path_str.contains(pattern))
-            } else {
-                self.patterns
-                    .iter()
-                    .any(|pattern| path_str.contains(pattern))
-            }
+        let path_str = path.to_string_lossy();
+        if self.match_all {
+            self.patterns
+                .iter()
+                .all(|pattern| path_str.contains(pattern))
         } else {
-            false
+            self.patterns
+                .iter()
+                .any(|pattern| path_str.contains(pattern))
         }
     }
 }
@@ -70,4 +68,11 @@ impl FileFilter for PatternFilter {
             .cloned()
             .collect())
     }
+
+    fn highlight(&self, name: &str) -> Option<Range<usize>> {
+        self.patterns.iter().find_map(|pattern| {
+            name.find(pattern.as_str())
+                .map(|start| start..start + pattern.len())
+        })
+    }
 }
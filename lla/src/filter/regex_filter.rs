@@ -1,6 +1,7 @@
 use super::FileFilter;
 use crate::error::Result;
 use regex::Regex;
+use std::ops::Range;
 use std::path::PathBuf;
 
 pub struct RegexFilter {
@@ -21,11 +22,17 @@ impl FileFilter for RegexFilter {
             .iter()
             .filter(|file| {
                 file.file_name()
-                    .and_then(|name| name.to_str())
-                    .map(|name| self.regex.is_match(name))
+                    .map(|name| self.regex.is_match(&name.to_string_lossy()))
                     .unwrap_or(false)
             })
             .cloned()
             .collect())
     }
+
+    fn highlight(&self, name: &str) -> Option<Range<usize>> {
+        self.regex
+            .find(name)
+            .filter(|found| !found.range().is_empty())
+            .map(|found| found.range())
+    }
 }
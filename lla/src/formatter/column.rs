@@ -0,0 +1,359 @@
+use crate::plugin::PluginManager;
+use crate::theme::{self, ColorValue};
+use crate::utils::color::*;
+use crate::utils::icons::format_with_icon;
+use crate::utils::quoting::quote_name;
+use crate::utils::symlink::symlink_suffix;
+use crate::utils::time::{time_field, time_style, to_system_time, TimeField};
+use chrono::{DateTime, Local};
+use colored::*;
+use lla_plugin_interface::proto::DecoratedEntry;
+use lla_plugin_interface::FileType;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs::Permissions;
+use std::os::unix::fs::PermissionsExt;
+use std::sync::Mutex;
+use unicode_width::UnicodeWidthStr;
+use users::{get_group_by_gid, get_user_by_uid};
+
+static USER_CACHE: Lazy<Mutex<HashMap<u32, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static GROUP_CACHE: Lazy<Mutex<HashMap<u32, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    Path,
+    Name,
+    Size,
+    Modified,
+    Accessed,
+    Created,
+    Changed,
+    Time,
+    Permissions,
+    Mode,
+    Inode,
+    Nlink,
+    Dev,
+    Rdev,
+    Blocks,
+    Blksize,
+    Uid,
+    Gid,
+    User,
+    Group,
+    Type,
+    Plugins,
+    Field(String),
+}
+
+impl Column {
+    /// Resolves a column name. Plugin fields need a `field:` or `plugin.`
+    /// prefix, so a misspelled column is reported rather than shown empty.
+    pub fn from_name(name: &str) -> std::result::Result<Self, String> {
+        let column = match name {
+            "path" => Column::Path,
+            "name" => Column::Name,
+            "size" => Column::Size,
+            "modified" | "mtime" => Column::Modified,
+            "accessed" | "atime" => Column::Accessed,
+            "created" | "btime" => Column::Created,
+            "changed" | "ctime" => Column::Changed,
+            "time" => Column::Time,
+            "permissions" | "perms" => Column::Permissions,
+            "mode" | "octal" => Column::Mode,
+            "inode" => Column::Inode,
+            "nlink" | "links" => Column::Nlink,
+            "dev" | "device" => Column::Dev,
+            "rdev" => Column::Rdev,
+            "blocks" => Column::Blocks,
+            "blksize" => Column::Blksize,
+            "uid" => Column::Uid,
+            "gid" => Column::Gid,
+            "user" => Column::User,
+            "group" => Column::Group,
+            "type" => Column::Type,
+            "plugins" => Column::Plugins,
+            other => match field_name(other) {
+                Some(key) => Column::Field(key.to_string()),
+                None => return Err(unknown_column(other)),
+            },
+        };
+        Ok(column)
+    }
+
+    pub fn parse_list(spec: &str) -> std::result::Result<Vec<Column>, String> {
+        let columns = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(Column::from_name)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        if columns.is_empty() {
+            return Err("no columns given".to_string());
+        }
+        Ok(columns)
+    }
+
+    pub fn from_names(names: &[String]) -> std::result::Result<Option<Vec<Column>>, String> {
+        if names.is_empty() {
+            Ok(None)
+        } else {
+            Column::parse_list(&names.join(",")).map(Some)
+        }
+    }
+
+    pub fn header(&self) -> String {
+        match self {
+            Column::Path => "path".to_string(),
+            Column::Name => "name".to_string(),
+            Column::Size => "size".to_string(),
+            Column::Modified => "modified".to_string(),
+            Column::Accessed => "accessed".to_string(),
+            Column::Created => "created".to_string(),
+            Column::Changed => "changed".to_string(),
+            Column::Time => time_field().title().to_lowercase(),
+            Column::Permissions => "permissions".to_string(),
+            Column::Mode => "mode".to_string(),
+            Column::Inode => "inode".to_string(),
+            Column::Nlink => "nlink".to_string(),
+            Column::Dev => "dev".to_string(),
+            Column::Rdev => "rdev".to_string(),
+            Column::Blocks => "blocks".to_string(),
+            Column::Blksize => "blksize".to_string(),
+            Column::Uid => "uid".to_string(),
+            Column::Gid => "gid".to_string(),
+            Column::User => "user".to_string(),
+            Column::Group => "group".to_string(),
+            Column::Type => "type".to_string(),
+            Column::Plugins => "plugins".to_string(),
+            Column::Field(key) => key.clone(),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            Column::Path => "Path".to_string(),
+            Column::Name => "Name".to_string(),
+            Column::Size => "Size".to_string(),
+            Column::Modified => "Modified".to_string(),
+            Column::Accessed => "Accessed".to_string(),
+            Column::Created => "Created".to_string(),
+            Column::Changed => "Changed".to_string(),
+            Column::Time => time_field().title().to_string(),
+            Column::Permissions => "Permissions".to_string(),
+            Column::Mode => "Mode".to_string(),
+            Column::Inode => "Inode".to_string(),
+            Column::Nlink => "Links".to_string(),
+            Column::Dev => "Device".to_string(),
+            Column::Rdev => "Rdev".to_string(),
+            Column::Blocks => "Blocks".to_string(),
+            Column::Blksize => "Blksize".to_string(),
+            Column::Uid => "UID".to_string(),
+            Column::Gid => "GID".to_string(),
+            Column::User => "User".to_string(),
+            Column::Group => "Group".to_string(),
+            Column::Type => "Type".to_string(),
+            Column::Plugins => "Plugins".to_string(),
+            Column::Field(key) => key.clone(),
+        }
+    }
+
+    pub fn time_field(&self) -> Option<TimeField> {
+        match self {
+            Column::Modified => Some(TimeField::Modified),
+            Column::Accessed => Some(TimeField::Accessed),
+            Column::Created => Some(TimeField::Created),
+            Column::Changed => Some(TimeField::Changed),
+            Column::Time => Some(time_field()),
+            _ => None,
+        }
+    }
+
+    pub fn align_right(&self) -> bool {
+        matches!(
+            self,
+            Column::Size
+                | Column::Inode
+                | Column::Nlink
+                | Column::Rdev
+                | Column::Blocks
+                | Column::Blksize
+                | Column::Uid
+                | Column::Gid
+        )
+    }
+
+    pub fn raw_value(&self, entry: &DecoratedEntry) -> String {
+        let metadata = entry.metadata.as_ref().cloned().unwrap_or_default();
+        match self {
+            Column::Path => quote_name(entry.fs_path().as_os_str()),
+            Column::Name => entry
+                .fs_path()
+                .file_name()
+                .map(quote_name)
+                .unwrap_or_else(|| entry.path.clone()),
+            Column::Size => metadata.size.to_string(),
+            Column::Modified
+            | Column::Accessed
+            | Column::Created
+            | Column::Changed
+            | Column::Time => {
+                let time = to_system_time(self.time_field().unwrap().timestamp(entry));
+                match time_style() {
+                    Some(style) => style.format(&time),
+                    None => {
+                        let datetime: DateTime<Local> = time.into();
+                        datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+                    }
+                }
+            }
+            Column::Permissions => format_permissions_no_color(metadata.permissions),
+            Column::Mode => format!("{:o}", metadata.permissions & 0o7777),
+            Column::Inode => metadata.inode.to_string(),
+            Column::Nlink => metadata.nlink.to_string(),
+            Column::Dev => format_device(metadata.dev),
+            Column::Rdev => match metadata.kind() {
+                FileType::BlockDevice | FileType::CharDevice => format_device(metadata.rdev),
+                _ => String::new(),
+            },
+            Column::Blocks => metadata.blocks.to_string(),
+            Column::Blksize => metadata.blksize.to_string(),
+            Column::Uid => metadata.uid.to_string(),
+            Column::Gid => metadata.gid.to_string(),
+            Column::User => user_name(metadata.uid),
+            Column::Group => group_name(metadata.gid),
+            Column::Type => metadata.kind().as_str().to_string(),
+            Column::Plugins => {
+                let mut fields: Vec<_> = entry.custom_fields.iter().collect();
+                fields.sort();
+                fields
+                    .into_iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+            Column::Field(key) => entry.custom_fields.get(key).cloned().unwrap_or_default(),
+        }
+    }
+
+    pub fn render(
+        &self,
+        entry: &DecoratedEntry,
+        show_icons: bool,
+        plugin_manager: &mut PluginManager,
+        format: &str,
+    ) -> String {
+        let metadata = entry.metadata.as_ref().cloned().unwrap_or_default();
+        let theme = get_theme();
+        match self {
+            Column::Name => {
+                let path = entry.fs_path();
+                let path = path.as_path();
+                let colored_name = colorize_file_name(path).to_string();
+                let name = colorize_file_name_with_icon(
+                    path,
+                    format_with_icon(path, colored_name, show_icons),
+                );
+                format!("{}{}", name, symlink_suffix(path))
+            }
+            Column::Size => colorize_size(metadata.size).to_string(),
+            Column::Modified
+            | Column::Accessed
+            | Column::Created
+            | Column::Changed
+            | Column::Time => {
+                let timestamp = self.time_field().unwrap().timestamp(entry);
+                colorize_date(&to_system_time(timestamp)).to_string()
+            }
+            Column::Permissions => {
+                colorize_permissions(&Permissions::from_mode(metadata.permissions))
+            }
+            Column::Mode => colorize_with(&self.raw_value(entry), &theme.colors.permission_read),
+            Column::Inode
+            | Column::Nlink
+            | Column::Dev
+            | Column::Rdev
+            | Column::Blocks
+            | Column::Blksize
+            | Column::Gid => colorize_with(&self.raw_value(entry), &theme.colors.group),
+            Column::Uid => colorize_with(&self.raw_value(entry), &theme.colors.user),
+            Column::User => colorize_user(&self.raw_value(entry)).to_string(),
+            Column::Group => colorize_group(&self.raw_value(entry)).to_string(),
+            Column::Plugins => plugin_manager.format_fields(entry, format).join(" "),
+            Column::Path | Column::Type | Column::Field(_) => self.raw_value(entry),
+        }
+    }
+}
+
+/// The plugin field a `field:NAME` or `plugin.NAME` column refers to.
+pub fn field_name(name: &str) -> Option<&str> {
+    name.strip_prefix("field:")
+        .or_else(|| name.strip_prefix("plugin."))
+        .filter(|key| !key.is_empty())
+}
+
+pub fn unknown_column(name: &str) -> String {
+    format!(
+        "unknown column '{}' (use 'field:{}' for a plugin field)",
+        name, name
+    )
+}
+
+pub fn visible_width(s: &str) -> usize {
+    String::from_utf8_lossy(&strip_ansi_escapes::strip(s).unwrap_or_default()).width()
+}
+
+pub fn pad_cell(content: &str, width: usize, align_right: bool) -> String {
+    let padding = " ".repeat(width.saturating_sub(visible_width(content)));
+    if align_right {
+        format!("{}{}", padding, content)
+    } else {
+        format!("{}{}", content, padding)
+    }
+}
+
+pub fn user_name(uid: u32) -> String {
+    let mut cache = USER_CACHE.lock().unwrap();
+    cache
+        .entry(uid)
+        .or_insert_with(|| {
+            get_user_by_uid(uid)
+                .map(|u| u.name().to_string_lossy().into_owned())
+                .unwrap_or_else(|| uid.to_string())
+        })
+        .clone()
+}
+
+pub fn group_name(gid: u32) -> String {
+    let mut cache = GROUP_CACHE.lock().unwrap();
+    cache
+        .entry(gid)
+        .or_insert_with(|| {
+            get_group_by_gid(gid)
+                .map(|g| g.name().to_string_lossy().into_owned())
+                .unwrap_or_else(|| gid.to_string())
+        })
+        .clone()
+}
+
+fn colorize_with(value: &str, color: &ColorValue) -> String {
+    if theme::is_no_color() {
+        value.to_string()
+    } else {
+        value.color(theme::color_value_to_color(color)).to_string()
+    }
+}
+
+/// Renders a device number as `major,minor`, like `ls -l` does for device files.
+fn format_device(dev: u64) -> String {
+    let (major, minor) = if cfg!(target_os = "linux") {
+        (
+            ((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0x0000_0fff),
+            ((dev >> 12) & 0xffff_ff00) | (dev & 0x0000_00ff),
+        )
+    } else {
+        ((dev >> 24) & 0xff, dev & 0x00ff_ffff)
+    };
+    format!("{},{}", major, minor)
+}
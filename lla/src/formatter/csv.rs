@@ -0,0 +1,171 @@
+use super::column::Column;
+use super::FileFormatter;
+use crate::error::Result;
+use crate::plugin::PluginManager;
+use lla_plugin_interface::proto::DecoratedEntry;
+use std::collections::BTreeSet;
+
+const DEFAULT_COLUMNS: [Column; 6] = [
+    Column::Path,
+    Column::Size,
+    Column::Time,
+    Column::Permissions,
+    Column::Uid,
+    Column::Gid,
+];
+
+pub struct CsvFormatter {
+    pub delimiter: char,
+    pub columns: Option<Vec<Column>>,
+}
+
+impl CsvFormatter {
+    pub fn new(delimiter: char, columns: Option<Vec<Column>>) -> Self {
+        Self { delimiter, columns }
+    }
+
+    fn resolve_columns(&self, files: &[DecoratedEntry]) -> Vec<Column> {
+        if let Some(columns) = &self.columns {
+            return columns.clone();
+        }
+
+        let plugin_fields: BTreeSet<&String> = files
+            .iter()
+            .flat_map(|entry| entry.custom_fields.keys())
+            .collect();
+
+        DEFAULT_COLUMNS
+            .iter()
+            .cloned()
+            .chain(plugin_fields.into_iter().cloned().map(Column::Field))
+            .collect()
+    }
+
+    fn escape(&self, value: &str) -> String {
+        if self.delimiter == '\t' {
+            return value
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r");
+        }
+
+        if value.contains(self.delimiter)
+            || value.contains('"')
+            || value.contains('\n')
+            || value.contains('\r')
+        {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    fn format_row(&self, values: impl Iterator<Item = String>) -> String {
+        values
+            .map(|value| self.escape(&value))
+            .collect::<Vec<_>>()
+            .join(&self.delimiter.to_string())
+    }
+}
+
+impl FileFormatter for CsvFormatter {
+    fn format_files(
+        &self,
+        files: &[DecoratedEntry],
+        _plugin_manager: &mut PluginManager,
+        _depth: Option<usize>,
+    ) -> Result<String> {
+        let columns = self.resolve_columns(files);
+
+        let mut rows = Vec::with_capacity(files.len() + 1);
+        rows.push(self.format_row(columns.iter().map(Column::header)));
+        for entry in files {
+            rows.push(self.format_row(columns.iter().map(|column| column.raw_value(entry))));
+        }
+
+        Ok(rows.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use lla_plugin_interface::proto::EntryMetadata;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    fn entry(path: &Path, note: &str) -> DecoratedEntry {
+        let metadata = EntryMetadata {
+            size: 42,
+            is_file: true,
+            ..Default::default()
+        };
+        let fields = [("note".to_string(), note.to_string())].into();
+        DecoratedEntry::new(path, Some(metadata), fields)
+    }
+
+    fn format(delimiter: char, files: &[DecoratedEntry]) -> String {
+        let columns = vec![
+            Column::Name,
+            Column::Size,
+            Column::Field("note".to_string()),
+        ];
+        CsvFormatter::new(delimiter, Some(columns))
+            .format_files(files, &mut PluginManager::new(Config::default()), None)
+            .unwrap()
+    }
+
+    #[test]
+    fn quotes_csv_fields_that_need_it() {
+        let files = [
+            entry(Path::new("plain.txt"), "a\tb"),
+            entry(Path::new("a,b.txt"), "say \"hi\""),
+            entry(Path::new("two\nlines"), "cr\r"),
+        ];
+        assert_eq!(
+            format(',', &files),
+            "name,size,note\n\
+             plain.txt,42,a\tb\n\
+             \"a,b.txt\",42,\"say \"\"hi\"\"\"\n\
+             \"two\nlines\",42,\"cr\r\""
+        );
+    }
+
+    #[test]
+    fn escapes_tsv_fields() {
+        let files = [
+            entry(Path::new("a\tb"), "back\\slash"),
+            entry(Path::new("a,\"b\""), "two\nlines\r"),
+        ];
+        assert_eq!(
+            format('\t', &files),
+            "name\tsize\tnote\n\
+             a\\tb\t42\tback\\\\slash\n\
+             a,\"b\"\t42\ttwo\\nlines\\r"
+        );
+    }
+
+    #[test]
+    fn writes_names_that_are_not_utf8() {
+        let files = [entry(Path::new(OsStr::from_bytes(b"caf\xe9,")), "")];
+        assert_eq!(format(',', &files), "name,size,note\n\"caf\u{fffd},\",42,");
+    }
+
+    #[test]
+    fn appends_plugin_fields_to_the_default_columns() {
+        let formatter = CsvFormatter::new(',', None);
+        let mut files = [entry(Path::new("a"), "x"), entry(Path::new("b"), "y")];
+        files[1]
+            .custom_fields
+            .insert("category".to_string(), "Image".to_string());
+        let headers: Vec<String> = formatter
+            .resolve_columns(&files)
+            .iter()
+            .map(Column::header)
+            .collect();
+        assert_eq!(headers[DEFAULT_COLUMNS.len()..], ["category", "note"]);
+    }
+}
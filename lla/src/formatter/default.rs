@@ -3,8 +3,8 @@ use crate::error::Result;
 use crate::plugin::PluginManager;
 use crate::utils::color::{colorize_file_name, colorize_file_name_with_icon};
 use crate::utils::icons::format_with_icon;
+use crate::utils::symlink::symlink_suffix;
 use lla_plugin_interface::proto::DecoratedEntry;
-use std::path::Path;
 pub struct DefaultFormatter {
     pub show_icons: bool,
 }
@@ -24,13 +24,16 @@ impl FileFormatter for DefaultFormatter {
         Ok(files
             .iter()
             .map(|file| {
-                let path = Path::new(&file.path);
+                let path = &file.fs_path();
                 let colored_name = colorize_file_name(path).to_string();
-                let name_with_icon = colorize_file_name_with_icon(
-                    path,
-                    format_with_icon(path, colored_name, self.show_icons),
-                )
-                .to_string();
+                let name_with_icon = format!(
+                    "{}{}",
+                    colorize_file_name_with_icon(
+                        path,
+                        format_with_icon(path, colored_name, self.show_icons),
+                    ),
+                    symlink_suffix(path)
+                );
                 let plugin_fields = plugin_manager.format_fields(file, "default").join(" ");
                 if plugin_fields.is_empty() {
                     name_with_icon
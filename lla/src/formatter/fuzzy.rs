@@ -3,12 +3,12 @@ use crate::error::Result;
 use crate::plugin::PluginManager;
 use crate::utils::color::*;
 use crate::utils::icons::format_with_icon;
+use crate::utils::quoting::quote_name;
+use crate::utils::time::{time_field, to_system_time};
 use colored::*;
 use lla_plugin_interface::proto::DecoratedEntry;
 use std::fs::Permissions;
 use std::os::unix::fs::PermissionsExt;
-use std::path::Path;
-use std::time::{Duration, SystemTime};
 
 pub struct FuzzyFormatter {
     pub show_icons: bool,
@@ -27,7 +27,7 @@ impl FuzzyFormatter {
         _plugin_manager: &mut PluginManager,
     ) -> String {
         let metadata = entry.metadata.as_ref().cloned().unwrap_or_default();
-        let path = Path::new(&entry.path);
+        let path = &entry.fs_path();
 
         let colored_name = colorize_file_name(path).to_string();
         let name_display = if self.show_icons {
@@ -43,8 +43,7 @@ impl FuzzyFormatter {
         let perms = Permissions::from_mode(metadata.permissions);
         let perms_display = colorize_permissions(&perms);
         let size = colorize_size(metadata.size);
-        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(metadata.modified);
-        let date = colorize_date(&modified);
+        let date = colorize_date(&to_system_time(time_field().timestamp(entry)));
 
         let prefix = if selected { "→" } else { " " };
 
@@ -52,7 +51,7 @@ impl FuzzyFormatter {
             "  {} {}  {}  {}  {}  {}",
             prefix,
             name_display,
-            entry.path.bright_black(),
+            quote_name(entry.fs_path().as_os_str()).bright_black(),
             perms_display,
             size,
             date
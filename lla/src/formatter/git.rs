@@ -1,3 +1,4 @@
+use super::column::{field_name, unknown_column};
 use super::FileFormatter;
 use crate::error::Result;
 use crate::plugin::PluginManager;
@@ -7,22 +8,97 @@ use crate::utils::icons::format_with_icon;
 use colored::*;
 use console::strip_ansi_codes;
 use lla_plugin_interface::proto::DecoratedEntry;
-use std::collections::HashMap;
-use std::path::Path;
-use std::process::Command;
+use lla_plugin_utils::git::{self, Repository};
 use unicode_width::UnicodeWidthStr;
 
+/// A column of the git view, shown after each entry's name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitColumn {
+    Commit,
+    Author,
+    Date,
+    Churn,
+    Lines,
+    Status,
+    Field(String),
+}
+
+impl GitColumn {
+    pub fn from_name(name: &str) -> std::result::Result<Self, String> {
+        let column = match name {
+            "commit" | "hash" => GitColumn::Commit,
+            "author" => GitColumn::Author,
+            "date" | "time" => GitColumn::Date,
+            "churn" | "commits" => GitColumn::Churn,
+            "lines" | "changes" => GitColumn::Lines,
+            "status" => GitColumn::Status,
+            other => match field_name(other) {
+                Some(key) => GitColumn::Field(key.to_string()),
+                None => return Err(unknown_column(other)),
+            },
+        };
+        Ok(column)
+    }
+
+    /// Parses a comma-separated list of columns. The name always comes first,
+    /// so `name` is skipped.
+    pub fn parse_list(spec: &str) -> std::result::Result<Vec<GitColumn>, String> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && *name != "name")
+            .map(GitColumn::from_name)
+            .collect()
+    }
+
+    pub fn from_names(names: &[String]) -> std::result::Result<Option<Vec<GitColumn>>, String> {
+        if names.is_empty() {
+            Ok(None)
+        } else {
+            GitColumn::parse_list(&names.join(",")).map(Some)
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            GitColumn::Commit => "Commit".to_string(),
+            GitColumn::Author => "Author".to_string(),
+            GitColumn::Date => "Date".to_string(),
+            GitColumn::Churn => "Churn".to_string(),
+            GitColumn::Lines => "Lines".to_string(),
+            GitColumn::Status => "Status".to_string(),
+            GitColumn::Field(key) => key.clone(),
+        }
+    }
+
+    fn align_right(&self) -> bool {
+        matches!(self, GitColumn::Churn)
+    }
+
+    fn needs_history(&self) -> bool {
+        matches!(self, GitColumn::Churn)
+    }
+}
+
 pub struct GitFormatter {
     pub show_icons: bool,
+    pub columns: Vec<GitColumn>,
 }
 
 impl GitFormatter {
-    pub fn new(show_icons: bool) -> Self {
-        Self { show_icons }
+    pub fn new(show_icons: bool, columns: Option<Vec<GitColumn>>) -> Self {
+        Self {
+            show_icons,
+            columns: columns.unwrap_or_else(Self::default_columns),
+        }
     }
 
-    fn strip_ansi(s: &str) -> String {
-        String::from_utf8(strip_ansi_escapes::strip(s).unwrap_or_default()).unwrap_or_default()
+    fn default_columns() -> Vec<GitColumn> {
+        vec![
+            GitColumn::Commit,
+            GitColumn::Date,
+            GitColumn::Author,
+            GitColumn::Status,
+        ]
     }
 
     fn get_theme_color(value: &ColorValue) -> Color {
@@ -30,46 +106,20 @@ impl GitFormatter {
     }
 }
 #[derive(Debug, Default)]
-struct GitInfo {
-    branch: String,
-    ahead: usize,
-    behind: usize,
+pub(super) struct GitInfo {
+    pub(super) branch: String,
+    pub(super) ahead: usize,
+    pub(super) behind: usize,
 }
 
 impl GitFormatter {
-    fn get_git_info(path: &Path) -> Option<GitInfo> {
-        let status = Command::new("git")
-            .arg("rev-parse")
-            .arg("--is-inside-work-tree")
-            .current_dir(path)
-            .output()
-            .ok()?;
-
-        if !status.status.success() {
-            return None;
+    pub(super) fn get_git_info(repo: &Repository) -> GitInfo {
+        let (ahead, behind) = repo.ahead_behind().unwrap_or((0, 0));
+        GitInfo {
+            branch: repo.branch().unwrap_or_else(|| "(detached)".to_string()),
+            ahead,
+            behind,
         }
-
-        let mut info = GitInfo::default();
-
-        if let Ok(output) = Command::new("git")
-            .args(["status", "-b", "--porcelain=v2"])
-            .current_dir(path)
-            .output()
-        {
-            for line in String::from_utf8_lossy(&output.stdout).lines() {
-                if line.starts_with("# branch.head ") {
-                    info.branch = line.split_whitespace().nth(2)?.to_string();
-                } else if line.starts_with("# branch.ab ") {
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 4 {
-                        info.ahead = parts[2].trim_start_matches('+').parse().unwrap_or(0);
-                        info.behind = parts[3].trim_start_matches('-').parse().unwrap_or(0);
-                    }
-                }
-            }
-        }
-
-        Some(info)
     }
 
     fn format_git_status(status: &str) -> (String, String) {
@@ -111,84 +161,15 @@ impl GitFormatter {
         (status_str.to_string(), "".to_string())
     }
 
-    fn get_git_status_map(workspace_root: &Path) -> HashMap<String, String> {
-        let mut status_map = HashMap::new();
-
-        if let Ok(output) = Command::new("git")
-            .args(["status", "--porcelain=v2", "--untracked-files=all"])
-            .current_dir(workspace_root)
-            .output()
-        {
-            for line in String::from_utf8_lossy(&output.stdout).lines() {
-                if line.is_empty() || line.starts_with('#') {
-                    continue;
-                }
-
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.is_empty() {
-                    continue;
-                }
-
-                match parts[0] {
-                    "1" | "2" if parts.len() >= 9 => {
-                        let xy = parts[1];
-                        let path = parts[8];
-                        status_map.insert(path.to_string(), xy.to_string());
-                    }
-                    "?" if parts.len() >= 2 => {
-                        status_map.insert(parts[1].to_string(), "??".to_string());
-                    }
-                    "!" if parts.len() >= 2 => {
-                        status_map.insert(parts[1].to_string(), "!!".to_string());
-                    }
-                    _ => {}
-                }
-            }
-        }
-
-        if let Ok(output) = Command::new("git")
-            .args(["ls-files"])
-            .current_dir(workspace_root)
-            .output()
-        {
-            for line in String::from_utf8_lossy(&output.stdout).lines() {
-                status_map
-                    .entry(line.to_string())
-                    .or_insert_with(|| ".".to_string());
-            }
-        }
-
-        status_map
-    }
-
-    fn get_last_commit_info(path: &Path, file_path: &Path) -> Option<(String, String, String)> {
-        let output = Command::new("git")
-            .args([
-                "log",
-                "-1",
-                "--format=%h|%s|%cr|%an",
-                "--",
-                file_path.to_str()?,
-            ])
-            .current_dir(path)
-            .output()
-            .ok()?;
-
-        if !output.status.success() {
-            return None;
-        }
-
-        let log = String::from_utf8_lossy(&output.stdout);
-        let parts: Vec<&str> = log.trim().split('|').collect();
-        if parts.len() >= 4 {
-            Some((
-                parts[0].to_string(),
-                parts[2].to_string(),
-                parts[3].to_string(),
-            ))
-        } else {
-            None
+    fn format_line_changes((added, removed): (usize, usize)) -> String {
+        if added == 0 && removed == 0 {
+            return String::new();
         }
+        format!(
+            "{} {}",
+            format!("+{}", added).green(),
+            format!("-{}", removed).red()
+        )
     }
 }
 
@@ -212,37 +193,117 @@ impl FileFormatter for GitFormatter {
         let time_color = Self::get_theme_color(&ColorValue::Named("bright black".to_string()));
         let author_color = Self::get_theme_color(&theme.colors.user);
 
-        let workspace_root = Path::new(&files[0].path)
-            .ancestors()
-            .find(|p| p.join(".git").exists())
-            .unwrap_or(Path::new("."));
-
-        let git_info = match GitFormatter::get_git_info(workspace_root) {
-            Some(info) => info,
+        let repo = match Repository::discover(&files[0].fs_path()) {
+            Some(repo) => repo,
             None => return Ok("Not a git repository".red().to_string()),
         };
-
-        let status_map = GitFormatter::get_git_status_map(workspace_root);
-        let mut max_name_width: usize = 0;
-        let mut max_hash_width: usize = 0;
-        let mut max_time_width: usize = 0;
-        let mut max_author_width: usize = 0;
-
-        for file in files {
-            let path = Path::new(&file.path);
-            let name = colorize_file_name(path);
-            let name_with_icon = colorize_file_name_with_icon(
-                path,
-                format_with_icon(path, name.to_string(), self.show_icons),
-            );
-            let commit_info = GitFormatter::get_last_commit_info(workspace_root, path)
-                .unwrap_or_else(|| ("-".to_string(), "never".to_string(), "-".to_string()));
-
-            max_name_width = max_name_width.max(Self::strip_ansi(&name_with_icon).width());
-            max_hash_width = max_hash_width.max(commit_info.0.len());
-            max_time_width = max_time_width.max(commit_info.1.len());
-            max_author_width = max_author_width.max(commit_info.2.len());
-        }
+        let git_info = GitFormatter::get_git_info(&repo);
+        let status = repo.status();
+        let relative_paths: Vec<String> = files
+            .iter()
+            .map(|file| repo.relative_path(&file.fs_path()).unwrap_or_default())
+            .collect();
+        let history = self
+            .columns
+            .iter()
+            .any(GitColumn::needs_history)
+            .then(|| repo.history_of(relative_paths.iter().map(String::as_str)));
+
+        // Without churn there is no history to reuse, so the commit columns
+        // walk back to each path's last commit and stop there.
+        let shows_commit = self.columns.iter().any(|column| {
+            matches!(
+                column,
+                GitColumn::Commit | GitColumn::Author | GitColumn::Date
+            )
+        });
+        let rows: Vec<(String, Vec<String>)> = files
+            .iter()
+            .zip(&relative_paths)
+            .map(|(file, relative_path)| {
+                let path = &file.fs_path();
+                let name = colorize_file_name(path);
+                let name_with_icon = colorize_file_name_with_icon(
+                    path,
+                    format_with_icon(path, name.to_string(), self.show_icons),
+                )
+                .to_string();
+
+                let commit = match &history {
+                    Some(history) => history
+                        .last_commit_index(relative_path)
+                        .and_then(|index| repo.commits().get(index).cloned()),
+                    None if shows_commit => repo.last_commit(relative_path),
+                    None => None,
+                };
+                let cells = self
+                    .columns
+                    .iter()
+                    .map(|column| match column {
+                        GitColumn::Commit => commit
+                            .as_ref()
+                            .map(|commit| commit.oid.short())
+                            .unwrap_or_else(|| "-".to_string())
+                            .color(hash_color)
+                            .to_string(),
+                        GitColumn::Author => commit
+                            .as_ref()
+                            .map(|commit| commit.author.clone())
+                            .unwrap_or_else(|| "-".to_string())
+                            .color(author_color)
+                            .to_string(),
+                        GitColumn::Date => commit
+                            .as_ref()
+                            .map(|commit| git::relative_time(commit.commit_time))
+                            .unwrap_or_else(|| "never".to_string())
+                            .color(time_color)
+                            .to_string(),
+                        GitColumn::Churn => history
+                            .as_ref()
+                            .map_or(0, |history| history.churn(relative_path))
+                            .to_string(),
+                        GitColumn::Lines => {
+                            GitFormatter::format_line_changes(repo.line_changes(relative_path))
+                        }
+                        GitColumn::Status => status
+                            .get(relative_path)
+                            .map(|status| GitFormatter::format_git_status(status).0)
+                            .unwrap_or_default(),
+                        GitColumn::Field(key) => {
+                            file.custom_fields.get(key).cloned().unwrap_or_default()
+                        }
+                    })
+                    .collect();
+                (name_with_icon, cells)
+            })
+            .collect();
+
+        let name_width = rows
+            .iter()
+            .map(|(name, _)| strip_ansi_codes(name).width())
+            .chain(std::iter::once("Name".len()))
+            .max()
+            .unwrap_or(0);
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                rows.iter()
+                    .map(|(_, cells)| strip_ansi_codes(&cells[i]).width())
+                    .chain(std::iter::once(column.title().width()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let pad = |content: &str, width: usize, align_right: bool| {
+            let padding = " ".repeat(width.saturating_sub(strip_ansi_codes(content).width()));
+            if align_right {
+                format!("{}{}", padding, content)
+            } else {
+                format!("{}{}", content, padding)
+            }
+        };
 
         let mut output = format!(
             "\n{} {}{}{}\n{}\n",
@@ -261,73 +322,36 @@ impl FileFormatter for GitFormatter {
             "─".repeat(40).color(separator_color)
         );
 
-        output.push_str(&format!(
-            "{}  {}  {}  {}  {}\n",
-            "Name".bold(),
-            "Commit".bold(),
-            "Time".bold(),
-            "Author".bold(),
-            "Status".bold()
-        ));
+        let mut header = vec![pad("Name", name_width, false).bold().to_string()];
+        for (column, &width) in self.columns.iter().zip(&widths) {
+            header.push(
+                pad(&column.title(), width, column.align_right())
+                    .bold()
+                    .to_string(),
+            );
+        }
+        output.push_str(header.join("  ").trim_end());
+        output.push('\n');
         output.push_str(
             &"─"
-                .repeat(max_name_width + max_hash_width + max_time_width + max_author_width + 40)
+                .repeat(name_width + widths.iter().map(|width| width + 2).sum::<usize>())
                 .bright_black(),
         );
         output.push('\n');
 
-        let mut entries = Vec::new();
-        for file in files {
-            let path = Path::new(&file.path);
-            let name = colorize_file_name(path);
-            let name_with_icon = colorize_file_name_with_icon(
-                path,
-                format_with_icon(path, name.to_string(), self.show_icons),
-            );
-
-            let relative_path = path.strip_prefix(workspace_root).unwrap_or(path);
-            let relative_path_str = relative_path.to_string_lossy();
-
-            let (status_str, _) = status_map
-                .get(relative_path_str.as_ref())
-                .map(|s| GitFormatter::format_git_status(s))
-                .unwrap_or_else(|| ("".to_string(), "".to_string()));
-
-            let commit_info = GitFormatter::get_last_commit_info(workspace_root, relative_path)
-                .unwrap_or_else(|| ("-".to_string(), "never".to_string(), "-".to_string()));
+        for (file, (name, cells)) in files.iter().zip(rows) {
+            let mut line = vec![pad(&name, name_width, false)];
+            for ((column, cell), &width) in self.columns.iter().zip(cells).zip(&widths) {
+                line.push(pad(&cell, width, column.align_right()));
+            }
+            let mut line = line.join("  ").trim_end().to_string();
 
             let plugin_fields = plugin_manager.format_fields(file, "git").join(" ");
-
-            entries.push((name_with_icon, commit_info, status_str, plugin_fields));
-        }
-
-        for (name, commit_info, status, plugin_fields) in entries {
-            let name_width = strip_ansi_codes(&name).width();
-            let name_padding = " ".repeat(max_name_width.saturating_sub(name_width) as usize);
-
-            let hash_padding = " ".repeat(max_hash_width.saturating_sub(commit_info.0.len()));
-            let time_padding = " ".repeat(max_time_width.saturating_sub(commit_info.1.len()));
-
-            output.push_str(&format!(
-                "{}{}  @{}{}  {}{}  {}{}{}",
-                name,
-                name_padding,
-                commit_info.0.color(hash_color),
-                hash_padding,
-                commit_info.1.color(time_color),
-                time_padding,
-                if commit_info.2 != "-" {
-                    format!("by {} ", commit_info.2.color(author_color))
-                } else {
-                    "".into()
-                },
-                status,
-                if plugin_fields.is_empty() {
-                    String::new()
-                } else {
-                    format!(" {}", plugin_fields)
-                }
-            ));
+            if !plugin_fields.is_empty() {
+                line.push(' ');
+                line.push_str(&plugin_fields);
+            }
+            output.push_str(&line);
             output.push('\n');
         }
 
@@ -4,7 +4,6 @@ use crate::utils::color::colorize_file_name;
 use crate::utils::icons::format_with_icon;
 use crate::{error::Result, utils::color::colorize_file_name_with_icon};
 use lla_plugin_interface::proto::DecoratedEntry;
-use std::path::Path;
 use terminal_size::{terminal_size, Width};
 use unicode_width::UnicodeWidthStr;
 
@@ -36,7 +35,7 @@ impl FileFormatter for GridFormatter {
         let mut max_width = 0;
 
         for file in files {
-            let path = Path::new(&file.path);
+            let path = &file.fs_path();
             let colored_name = colorize_file_name(path).to_string();
             let name_with_icon = colorize_file_name_with_icon(
                 path,
@@ -0,0 +1,237 @@
+use super::{user_name, FileFormatter};
+use crate::error::Result;
+use crate::plugin::PluginManager;
+use crate::theme::{self, ColorValue};
+use crate::utils::color;
+use crate::utils::git::{self, StatusRanks};
+use crate::utils::size::{entry_size, format_size};
+use crate::utils::time::{time_field, to_system_time};
+use chrono::{DateTime, Duration, Local};
+use colored::*;
+use lla_plugin_interface::proto::DecoratedEntry;
+use lla_plugin_interface::FileType;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupBy {
+    Extension,
+    Type,
+    Owner,
+    Category,
+    GitStatus,
+    DateBucket,
+    Field(String),
+}
+
+impl GroupBy {
+    pub const VALUES: [&'static str; 7] = [
+        "extension",
+        "type",
+        "owner",
+        "category",
+        "git-status",
+        "date-bucket",
+        "field:NAME",
+    ];
+
+    pub fn parse(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "extension" | "ext" => Ok(GroupBy::Extension),
+            "type" => Ok(GroupBy::Type),
+            "owner" | "user" => Ok(GroupBy::Owner),
+            "category" => Ok(GroupBy::Category),
+            "git-status" | "git" => Ok(GroupBy::GitStatus),
+            "date-bucket" | "date" => Ok(GroupBy::DateBucket),
+            value => match value.strip_prefix("field:") {
+                Some(name) if !name.is_empty() => Ok(GroupBy::Field(name.to_string())),
+                _ => Err(format!("must be one of: {}", Self::VALUES.join(", "))),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub(super) enum TimeGroup {
+    Today,
+    Yesterday,
+    LastWeek,
+    LastMonth,
+    Older,
+}
+
+impl TimeGroup {
+    pub(super) fn from_datetime(dt: DateTime<Local>) -> Self {
+        let now = Local::now();
+        let today = now.date_naive();
+        let yesterday = today - Duration::days(1);
+        let last_week = today - Duration::days(7);
+        let last_month = today - Duration::days(30);
+
+        let file_date = dt.date_naive();
+
+        if file_date == today {
+            TimeGroup::Today
+        } else if file_date == yesterday {
+            TimeGroup::Yesterday
+        } else if file_date > last_week {
+            TimeGroup::LastWeek
+        } else if file_date > last_month {
+            TimeGroup::LastMonth
+        } else {
+            TimeGroup::Older
+        }
+    }
+
+    pub(super) fn display_name(&self) -> &'static str {
+        match self {
+            TimeGroup::Today => "Today",
+            TimeGroup::Yesterday => "Yesterday",
+            TimeGroup::LastWeek => "Last Week",
+            TimeGroup::LastMonth => "Last Month",
+            TimeGroup::Older => "Older",
+        }
+    }
+}
+
+/// A section heading and where it goes: groups are ordered by `rank`, then by
+/// title. Groups for entries without a value rank last.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct GroupKey {
+    rank: u8,
+    title: String,
+}
+
+impl GroupKey {
+    fn new(rank: u8, title: impl Into<String>) -> Self {
+        Self {
+            rank,
+            title: title.into(),
+        }
+    }
+
+    fn value_or(value: Option<String>, missing: &str) -> Self {
+        match value.filter(|value| !value.is_empty()) {
+            Some(value) => Self::new(0, value),
+            None => Self::new(1, missing),
+        }
+    }
+}
+
+/// Splits the listing into headed sections, one per group, each with its
+/// entry count and total size. Entries keep their sorted order within a
+/// section, and each section is rendered by the wrapped formatter.
+pub struct GroupedFormatter {
+    inner: Box<dyn FileFormatter>,
+    group_by: GroupBy,
+}
+
+impl GroupedFormatter {
+    pub fn new(inner: Box<dyn FileFormatter>, group_by: GroupBy) -> Self {
+        Self { inner, group_by }
+    }
+
+    fn get_header_color() -> Color {
+        let theme = color::get_theme();
+        theme::color_value_to_color(&theme.colors.directory)
+    }
+
+    fn get_separator_color() -> Color {
+        theme::color_value_to_color(&ColorValue::Named("bright black".to_string()))
+    }
+
+    fn group_key(&self, entry: &DecoratedEntry, git: Option<&StatusRanks>) -> GroupKey {
+        let metadata = entry.metadata.clone().unwrap_or_default();
+        match &self.group_by {
+            GroupBy::Extension => GroupKey::value_or(
+                entry
+                    .fs_path()
+                    .extension()
+                    .map(|ext| format!(".{}", ext.to_string_lossy().to_lowercase())),
+                "No extension",
+            ),
+            GroupBy::Type => {
+                let (rank, title) = match metadata.kind() {
+                    FileType::Directory => (0, "Directories"),
+                    FileType::Symlink => (1, "Symlinks"),
+                    FileType::Regular => (2, "Files"),
+                    FileType::Fifo => (3, "FIFOs"),
+                    FileType::Socket => (4, "Sockets"),
+                    FileType::BlockDevice => (5, "Block devices"),
+                    FileType::CharDevice => (6, "Character devices"),
+                    FileType::Unknown => (7, "Other"),
+                };
+                GroupKey::new(rank, title)
+            }
+            GroupBy::Owner => GroupKey::new(0, user_name(metadata.uid)),
+            GroupBy::Category => GroupKey::value_or(
+                entry.custom_fields.get("category").cloned(),
+                "Uncategorized",
+            ),
+            GroupBy::GitStatus => {
+                let rank =
+                    git.map_or_else(|| git::status_rank(None), |git| git.rank(&entry.fs_path()));
+                GroupKey::new(rank, git::rank_title(rank))
+            }
+            GroupBy::DateBucket => {
+                let dt = DateTime::<Local>::from(to_system_time(time_field().timestamp(entry)));
+                let group = TimeGroup::from_datetime(dt);
+                GroupKey::new(group as u8, group.display_name())
+            }
+            GroupBy::Field(name) => {
+                GroupKey::value_or(entry.custom_fields.get(name).cloned(), "(none)")
+            }
+        }
+    }
+}
+
+impl FileFormatter for GroupedFormatter {
+    fn format_files(
+        &self,
+        files: &[DecoratedEntry],
+        plugin_manager: &mut PluginManager,
+        depth: Option<usize>,
+    ) -> Result<String> {
+        if files.is_empty() {
+            return Ok(String::new());
+        }
+
+        let git = match self.group_by {
+            GroupBy::GitStatus => StatusRanks::load(&files[0].fs_path()),
+            _ => None,
+        };
+
+        let mut groups: HashMap<GroupKey, Vec<DecoratedEntry>> = HashMap::new();
+        for file in files {
+            groups
+                .entry(self.group_key(file, git.as_ref()))
+                .or_default()
+                .push(file.clone());
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut output = String::new();
+        for (key, entries) in groups {
+            let total: u64 = entries.iter().map(entry_size).sum();
+            let count = if entries.len() == 1 {
+                "1 entry".to_string()
+            } else {
+                format!("{} entries", entries.len())
+            };
+            let summary = format!("{}, {}", count, format_size(total));
+
+            output.push_str(&format!(
+                "\n{} {}\n{}\n",
+                key.title.color(Self::get_header_color()).bold(),
+                summary.color(Self::get_separator_color()),
+                "─".repeat(40).color(Self::get_separator_color())
+            ));
+
+            let section = self.inner.format_files(&entries, plugin_manager, depth)?;
+            output.push_str(section.trim_end_matches('\n'));
+            output.push('\n');
+        }
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,128 @@
+use super::FileFormatter;
+use crate::error::Result;
+use crate::plugin::PluginManager;
+use lla_plugin_interface::proto::DecoratedEntry;
+use serde_json::Value;
+
+pub struct JsonFormatter {
+    pub ndjson: bool,
+}
+
+impl JsonFormatter {
+    pub fn new(ndjson: bool) -> Self {
+        Self { ndjson }
+    }
+
+    /// Non-UTF-8 paths can't be represented as JSON strings, so `path` holds
+    /// the lossy form and the exact bytes are emitted separately as `raw_path`.
+    fn entry_to_value(entry: &DecoratedEntry) -> Result<Value> {
+        let mut entry = entry.clone();
+        let raw_path = entry.raw_path.take();
+        let entry = lla_plugin_interface::DecoratedEntry::try_from(entry)?;
+        let mut value = serde_json::to_value(entry)?;
+        if let (Some(raw_path), Value::Object(object)) = (raw_path, &mut value) {
+            object.insert("raw_path".to_string(), Value::from(raw_path));
+        }
+        Ok(value)
+    }
+}
+
+impl FileFormatter for JsonFormatter {
+    fn format_files(
+        &self,
+        files: &[DecoratedEntry],
+        _plugin_manager: &mut PluginManager,
+        _depth: Option<usize>,
+    ) -> Result<String> {
+        let values = files
+            .iter()
+            .map(Self::entry_to_value)
+            .collect::<Result<Vec<_>>>()?;
+
+        if self.ndjson {
+            Ok(values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"))
+        } else {
+            Ok(serde_json::to_string_pretty(&values)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use lla_plugin_interface::proto::EntryMetadata;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    fn entry(path: &Path) -> DecoratedEntry {
+        let metadata = EntryMetadata {
+            size: 7,
+            is_file: true,
+            ..Default::default()
+        };
+        let fields = [("note".to_string(), "a\nb".to_string())].into();
+        DecoratedEntry::new(path, Some(metadata), fields)
+    }
+
+    fn format(ndjson: bool, files: &[DecoratedEntry]) -> String {
+        JsonFormatter::new(ndjson)
+            .format_files(files, &mut PluginManager::new(Config::default()), None)
+            .unwrap()
+    }
+
+    #[test]
+    fn writes_one_object_per_line_as_ndjson() {
+        let files = [entry(Path::new("a.txt")), entry(Path::new("two\nlines"))];
+        let output = format(true, &files);
+        let lines: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["path"], "a.txt");
+        assert_eq!(lines[1]["path"], "two\nlines");
+        assert_eq!(lines[1]["metadata"]["size"], 7);
+        assert_eq!(lines[1]["custom_fields"]["note"], "a\nb");
+    }
+
+    #[test]
+    fn writes_an_array_otherwise() {
+        let files = [entry(Path::new("a.txt")), entry(Path::new("b.txt"))];
+        let value: Value = serde_json::from_str(&format(false, &files)).unwrap();
+        let paths: Vec<&Value> = value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| &entry["path"])
+            .collect();
+        assert_eq!(paths, ["a.txt", "b.txt"]);
+        assert_eq!(format(true, &[]), "");
+        assert_eq!(format(false, &[]), "[]");
+    }
+
+    #[test]
+    fn adds_raw_path_only_for_names_that_are_not_utf8() {
+        let files = [
+            entry(Path::new(OsStr::from_bytes(b"dir/caf\xe9"))),
+            entry(Path::new("café")),
+        ];
+        let output = format(true, &files);
+        let lines: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines[0]["path"], "dir/caf\u{fffd}");
+        let raw: Vec<u8> = serde_json::from_value(lines[0]["raw_path"].clone()).unwrap();
+        assert_eq!(raw, b"dir/caf\xe9");
+        assert_eq!(lines[1]["path"], "café");
+        assert!(lines[1].get("raw_path").is_none());
+    }
+}
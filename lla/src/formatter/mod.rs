@@ -11,24 +11,40 @@ pub trait FileFormatter {
     ) -> Result<String>;
 }
 
+mod column;
+mod csv;
 mod default;
 mod fuzzy;
 mod git;
 mod grid;
+mod group;
+mod json;
 mod long;
+mod paths;
 mod recursive;
+mod repos;
 mod sizemap;
 mod table;
+mod template;
 mod timeline;
 mod tree;
+mod treemap;
 
+pub use column::Column;
+pub(crate) use column::{group_name, user_name};
+pub use csv::CsvFormatter;
 pub use default::DefaultFormatter;
 pub use fuzzy::FuzzyFormatter;
-pub use git::GitFormatter;
+pub use git::{GitColumn, GitFormatter};
 pub use grid::GridFormatter;
+pub use group::{GroupBy, GroupedFormatter};
+pub use json::JsonFormatter;
 pub use long::LongFormatter;
+pub use paths::PathsFormatter;
 pub use recursive::RecursiveFormatter;
+pub use repos::ReposFormatter;
 pub use sizemap::SizeMapFormatter;
 pub use table::TableFormatter;
-pub use timeline::TimelineFormatter;
-pub use tree::TreeFormatter;
+pub use template::{Template, TemplateFormatter};
+pub use timeline::{TimeBucket, TimelineFormatter, TimelineOptions};
+pub use tree::{TreeFormatter, TreeOptions, FILE_COUNT_FIELD};
@@ -0,0 +1,34 @@
+use crate::error::Result;
+use lla_plugin_interface::proto::DecoratedEntry;
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
+
+/// Writes bare paths for shell pipelines. Unlike the other formatters this
+/// writes bytes rather than building a `String`, so names that aren't valid
+/// UTF-8 reach `xargs -0` unchanged.
+pub struct PathsFormatter {
+    pub separator: u8,
+}
+
+impl PathsFormatter {
+    pub fn new(null_separated: bool) -> Self {
+        Self {
+            separator: if null_separated { b'\0' } else { b'\n' },
+        }
+    }
+
+    pub fn write_paths(&self, files: &[DecoratedEntry], out: &mut impl Write) -> Result<()> {
+        match self.write_all(files, out) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => Ok(result?),
+        }
+    }
+
+    fn write_all(&self, files: &[DecoratedEntry], out: &mut impl Write) -> io::Result<()> {
+        for entry in files {
+            out.write_all(entry.fs_path().as_os_str().as_bytes())?;
+            out.write_all(&[self.separator])?;
+        }
+        out.flush()
+    }
+}
@@ -4,12 +4,11 @@ use crate::plugin::PluginManager;
 use crate::theme::{self, ColorValue};
 use crate::utils::color::{self, colorize_file_name, colorize_file_name_with_icon};
 use crate::utils::icons::format_with_icon;
-use chrono::{DateTime, Local};
+use crate::utils::time::{time_field, time_style, to_system_time, TimeStyle};
 use colored::*;
 use lla_plugin_interface::proto::DecoratedEntry;
-use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::path::Path;
-use std::time::{Duration, UNIX_EPOCH};
 
 pub struct RecursiveFormatter {
     pub show_icons: bool,
@@ -21,9 +20,9 @@ impl RecursiveFormatter {
     }
 
     fn format_date(timestamp: u64) -> ColoredString {
-        let datetime = UNIX_EPOCH + Duration::from_secs(timestamp);
-        let datetime: DateTime<Local> = DateTime::from(datetime);
-        let date_str = datetime.format("%Y-%m-%d %H:%M").to_string();
+        let date_str = time_style()
+            .unwrap_or(&TimeStyle::LongIso)
+            .format(&to_system_time(timestamp));
         let color = theme::color_value_to_color(&ColorValue::Named("bright black".to_string()));
         date_str.color(color)
     }
@@ -38,9 +37,9 @@ impl RecursiveFormatter {
     }
 
     fn print_entry(&self, entry: &DecoratedEntry, plugin_manager: &mut PluginManager) {
-        if let Some(metadata) = &entry.metadata {
-            let date = Self::format_date(metadata.modified);
-            let path = Path::new(&entry.path);
+        if entry.metadata.is_some() {
+            let date = Self::format_date(time_field().timestamp(entry));
+            let path = &entry.fs_path();
             let colored_name = colorize_file_name(path).to_string();
             let name = colorize_file_name_with_icon(
                 path,
@@ -77,11 +76,18 @@ impl FileFormatter for RecursiveFormatter {
             return Ok(String::new());
         }
 
-        let mut groups: BTreeMap<String, Vec<&DecoratedEntry>> = BTreeMap::new();
+        // Groups appear in the order their first entry does; entries are
+        // sorted so that each directory's children follow the directory.
+        let mut groups: Vec<(String, Vec<&DecoratedEntry>)> = Vec::new();
+        let mut group_index: HashMap<String, usize> = HashMap::new();
 
         for file in files {
             let parent = Self::get_parent_path(&file.path);
-            groups.entry(parent).or_default().push(file);
+            let index = *group_index.entry(parent.clone()).or_insert_with(|| {
+                groups.push((parent, Vec::new()));
+                groups.len() - 1
+            });
+            groups[index].1.push(file);
         }
 
         let single_group = groups.len() == 1;
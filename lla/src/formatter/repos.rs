@@ -0,0 +1,189 @@
+use super::column::{pad_cell, visible_width};
+use super::git::GitFormatter;
+use super::FileFormatter;
+use crate::error::Result;
+use crate::plugin::PluginManager;
+use crate::theme::{self, ColorValue};
+use crate::utils::color;
+use colored::*;
+use lla_plugin_interface::proto::DecoratedEntry;
+use lla_plugin_utils::git::{self, Repository};
+use rayon::prelude::*;
+
+const COLUMNS: [(&str, bool); 8] = [
+    ("Repository", false),
+    ("Branch", false),
+    ("Sync", false),
+    ("Staged", true),
+    ("Dirty", true),
+    ("Untracked", true),
+    ("Stash", true),
+    ("Last commit", false),
+];
+
+/// One row per git work tree found under the listed directory, for keeping
+/// an eye on many checkouts at once.
+pub struct ReposFormatter;
+
+#[derive(Debug, Default)]
+struct RepoSummary {
+    staged: usize,
+    dirty: usize,
+    untracked: usize,
+}
+
+impl ReposFormatter {
+    fn summarize(repo: &Repository) -> RepoSummary {
+        let mut summary = RepoSummary::default();
+        for status in repo.status().entries().values() {
+            let mut codes = status.chars();
+            match (codes.next(), codes.next()) {
+                (Some('?'), Some('?')) => summary.untracked += 1,
+                (Some('!'), Some('!')) => {}
+                (Some('U'), _) => summary.dirty += 1,
+                (Some(staged), Some(unstaged)) => {
+                    if staged != '.' {
+                        summary.staged += 1;
+                    }
+                    if unstaged != '.' {
+                        summary.dirty += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        summary
+    }
+
+    fn count(count: usize, color: Color) -> String {
+        if count == 0 {
+            "·".bright_black().to_string()
+        } else {
+            count.to_string().color(color).to_string()
+        }
+    }
+
+    fn format_row(entry: &DecoratedEntry) -> Option<(bool, Vec<String>)> {
+        let theme = color::get_theme();
+        let directory_color = theme::color_value_to_color(&theme.colors.directory);
+        let branch_color = theme::color_value_to_color(&theme.colors.executable);
+        let staged_color = theme::color_value_to_color(&theme.colors.executable);
+        let dirty_color = theme::color_value_to_color(&ColorValue::Named("yellow".to_string()));
+        let behind_color = theme::color_value_to_color(&ColorValue::Named("red".to_string()));
+        let untracked_color =
+            theme::color_value_to_color(&ColorValue::Named("bright black".to_string()));
+
+        let path = entry.fs_path();
+        let repo = Repository::discover(&path)?;
+        let info = GitFormatter::get_git_info(&repo);
+        let summary = Self::summarize(&repo);
+
+        let mut sync = Vec::new();
+        if info.ahead > 0 {
+            sync.push(format!("↑{}", info.ahead).color(dirty_color).to_string());
+        }
+        if info.behind > 0 {
+            sync.push(format!("↓{}", info.behind).color(behind_color).to_string());
+        }
+        if sync.is_empty() {
+            sync.push(match repo.upstream() {
+                Some(_) => "✓".color(staged_color).to_string(),
+                None => "-".bright_black().to_string(),
+            });
+        }
+
+        let name = entry.path.strip_prefix("./").unwrap_or(&entry.path);
+        let last_commit = repo
+            .head_commit()
+            .map(|commit| git::relative_time(commit.commit_time))
+            .unwrap_or_else(|| "never".to_string());
+
+        let is_dirty = summary.staged + summary.dirty + summary.untracked > 0;
+        Some((
+            is_dirty,
+            vec![
+                name.color(directory_color).bold().to_string(),
+                info.branch.color(branch_color).bold().to_string(),
+                sync.join(" "),
+                Self::count(summary.staged, staged_color),
+                Self::count(summary.dirty, dirty_color),
+                Self::count(summary.untracked, untracked_color),
+                Self::count(repo.stash_count(), dirty_color),
+                last_commit.bright_black().to_string(),
+            ],
+        ))
+    }
+}
+
+impl FileFormatter for ReposFormatter {
+    fn format_files(
+        &self,
+        files: &[DecoratedEntry],
+        _plugin_manager: &mut PluginManager,
+        _depth: Option<usize>,
+    ) -> Result<String> {
+        if files.is_empty() {
+            return Ok("No git repositories found".bright_black().to_string());
+        }
+
+        // Repositories nest, so they read best in path order whatever the
+        // sort key.
+        let mut files: Vec<&DecoratedEntry> = files.iter().collect();
+        files.sort_by_key(|entry| entry.fs_path());
+        let rows: Vec<(bool, Vec<String>)> = files
+            .par_iter()
+            .filter_map(|entry| Self::format_row(entry))
+            .collect();
+
+        let widths: Vec<usize> = COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(i, (title, _))| {
+                rows.iter()
+                    .map(|(_, cells)| visible_width(&cells[i]))
+                    .chain(std::iter::once(title.len()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut output = String::new();
+        let header: Vec<String> = COLUMNS
+            .iter()
+            .zip(&widths)
+            .map(|((title, align_right), &width)| pad_cell(title, width, *align_right))
+            .collect();
+        output.push_str(&format!(
+            "{}\n{}\n",
+            header.join("  ").trim_end().bold(),
+            "─"
+                .repeat(widths.iter().sum::<usize>() + 2 * (widths.len() - 1))
+                .bright_black()
+        ));
+
+        for (_, cells) in &rows {
+            let line: Vec<String> = cells
+                .iter()
+                .zip(COLUMNS.iter().zip(&widths))
+                .map(|(cell, ((_, align_right), &width))| pad_cell(cell, width, *align_right))
+                .collect();
+            output.push_str(line.join("  ").trim_end());
+            output.push('\n');
+        }
+
+        let dirty = rows.iter().filter(|(is_dirty, _)| *is_dirty).count();
+        let summary = format!(
+            "{} {}, {} with changes",
+            rows.len(),
+            if rows.len() == 1 {
+                "repository"
+            } else {
+                "repositories"
+            },
+            dirty
+        );
+        output.push_str(&format!("\n{}", summary.bright_black()));
+
+        Ok(output)
+    }
+}
@@ -1,22 +1,57 @@
+use super::treemap::Treemap;
 use super::FileFormatter;
 use crate::error::Result;
 use crate::plugin::PluginManager;
 use crate::theme::{self, ColorValue};
 use crate::utils::color::{self, colorize_file_name, colorize_file_name_with_icon};
 use crate::utils::icons::format_with_icon;
+use crate::utils::size::{entry_size, format_size};
 use colored::*;
 use lla_plugin_interface::proto::DecoratedEntry;
-use std::path::Path;
-use terminal_size::{terminal_size, Width};
+use terminal_size::{terminal_size, Height, Width};
 use unicode_width::UnicodeWidthStr;
 
 pub struct SizeMapFormatter {
     pub show_icons: bool,
+    /// Draw nested rectangles instead of one bar per entry.
+    pub treemap: bool,
 }
 
 impl SizeMapFormatter {
-    pub fn new(show_icons: bool) -> Self {
-        Self { show_icons }
+    pub fn new(show_icons: bool, treemap: bool) -> Self {
+        Self {
+            show_icons,
+            treemap,
+        }
+    }
+
+    fn format_treemap(&self, files: &[DecoratedEntry], depth: Option<usize>) -> String {
+        // A pty without a size set reports 0x0.
+        let (width, height) = terminal_size()
+            .map(|(Width(w), Height(h))| {
+                ((w as usize).max(20), (h as usize).saturating_sub(4).max(10))
+            })
+            .unwrap_or((100, 30));
+
+        let treemap = Treemap::new(files, depth);
+        let (root, top) = treemap.top_level();
+        let total: u64 = top.iter().map(|entry| entry_size(entry)).sum();
+        let title = match root {
+            Some(root) => {
+                let path = root.fs_path();
+                let colored_name = colorize_file_name(&path).to_string();
+                format_with_icon(&path, colored_name, self.show_icons)
+            }
+            None => "Total".to_string(),
+        };
+        let separator = ColorValue::Named("bright black".to_string());
+
+        format!(
+            "\n{} {}\n{}",
+            title,
+            format_size(total).color(theme::color_value_to_color(&separator)),
+            treemap.render(width, height)
+        )
     }
 
     fn strip_ansi(s: &str) -> String {
@@ -32,7 +67,7 @@ impl SizeMapFormatter {
         let name_width = files
             .iter()
             .map(|f| {
-                let path = Path::new(&f.path);
+                let path = &f.fs_path();
                 let colored_name = colorize_file_name(path).to_string();
                 let name_with_icon =
                     colorize_file_name_with_icon(path, format_with_icon(path, colored_name, true));
@@ -44,10 +79,7 @@ impl SizeMapFormatter {
 
         let size_width = files
             .iter()
-            .map(|f| {
-                let size = f.metadata.as_ref().map_or(0, |m| m.size);
-                Self::visible_width(&format_size(size))
-            })
+            .map(|f| Self::visible_width(&format_size(entry_size(f))))
             .max()
             .unwrap_or(8)
             .max(8);
@@ -175,31 +207,32 @@ impl FileFormatter for SizeMapFormatter {
         &self,
         files: &[DecoratedEntry],
         plugin_manager: &mut PluginManager,
-        _depth: Option<usize>,
+        depth: Option<usize>,
     ) -> Result<String> {
         if files.is_empty() {
             return Ok(String::new());
         }
 
+        if self.treemap {
+            return Ok(self.format_treemap(files, depth));
+        }
+
         let term_width = terminal_size()
             .map(|(Width(w), _)| w as usize)
             .unwrap_or(100);
 
         let (name_width, size_width, bar_width) = Self::calculate_layout(files, term_width);
 
-        let total_size: u64 = files
-            .iter()
-            .map(|f| f.metadata.as_ref().map_or(0, |m| m.size))
-            .sum();
+        let total_size: u64 = files.iter().map(entry_size).sum();
 
         let mut output = String::new();
         output.push('\n');
 
         let mut files = files.to_vec();
-        files.sort_by_key(|f| std::cmp::Reverse(f.metadata.as_ref().map_or(0, |m| m.size)));
+        files.sort_by_key(|f| std::cmp::Reverse(entry_size(f)));
 
         for file in &files {
-            let path = Path::new(&file.path);
+            let path = &file.fs_path();
             let colored_name = colorize_file_name(path).to_string();
             let name = format_with_icon(path, colored_name, self.show_icons);
             let metadata = file.metadata.as_ref().unwrap();
@@ -234,22 +267,3 @@ impl FileFormatter for SizeMapFormatter {
         Ok(output)
     }
 }
-
-fn format_size(size: u64) -> String {
-    const UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
-    if size == 0 {
-        return "0 B".to_string();
-    }
-
-    let size = size as f64;
-    let base = 1024_f64;
-    let exp = (size.ln() / base.ln()).floor() as i32;
-    let exp = exp.clamp(0, (UNITS.len() - 1) as i32);
-
-    let size = size / base.powi(exp);
-    if exp == 0 {
-        format!("{:.0} {}", size, UNITS[exp as usize])
-    } else {
-        format!("{:.1} {}", size, UNITS[exp as usize])
-    }
-}
@@ -1,67 +1,50 @@
+use super::column::{pad_cell, visible_width, Column};
 use super::FileFormatter;
 use crate::error::Result;
 use crate::plugin::PluginManager;
 use crate::theme::{self, ColorValue};
-use crate::utils::color::{self, *};
-use crate::utils::icons::format_with_icon;
+use crate::utils::color;
 use colored::*;
 use lla_plugin_interface::proto::DecoratedEntry;
 use std::cmp;
-use std::fs::Permissions;
-use std::os::unix::fs::PermissionsExt;
-use std::path::Path;
-use std::time::{Duration, UNIX_EPOCH};
 use unicode_width::UnicodeWidthStr;
 
 pub struct TableFormatter {
     pub show_icons: bool,
+    pub columns: Vec<Column>,
 }
 
 impl TableFormatter {
-    pub fn new(show_icons: bool) -> Self {
-        Self { show_icons }
+    pub fn new(show_icons: bool, columns: Option<Vec<Column>>) -> Self {
+        Self {
+            show_icons,
+            columns: columns.unwrap_or_else(Self::default_columns),
+        }
+    }
+
+    fn default_columns() -> Vec<Column> {
+        vec![
+            Column::Permissions,
+            Column::Size,
+            Column::Time,
+            Column::Name,
+            Column::Plugins,
+        ]
     }
 }
 impl TableFormatter {
     const PADDING: usize = 1;
 
-    fn strip_ansi(s: &str) -> String {
-        String::from_utf8(strip_ansi_escapes::strip(s).unwrap_or_default()).unwrap_or_default()
-    }
-
-    fn visible_width(s: &str) -> usize {
-        Self::strip_ansi(s).width()
-    }
-
-    fn calculate_column_widths(files: &[DecoratedEntry]) -> [usize; 4] {
-        let mut widths = [
-            "Permissions".len(),
-            "Size".len(),
-            "Modified".len(),
-            "Name".len(),
-        ];
-
-        for entry in files {
-            let metadata = entry.metadata.as_ref().cloned().unwrap_or_default();
-            let perms = Permissions::from_mode(metadata.permissions);
-            let perms = colorize_permissions(&perms);
-            widths[0] = cmp::max(widths[0], Self::visible_width(&perms));
-
-            let size: ColoredString = colorize_size(metadata.size);
-            widths[1] = cmp::max(widths[1], Self::visible_width(&size));
-
-            let modified = UNIX_EPOCH + Duration::from_secs(metadata.modified);
-            let date = colorize_date(&modified);
-            widths[2] = cmp::max(widths[2], Self::visible_width(&date));
-
-            let path = Path::new(&entry.path);
-            let colored_name = colorize_file_name(path).to_string();
-            let name_with_icon =
-                colorize_file_name_with_icon(path, format_with_icon(path, colored_name, true));
-            widths[3] = cmp::max(widths[3], Self::visible_width(&name_with_icon));
-        }
-
-        widths
+    fn calculate_column_widths(columns: &[Column], rows: &[Vec<String>]) -> Vec<usize> {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                rows.iter()
+                    .map(|row| visible_width(&row[i]))
+                    .fold(column.title().width(), cmp::max)
+            })
+            .collect()
     }
 
     fn get_border_color() -> Color {
@@ -87,14 +70,14 @@ impl TableFormatter {
         separator.color(border_color).to_string()
     }
 
-    fn create_header(widths: &[usize]) -> String {
+    fn create_header(columns: &[Column], widths: &[usize]) -> String {
         let border_color = Self::get_border_color();
         let header_color = Self::get_header_color();
-        let headers = ["Permissions", "Size", "Modified", "Name"];
         let mut header = String::new();
         header.push('│');
 
-        for (&width, &title) in widths.iter().zip(headers.iter()) {
+        for (&width, column) in widths.iter().zip(columns.iter()) {
+            let title = column.title();
             header.push(' ');
             header.push_str(
                 &format!("{:width$}", title, width = width)
@@ -135,23 +118,12 @@ impl TableFormatter {
         border.push('┘');
         border.color(border_color).to_string()
     }
-
-    fn format_cell(content: &str, width: usize, align_right: bool) -> String {
-        let visible_width = Self::visible_width(content);
-        let padding = width.saturating_sub(visible_width);
-
-        if align_right {
-            format!("{}{}", " ".repeat(padding), content)
-        } else {
-            format!("{}{}", content, " ".repeat(padding))
-        }
-    }
 }
 
 impl FileFormatter for TableFormatter {
     fn format_files(
         &self,
-        files: &[lla_plugin_interface::proto::DecoratedEntry],
+        files: &[DecoratedEntry],
         plugin_manager: &mut PluginManager,
         _depth: Option<usize>,
     ) -> Result<String> {
@@ -159,48 +131,45 @@ impl FileFormatter for TableFormatter {
             return Ok(String::new());
         }
 
-        let widths = Self::calculate_column_widths(files);
+        let mut columns = self.columns.clone();
+        let mut rows: Vec<Vec<String>> = files
+            .iter()
+            .map(|entry| {
+                columns
+                    .iter()
+                    .map(|column| column.render(entry, self.show_icons, plugin_manager, "table"))
+                    .collect()
+            })
+            .collect();
+
+        if let Some(idx) = columns.iter().position(|c| *c == Column::Plugins) {
+            if rows.iter().all(|row| row[idx].is_empty()) {
+                columns.remove(idx);
+                for row in &mut rows {
+                    row.remove(idx);
+                }
+            }
+        }
+
+        let widths = Self::calculate_column_widths(&columns, &rows);
 
         let mut output = String::new();
         output.push_str(&Self::create_top_border(&widths));
         output.push('\n');
-        output.push_str(&Self::create_header(&widths));
+        output.push_str(&Self::create_header(&columns, &widths));
         output.push('\n');
         output.push_str(&Self::create_separator(&widths));
         output.push('\n');
 
-        for entry in files {
-            let metadata = entry.metadata.as_ref().cloned().unwrap_or_default();
-            let perms = Permissions::from_mode(metadata.permissions);
-            let perms = colorize_permissions(&perms);
-            let size = colorize_size(metadata.size);
-            let modified = UNIX_EPOCH + Duration::from_secs(metadata.modified);
-            let date = colorize_date(&modified);
-            let path = Path::new(&entry.path);
-            let colored_name = colorize_file_name(path).to_string();
-            let name = format_with_icon(path, colored_name, self.show_icons);
-
-            let plugin_fields = plugin_manager.format_fields(entry, "table").join(" ");
-            let plugin_suffix = if plugin_fields.is_empty() {
-                String::new()
-            } else {
-                format!(" {}", plugin_fields)
-            };
-
-            let formatted_perms = Self::format_cell(&perms, widths[0], false);
-            let formatted_size = Self::format_cell(&size, widths[1], true);
-            let formatted_date = Self::format_cell(&date, widths[2], false);
-            let formatted_name = Self::format_cell(&name, widths[3], false);
-
-            output.push_str(&format!(
-                "│{pad}{}{pad}│{pad}{}{pad}│{pad}{}{pad}│{pad}{}{pad}│{}\n",
-                formatted_perms,
-                formatted_size,
-                formatted_date,
-                formatted_name,
-                plugin_suffix,
-                pad = " ",
-            ));
+        for row in rows {
+            output.push('│');
+            for ((cell, column), &width) in row.iter().zip(columns.iter()).zip(widths.iter()) {
+                output.push(' ');
+                output.push_str(&pad_cell(cell, width, column.align_right()));
+                output.push(' ');
+                output.push('│');
+            }
+            output.push('\n');
         }
 
         output.push_str(&Self::create_bottom_border(&widths));
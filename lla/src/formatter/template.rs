@@ -0,0 +1,343 @@
+use super::column::{pad_cell, visible_width, Column};
+use super::FileFormatter;
+use crate::error::{LlaError, Result};
+use crate::plugin::PluginManager;
+use crate::theme::{self, ColorValue};
+use crate::utils::color::{colorize_size, get_theme};
+use crate::utils::time::{check_strftime, to_system_time};
+use chrono::{DateTime, Local};
+use colored::*;
+use lla_plugin_interface::proto::DecoratedEntry;
+use lla_plugin_interface::size::SizeStyle;
+
+#[derive(Debug, Clone, Copy)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Field {
+        column: Column,
+        format: Option<String>,
+        align: Option<(Align, usize)>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        // Positions in errors count characters, not bytes.
+        let mut chars = template.chars().enumerate().peekable();
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '{' if matches!(chars.peek(), Some((_, '{'))) => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if matches!(chars.peek(), Some((_, '}'))) => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    let mut closed = false;
+                    for (_, c) in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        placeholder.push(c);
+                    }
+                    if !closed {
+                        return Err(LlaError::Parse(format!(
+                            "unclosed '{{' at position {} in format string",
+                            pos
+                        )));
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Self::parse_placeholder(&placeholder, pos)?);
+                }
+                '}' => {
+                    return Err(LlaError::Parse(format!(
+                        "unmatched '}}' at position {} in format string (use '}}}}' for a literal brace)",
+                        pos
+                    )));
+                }
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+
+    fn parse_placeholder(placeholder: &str, pos: usize) -> Result<Segment> {
+        // `field:NAME` keeps its colon; the spec follows the next one.
+        let (prefix, rest) = match placeholder.trim_start().strip_prefix("field:") {
+            Some(rest) => ("field:", rest),
+            None => ("", placeholder),
+        };
+        let (key, spec) = match rest.split_once(':') {
+            Some((key, spec)) => (key.trim(), Some(spec)),
+            None => (rest.trim(), None),
+        };
+        if key.is_empty() {
+            return Err(LlaError::Parse(format!(
+                "empty placeholder at position {} in format string",
+                pos
+            )));
+        }
+        let key = format!("{}{}", prefix, key);
+        let key = key.as_str();
+
+        let (format, align) = match spec {
+            None => (None, None),
+            Some(spec) => match Self::parse_align(spec) {
+                Some(align) => (None, Some(align)),
+                None => match spec.rsplit_once(':') {
+                    Some((format, tail)) => match Self::parse_align(tail) {
+                        Some(align) => (Some(format.to_string()), Some(align)),
+                        None => (Some(spec.to_string()), None),
+                    },
+                    None => (Some(spec.to_string()), None),
+                },
+            },
+        };
+
+        let column = Column::from_name(key)
+            .map_err(|e| LlaError::Parse(format!("{} at position {} in format string", e, pos)))?;
+        if let Some(format) = &format {
+            Self::check_format(&column, format).map_err(|e| {
+                LlaError::Parse(format!("{} at position {} in format string", e, pos))
+            })?;
+        }
+
+        Ok(Segment::Field {
+            column,
+            format,
+            align,
+        })
+    }
+
+    /// Only time columns take a strftime format and `size` a size style;
+    /// a format on any other column would be silently ignored.
+    fn check_format(column: &Column, format: &str) -> std::result::Result<(), String> {
+        match column {
+            Column::Modified
+            | Column::Accessed
+            | Column::Created
+            | Column::Changed
+            | Column::Time => check_strftime(format),
+            Column::Size => match SizeStyle::parse(format) {
+                Some(_) => Ok(()),
+                None => Err(format!(
+                    "unknown size format '{}' (use one of: {})",
+                    format,
+                    SizeStyle::VALUES.join(", ")
+                )),
+            },
+            _ => Err(format!(
+                "column '{}' does not take a format",
+                column.header()
+            )),
+        }
+    }
+
+    fn parse_align(spec: &str) -> Option<(Align, usize)> {
+        let (align, width) = match spec.chars().next()? {
+            '<' => (Align::Left, &spec[1..]),
+            '>' => (Align::Right, &spec[1..]),
+            '^' => (Align::Center, &spec[1..]),
+            _ => (Align::Left, spec),
+        };
+        if width.is_empty() || !width.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        Some((align, width.parse().ok()?))
+    }
+
+    pub fn render(
+        &self,
+        entry: &DecoratedEntry,
+        show_icons: bool,
+        plugin_manager: &mut PluginManager,
+    ) -> String {
+        let mut output = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => output.push_str(text),
+                Segment::Field {
+                    column,
+                    format,
+                    align,
+                } => {
+                    let value = match format {
+                        Some(format) => Self::render_with_format(column, format, entry),
+                        None => column.render(entry, show_icons, plugin_manager, "long"),
+                    };
+                    match align {
+                        Some((Align::Left, width)) => {
+                            output.push_str(&pad_cell(&value, *width, false))
+                        }
+                        Some((Align::Right, width)) => {
+                            output.push_str(&pad_cell(&value, *width, true))
+                        }
+                        Some((Align::Center, width)) => {
+                            let padding = width.saturating_sub(visible_width(&value));
+                            let left = padding / 2;
+                            output.push_str(&" ".repeat(left));
+                            output.push_str(&value);
+                            output.push_str(&" ".repeat(padding - left));
+                        }
+                        None => output.push_str(&value),
+                    }
+                }
+            }
+        }
+        output
+    }
+
+    fn render_with_format(column: &Column, format: &str, entry: &DecoratedEntry) -> String {
+        let metadata = entry.metadata.as_ref().cloned().unwrap_or_default();
+        let theme = get_theme();
+        match column {
+            Column::Modified
+            | Column::Accessed
+            | Column::Created
+            | Column::Changed
+            | Column::Time => {
+                let secs = column.time_field().unwrap().timestamp(entry);
+                let datetime: DateTime<Local> = to_system_time(secs).into();
+                let formatted = datetime.format(format).to_string();
+                colorize(&formatted, &theme.colors.date)
+            }
+            Column::Size => match SizeStyle::parse(format) {
+                Some(style) if format != "human" => {
+                    colorize(&style.format(metadata.size), &theme.colors.size)
+                }
+                _ => colorize_size(metadata.size).to_string(),
+            },
+            _ => column.raw_value(entry),
+        }
+    }
+}
+
+fn colorize(value: &str, color: &ColorValue) -> String {
+    if theme::is_no_color() {
+        value.to_string()
+    } else {
+        value.color(theme::color_value_to_color(color)).to_string()
+    }
+}
+
+pub struct TemplateFormatter {
+    pub show_icons: bool,
+    pub template: Template,
+}
+
+impl TemplateFormatter {
+    pub fn new(show_icons: bool, template: Template) -> Self {
+        Self {
+            show_icons,
+            template,
+        }
+    }
+}
+
+impl FileFormatter for TemplateFormatter {
+    fn format_files(
+        &self,
+        files: &[DecoratedEntry],
+        plugin_manager: &mut PluginManager,
+        _depth: Option<usize>,
+    ) -> Result<String> {
+        Ok(files
+            .iter()
+            .map(|entry| self.template.render(entry, self.show_icons, plugin_manager))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(template: &str) -> String {
+        match Template::parse(template) {
+            Err(LlaError::Parse(message)) => message,
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        let template = Template::parse("{{name}} }}{name}").unwrap();
+        assert!(matches!(
+            template.segments.as_slice(),
+            [Segment::Literal(text), Segment::Field { column: Column::Name, .. }]
+                if text == "{name} }"
+        ));
+    }
+
+    #[test]
+    fn parses_alignment_after_an_optional_format() {
+        let template =
+            Template::parse("{name:>12}{size:^6}{modified:%Y-%m-%d:<10}{field:git:8}").unwrap();
+        let fields: Vec<_> = template
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Field { format, align, .. } => (
+                    format.clone(),
+                    align.map(|(align, width)| (format!("{:?}", align), width)),
+                ),
+                Segment::Literal(text) => panic!("unexpected literal {:?}", text),
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (None, Some(("Right".to_string(), 12))),
+                (None, Some(("Center".to_string(), 6))),
+                (Some("%Y-%m-%d".to_string()), Some(("Left".to_string(), 10))),
+                (None, Some(("Left".to_string(), 8))),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_error_positions() {
+        assert!(error("ab {name").contains("unclosed '{' at position 3"));
+        assert!(error("ab } {name}").contains("unmatched '}' at position 3"));
+        assert!(error("{name} {}").contains("empty placeholder at position 7"));
+        assert!(error("{name} {nmae}").contains("at position 7"));
+        assert!(error("{modified:%Q}").contains("at position 0"));
+        assert!(error("→ {nmae}").contains("at position 2"));
+        assert!(error("é → } {name}").contains("unmatched '}' at position 4"));
+    }
+
+    #[test]
+    fn rejects_formats_a_column_does_not_take() {
+        assert!(Template::parse("{size:si} {size:bytes} {mtime:%H:%M}").is_ok());
+        assert!(error("{size:bogus}").contains("unknown size format 'bogus'"));
+        assert!(error("x {user:%Y}").contains("column 'user' does not take a format at position 2"));
+        assert!(error("{field:git:raw}").contains("does not take a format"));
+    }
+}
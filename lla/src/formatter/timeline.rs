@@ -1,43 +1,218 @@
+use super::group::TimeGroup;
 use super::FileFormatter;
 use crate::error::Result;
 use crate::plugin::PluginManager;
 use crate::theme::{self, ColorValue};
 use crate::utils::color::{self, colorize_file_name, colorize_file_name_with_icon};
+use crate::utils::git::CommitTimes;
 use crate::utils::icons::format_with_icon;
-use chrono::{DateTime, Duration, Local};
+use crate::utils::time::{
+    format_duration, parse_duration, time_field, time_style, to_system_time, TimeStyle,
+};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, Timelike};
 use colored::*;
 use lla_plugin_interface::proto::DecoratedEntry;
+use std::cmp::Reverse;
 use std::collections::BTreeMap;
-use std::path::Path;
-use std::time::UNIX_EPOCH;
+
+/// The most bars drawn in the activity histogram; older periods are dropped.
+const MAX_BARS: usize = 60;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// How entries are split into sections.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum TimeBucket {
+    /// Today, yesterday, last week, last month and older.
+    #[default]
+    Relative,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+    /// Ages in seconds, ascending: the first section holds entries younger
+    /// than the first age, and the last those older than the last age.
+    Ranges(Vec<u64>),
+}
+
+impl TimeBucket {
+    pub const VALUES: [&'static str; 6] = ["relative", "hour", "day", "week", "month", "year"];
+
+    /// Parses a granularity or a comma-separated list of ages such as
+    /// `15m,1h,6h,1d`.
+    pub fn parse(value: &str) -> std::result::Result<Self, String> {
+        match value.trim() {
+            "relative" => Ok(TimeBucket::Relative),
+            "hour" | "hourly" => Ok(TimeBucket::Hour),
+            "day" | "daily" => Ok(TimeBucket::Day),
+            "week" | "weekly" => Ok(TimeBucket::Week),
+            "month" | "monthly" => Ok(TimeBucket::Month),
+            "year" | "yearly" => Ok(TimeBucket::Year),
+            ranges => {
+                let mut ages = ranges
+                    .split(',')
+                    .map(parse_duration)
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|err| {
+                        format!(
+                            "{}; expected one of {} or a list of ages such as 1h,1d,7d",
+                            err,
+                            Self::VALUES.join(", ")
+                        )
+                    })?;
+                if ages.contains(&0) {
+                    return Err("bucket ages must be greater than zero".to_string());
+                }
+                ages.sort_unstable();
+                ages.dedup();
+                Ok(TimeBucket::Ranges(ages))
+            }
+        }
+    }
+
+    fn key(&self, dt: DateTime<Local>, now: DateTime<Local>) -> BucketKey {
+        let local = dt.naive_local();
+        let date = local.date();
+        let start = |date: NaiveDate, hour: u32| date.and_hms_opt(hour, 0, 0).unwrap_or(local);
+        match self {
+            TimeBucket::Relative => BucketKey::Relative(TimeGroup::from_datetime(dt)),
+            TimeBucket::Hour => BucketKey::Period(Reverse(start(date, local.hour()))),
+            TimeBucket::Day => BucketKey::Period(Reverse(start(date, 0))),
+            TimeBucket::Week => BucketKey::Period(Reverse(start(
+                date - Duration::days(date.weekday().num_days_from_monday() as i64),
+                0,
+            ))),
+            TimeBucket::Month => {
+                BucketKey::Period(Reverse(start(date.with_day(1).unwrap_or(date), 0)))
+            }
+            TimeBucket::Year => {
+                BucketKey::Period(Reverse(start(date.with_ordinal(1).unwrap_or(date), 0)))
+            }
+            TimeBucket::Ranges(ages) => {
+                let age = now.signed_duration_since(dt).num_seconds().max(0) as u64;
+                BucketKey::Range(ages.iter().take_while(|&&max| age >= max).count())
+            }
+        }
+    }
+
+    /// The start of the period following the one starting at `start`.
+    fn next_period(&self, start: NaiveDateTime) -> NaiveDateTime {
+        let (year, month) = (start.year(), start.month());
+        let first_of = |year, month| {
+            NaiveDate::from_ymd_opt(year, month, 1)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .unwrap_or(start)
+        };
+        match self {
+            TimeBucket::Hour => start + Duration::hours(1),
+            TimeBucket::Day => start + Duration::days(1),
+            TimeBucket::Week => start + Duration::days(7),
+            TimeBucket::Month if month == 12 => first_of(year + 1, 1),
+            TimeBucket::Month => first_of(year, month + 1),
+            TimeBucket::Year => first_of(year + 1, 1),
+            _ => start,
+        }
+    }
+
+    fn title(&self, key: &BucketKey) -> String {
+        match key {
+            BucketKey::Relative(group) => group.display_name().to_string(),
+            BucketKey::Period(Reverse(start)) => {
+                let format = match self {
+                    TimeBucket::Hour => "%Y-%m-%d %H:00",
+                    TimeBucket::Day => "%Y-%m-%d %a",
+                    TimeBucket::Week => "Week of %Y-%m-%d",
+                    TimeBucket::Month => "%B %Y",
+                    _ => "%Y",
+                };
+                start.format(format).to_string()
+            }
+            BucketKey::Range(index) => match self {
+                TimeBucket::Ranges(ages) if *index == 0 => {
+                    format!("Last {}", format_duration(ages[0]))
+                }
+                TimeBucket::Ranges(ages) if *index < ages.len() => format!(
+                    "{} – {} ago",
+                    format_duration(ages[index - 1]),
+                    format_duration(ages[*index])
+                ),
+                TimeBucket::Ranges(ages) => {
+                    format!("Older than {}", format_duration(ages[ages.len() - 1]))
+                }
+                _ => String::new(),
+            },
+            BucketKey::Uncommitted => "Not committed".to_string(),
+        }
+    }
+
+    /// Every bucket from the oldest to the newest in `counts`, including empty
+    /// ones, for the histogram.
+    fn chronological(&self, counts: &BTreeMap<BucketKey, usize>) -> Vec<BucketKey> {
+        match self {
+            TimeBucket::Relative => [
+                TimeGroup::Older,
+                TimeGroup::LastMonth,
+                TimeGroup::LastWeek,
+                TimeGroup::Yesterday,
+                TimeGroup::Today,
+            ]
+            .into_iter()
+            .map(BucketKey::Relative)
+            .collect(),
+            TimeBucket::Ranges(ages) => (0..=ages.len()).rev().map(BucketKey::Range).collect(),
+            _ => {
+                let mut starts = counts.keys().filter_map(|key| match key {
+                    BucketKey::Period(Reverse(start)) => Some(*start),
+                    _ => None,
+                });
+                let (newest, oldest) = match (starts.next(), starts.last()) {
+                    (Some(newest), Some(oldest)) => (newest, oldest),
+                    (Some(only), None) => (only, only),
+                    _ => return Vec::new(),
+                };
+
+                let mut keys = Vec::new();
+                let mut start = oldest;
+                while start <= newest {
+                    keys.push(BucketKey::Period(Reverse(start)));
+                    start = self.next_period(start);
+                }
+                let skip = keys.len().saturating_sub(MAX_BARS);
+                keys.split_off(skip)
+            }
+        }
+    }
+}
+
+/// A section of the timeline. Sections are ordered newest first, with files
+/// git has never seen at the end.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum BucketKey {
+    Relative(TimeGroup),
+    Period(Reverse<NaiveDateTime>),
+    Range(usize),
+    Uncommitted,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TimelineOptions {
+    pub bucket: TimeBucket,
+    /// Show a histogram of activity per bucket above the sections.
+    pub histogram: bool,
+    /// Use the time of each file's last commit instead of its file time.
+    pub git_dates: bool,
+}
 
 pub struct TimelineFormatter {
     pub show_icons: bool,
+    pub options: TimelineOptions,
 }
 
 impl TimelineFormatter {
-    pub fn new(show_icons: bool) -> Self {
-        Self { show_icons }
-    }
-
-    fn format_relative_time(dt: DateTime<Local>) -> String {
-        let now = Local::now();
-        let duration = now.signed_duration_since(dt);
-
-        if duration.num_seconds() < 60 {
-            "just now".to_string()
-        } else if duration.num_minutes() < 60 {
-            format!("{} mins ago", duration.num_minutes())
-        } else if duration.num_hours() < 24 {
-            format!("{} hours ago", duration.num_hours())
-        } else if duration.num_days() < 7 {
-            format!("{} days ago", duration.num_days())
-        } else if duration.num_days() < 30 {
-            format!("{} weeks ago", duration.num_weeks())
-        } else if duration.num_days() < 365 {
-            dt.format("%b %d").to_string()
-        } else {
-            dt.format("%b %d, %Y").to_string()
+    pub fn new(show_icons: bool, options: TimelineOptions) -> Self {
+        Self {
+            show_icons,
+            options,
         }
     }
 
@@ -59,48 +234,55 @@ impl TimelineFormatter {
         let theme = color::get_theme();
         theme::color_value_to_color(&theme.colors.symlink)
     }
-}
 
-#[derive(Eq, PartialEq, Ord, PartialOrd)]
-enum TimeGroup {
-    Today,
-    Yesterday,
-    LastWeek,
-    LastMonth,
-    Older,
-}
+    /// A sparkline of entries per bucket from oldest to newest, labelled with
+    /// the first and last bucket and the busiest one.
+    fn format_histogram(&self, counts: &BTreeMap<BucketKey, usize>) -> String {
+        let keys = self.options.bucket.chronological(counts);
+        let (first, last) = match (keys.first(), keys.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return String::new(),
+        };
 
-impl TimeGroup {
-    fn from_datetime(dt: DateTime<Local>) -> Self {
-        let now = Local::now();
-        let today = now.date_naive();
-        let yesterday = today - Duration::days(1);
-        let last_week = today - Duration::days(7);
-        let last_month = today - Duration::days(30);
-
-        let file_date = dt.date_naive();
-
-        if file_date == today {
-            TimeGroup::Today
-        } else if file_date == yesterday {
-            TimeGroup::Yesterday
-        } else if file_date > last_week {
-            TimeGroup::LastWeek
-        } else if file_date > last_month {
-            TimeGroup::LastMonth
-        } else {
-            TimeGroup::Older
-        }
-    }
+        let count = |key: &BucketKey| counts.get(key).copied().unwrap_or(0);
+        let max = keys.iter().map(count).max().unwrap_or(0).max(1);
+        let sparkline: String = keys
+            .iter()
+            .map(|key| match count(key) {
+                0 => "⋅".color(Self::get_separator_color()).to_string(),
+                n => SPARKS[((n * SPARKS.len()).saturating_sub(1) / max).min(SPARKS.len() - 1)]
+                    .to_string()
+                    .color(Self::get_header_color())
+                    .to_string(),
+            })
+            .collect();
 
-    fn display_name(&self) -> &'static str {
-        match self {
-            TimeGroup::Today => "Today",
-            TimeGroup::Yesterday => "Yesterday",
-            TimeGroup::LastWeek => "Last Week",
-            TimeGroup::LastMonth => "Last Month",
-            TimeGroup::Older => "Older",
-        }
+        let busiest = keys.iter().rev().max_by_key(|key| count(key));
+        let summary = match busiest {
+            Some(key) if count(key) > 0 => format!(
+                "{} entries, busiest: {} ({})",
+                counts.values().sum::<usize>(),
+                self.options.bucket.title(key),
+                count(key)
+            ),
+            _ => format!("{} entries", counts.values().sum::<usize>()),
+        };
+
+        let last = if keys.len() > 1 {
+            format!(" {}", self.options.bucket.title(last))
+        } else {
+            String::new()
+        };
+        format!(
+            "\n{} {}{}\n{}\n",
+            self.options
+                .bucket
+                .title(first)
+                .color(Self::get_separator_color()),
+            sparkline,
+            last.color(Self::get_separator_color()),
+            summary.color(Self::get_separator_color())
+        )
     }
 }
 
@@ -115,33 +297,62 @@ impl FileFormatter for TimelineFormatter {
             return Ok(String::new());
         }
 
-        let mut groups: BTreeMap<TimeGroup, Vec<&DecoratedEntry>> = BTreeMap::new();
+        let field = time_field();
+        let commits = if self.options.git_dates {
+            CommitTimes::load(&files.iter().map(|file| file.fs_path()).collect::<Vec<_>>())
+        } else {
+            None
+        };
+        let now = Local::now();
+        let mut groups: BTreeMap<BucketKey, Vec<(&DecoratedEntry, Option<u64>)>> = BTreeMap::new();
 
         for file in files {
-            let modified = file.metadata.as_ref().map_or(0, |m| m.modified);
-            let modified = UNIX_EPOCH + std::time::Duration::from_secs(modified);
-            let dt = DateTime::<Local>::from(modified);
-            let group = TimeGroup::from_datetime(dt);
-            groups.entry(group).or_default().push(file);
+            let time = match &commits {
+                Some(commits) => commits.time(&file.fs_path()),
+                None => Some(field.timestamp(file)),
+            };
+            let key = match time {
+                Some(time) => {
+                    let dt = DateTime::<Local>::from(to_system_time(time));
+                    self.options.bucket.key(dt, now)
+                }
+                None => BucketKey::Uncommitted,
+            };
+            groups.entry(key).or_default().push((file, time));
         }
 
         let mut output = String::new();
+        if self.options.histogram {
+            let counts = groups
+                .iter()
+                .filter(|(key, _)| **key != BucketKey::Uncommitted)
+                .map(|(key, entries)| (key.clone(), entries.len()))
+                .collect();
+            output.push_str(&self.format_histogram(&counts));
+        }
 
-        for (group, entries) in groups {
+        for (key, entries) in groups {
             output.push_str(&format!(
                 "\n{}\n{}\n",
-                group.display_name().color(Self::get_header_color()).bold(),
+                self.options
+                    .bucket
+                    .title(&key)
+                    .color(Self::get_header_color())
+                    .bold(),
                 "─".repeat(40).color(Self::get_separator_color())
             ));
 
-            for entry in entries {
-                let modified = entry.metadata.as_ref().map_or(0, |m| m.modified);
-                let modified = UNIX_EPOCH + std::time::Duration::from_secs(modified);
-                let dt = DateTime::<Local>::from(modified);
+            for (entry, time) in entries {
+                let time_str = time
+                    .map(|time| {
+                        time_style()
+                            .unwrap_or(&TimeStyle::Relative)
+                            .format(&to_system_time(time))
+                    })
+                    .unwrap_or_else(|| "-".to_string())
+                    .color(Self::get_time_color());
 
-                let time_str = Self::format_relative_time(dt).color(Self::get_time_color());
-
-                let path = Path::new(&entry.path);
+                let path = &entry.fs_path();
                 let colored_name = colorize_file_name(path).to_string();
                 let name = colorize_file_name_with_icon(
                     path,
@@ -167,3 +378,66 @@ impl FileFormatter for TimelineFormatter {
         Ok(output)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_granularities() {
+        assert_eq!(TimeBucket::parse("relative"), Ok(TimeBucket::Relative));
+        assert_eq!(TimeBucket::parse("hour"), Ok(TimeBucket::Hour));
+        assert_eq!(TimeBucket::parse(" daily "), Ok(TimeBucket::Day));
+        assert_eq!(TimeBucket::parse("week"), Ok(TimeBucket::Week));
+        assert_eq!(TimeBucket::parse("monthly"), Ok(TimeBucket::Month));
+        assert_eq!(TimeBucket::parse("year"), Ok(TimeBucket::Year));
+    }
+
+    #[test]
+    fn parses_age_lists() {
+        assert_eq!(
+            TimeBucket::parse("1h,1d,7d"),
+            Ok(TimeBucket::Ranges(vec![3600, 86400, 7 * 86400]))
+        );
+        assert_eq!(
+            TimeBucket::parse("15m"),
+            Ok(TimeBucket::Ranges(vec![15 * 60]))
+        );
+    }
+
+    #[test]
+    fn sorts_and_dedups_ages_given_out_of_order() {
+        assert_eq!(
+            TimeBucket::parse("7d,1h,1d,60m"),
+            Ok(TimeBucket::Ranges(vec![3600, 86400, 7 * 86400]))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_age_lists() {
+        assert!(TimeBucket::parse("1h,2x").unwrap_err().contains("unit 'x'"));
+        assert!(TimeBucket::parse("fortnight").is_err());
+        assert!(TimeBucket::parse("1h,,1d").is_err());
+        assert!(TimeBucket::parse("0s,1h")
+            .unwrap_err()
+            .contains("greater than zero"));
+        assert!(TimeBucket::parse("1h,99999999999999999y")
+            .unwrap_err()
+            .contains("too large"));
+    }
+
+    #[test]
+    fn buckets_entries_by_age() {
+        let bucket = TimeBucket::parse("1h,1d").unwrap();
+        let now = Local::now();
+        let key = |age: i64| bucket.key(now - Duration::seconds(age), now);
+        assert_eq!(key(0), BucketKey::Range(0));
+        assert_eq!(key(3599), BucketKey::Range(0));
+        assert_eq!(key(3600), BucketKey::Range(1));
+        assert_eq!(key(86400), BucketKey::Range(2));
+        assert_eq!(
+            bucket.title(&BucketKey::Range(1)),
+            "1h – 1d ago".to_string()
+        );
+    }
+}
@@ -1,74 +1,225 @@
 use super::FileFormatter;
 use crate::error::Result;
+use crate::filter::FileFilter;
 use crate::plugin::PluginManager;
 use crate::utils::color::*;
 use crate::utils::icons::format_with_icon;
+use crate::utils::size::entry_size;
+use crate::utils::symlink::symlink_suffix;
 use colored::Colorize;
 use lla_plugin_interface::proto::DecoratedEntry;
 use std::collections::{HashMap, HashSet};
 use std::io::{self, Write};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 const BUFFER_SIZE: usize = 16384;
 
+/// The `custom_fields` key under which a directory whose size has been rolled
+/// up carries the number of files beneath it, including those past `--depth`.
+pub const FILE_COUNT_FIELD: &str = "file_count";
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TreeOptions {
+    /// Show each node's size, rolled up for directories, and how many files
+    /// each directory holds.
+    pub sizes: bool,
+    /// Hide entries smaller than this many bytes.
+    pub min_size: Option<u64>,
+    /// Show each node's share of its parent directory's size.
+    pub percent: bool,
+    /// Render chains of directories that each hold a single directory, like
+    /// `src/main/java`, as one node.
+    pub compact: bool,
+    /// Show at most this many entries per directory, then a count of the rest.
+    pub max_children: Option<usize>,
+}
+
+/// The tree built from a listing: the children of each directory, and for
+/// each path its size and, for directories, the number of files beneath it.
+struct Tree {
+    children: HashMap<PathBuf, Vec<PathBuf>>,
+    sizes: HashMap<PathBuf, u64>,
+    file_counts: HashMap<PathBuf, u64>,
+    highlights: HashMap<PathBuf, Range<usize>>,
+}
+
 pub struct TreeFormatter {
     pub show_icons: bool,
+    pub options: TreeOptions,
+    highlight: Option<Arc<dyn FileFilter + Send + Sync>>,
 }
 
 impl TreeFormatter {
-    pub fn new(show_icons: bool) -> Self {
-        Self { show_icons }
+    pub fn new(show_icons: bool, options: TreeOptions) -> Self {
+        Self {
+            show_icons,
+            options,
+            highlight: None,
+        }
+    }
+
+    /// Highlights the part of each matching name that `filter` matched on.
+    pub fn with_highlight(mut self, filter: Arc<dyn FileFilter + Send + Sync>) -> Self {
+        self.highlight = Some(filter);
+        self
     }
 
-    fn format_entry(&self, path: &Path) -> String {
-        let colored_name = colorize_file_name(path).to_string();
-        if self.show_icons {
-            format_with_icon(path, colored_name, true)
+    fn format_name(&self, path: &Path, tree: &Tree) -> String {
+        let colored_name = colorize_file_name(path);
+        let range = match tree.highlights.get(path) {
+            Some(range) => range.clone(),
+            None => return colored_name.to_string(),
+        };
+
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        // Quoting may have changed the name, which would shift the range.
+        let text: &str = &colored_name;
+        if !text.starts_with(name.as_ref()) {
+            return colored_name.to_string();
+        }
+
+        let paint = |part: &str| match colored_name.fgcolor() {
+            Some(color) if !part.is_empty() => part.color(color).to_string(),
+            _ => part.to_string(),
+        };
+        format!(
+            "{}{}{}{}",
+            paint(&name[..range.start]),
+            name[range.clone()].black().on_yellow(),
+            paint(&name[range.end..]),
+            paint(&text[name.len()..])
+        )
+    }
+
+    /// Formats a node, which is a chain of directories in compact mode. Sizes
+    /// and counts are those of the last path in the chain.
+    fn format_entry(&self, chain: &[&Path], tree: &Tree) -> String {
+        let (first, path) = (chain[0], chain[chain.len() - 1]);
+        let colored_name: String = chain
+            .iter()
+            .map(|path| self.format_name(path, tree))
+            .collect();
+        let name = if self.show_icons {
+            format_with_icon(first, colored_name, true)
         } else {
             colored_name
+        };
+        let mut line = format!("{}{}", name, symlink_suffix(path));
+
+        let size = tree.sizes.get(path).copied().unwrap_or(0);
+        if self.options.sizes {
+            line.push_str(&format!(" {}", colorize_size(size)));
         }
+        if self.options.percent {
+            let parent_size = first.parent().and_then(|parent| tree.sizes.get(parent));
+            if let Some(&parent_size) = parent_size {
+                let percent = if parent_size == 0 {
+                    0.0
+                } else {
+                    size as f64 * 100.0 / parent_size as f64
+                };
+                line.push_str(&format!(" {}", format!("{:.0}%", percent).bright_black()));
+            }
+        }
+        if self.options.sizes {
+            if let Some(count) = tree.file_counts.get(path) {
+                let files = if *count == 1 { "file" } else { "files" };
+                line.push_str(&format!(
+                    " {}",
+                    format!("{} {}", count, files).bright_black()
+                ));
+            }
+        }
+        line
     }
 
-    fn build_tree(
-        &self,
-        entries: &[DecoratedEntry],
-    ) -> (Vec<PathBuf>, HashMap<PathBuf, Vec<PathBuf>>) {
-        let mut tree: HashMap<PathBuf, Vec<PathBuf>> = HashMap::with_capacity(entries.len());
-        let mut path_set: HashSet<PathBuf> = HashSet::with_capacity(entries.len());
-        let mut child_paths = HashSet::new();
+    /// Children keep the order they have in `entries`, which is sorted per
+    /// directory before formatting. Directory sizes are taken as listed, so
+    /// they are rolled up beforehand when sizes are shown, and so are file
+    /// counts when the entries carry them.
+    fn build_tree(&self, entries: &[DecoratedEntry]) -> (Vec<PathBuf>, Tree) {
+        let mut tree = Tree {
+            children: HashMap::with_capacity(entries.len()),
+            sizes: HashMap::with_capacity(entries.len()),
+            file_counts: HashMap::new(),
+            highlights: HashMap::new(),
+        };
+        let path_set: HashSet<PathBuf> = entries.iter().map(|entry| entry.fs_path()).collect();
+        let mut root_paths = Vec::new();
 
         for entry in entries {
-            path_set.insert(PathBuf::from(&entry.path));
-        }
+            let path = entry.fs_path();
+            tree.sizes.insert(path.clone(), entry_size(entry));
+
+            if let Some(filter) = &self.highlight {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let matched = filter
+                    .filter_entries(std::slice::from_ref(entry))
+                    .map_or(false, |kept| !kept.is_empty());
+                if let Some(range) = filter.highlight(&name).filter(|_| matched) {
+                    tree.highlights.insert(path.clone(), range);
+                }
+            }
 
-        for path in path_set.iter() {
-            if let Some(parent) = path.parent() {
-                if path_set.contains(parent) {
-                    tree.entry(parent.to_path_buf())
+            let metadata = entry.metadata.as_ref();
+            if metadata.map_or(false, |m| m.is_dir) {
+                tree.file_counts.entry(path.clone()).or_insert(0);
+            } else if metadata.map_or(false, |m| m.is_file) {
+                for ancestor in path.ancestors().skip(1) {
+                    if path_set.contains(ancestor) {
+                        *tree.file_counts.entry(ancestor.to_path_buf()).or_insert(0) += 1;
+                    }
+                }
+            }
+
+            match path.parent() {
+                Some(parent) if path_set.contains(parent) => {
+                    tree.children
+                        .entry(parent.to_path_buf())
                         .or_insert_with(Vec::new)
-                        .push(path.clone());
-                    child_paths.insert(path.clone());
+                        .push(path);
                 }
+                _ => root_paths.push(path),
             }
         }
-        for children in tree.values_mut() {
-            children.sort_unstable();
+
+        for entry in entries {
+            if let Some(count) = entry.custom_fields.get(FILE_COUNT_FIELD) {
+                if let Ok(count) = count.parse() {
+                    tree.file_counts.insert(entry.fs_path(), count);
+                }
+            }
         }
-        let mut root_paths: Vec<_> = path_set
-            .into_iter()
-            .filter(|path| !child_paths.contains(path))
-            .collect();
-        root_paths.sort_unstable();
 
         (root_paths, tree)
     }
 
+    fn shown_children<'a>(&self, path: &Path, tree: &'a Tree) -> Vec<&'a Path> {
+        let is_shown = |child: &&PathBuf| {
+            self.options.min_size.map_or(true, |min| {
+                tree.sizes.get(child.as_path()).copied().unwrap_or(0) >= min
+            })
+        };
+        tree.children.get(path).map_or_else(Vec::new, |children| {
+            children
+                .iter()
+                .filter(is_shown)
+                .map(PathBuf::as_path)
+                .collect()
+        })
+    }
+
     fn write_tree_recursive(
         &self,
         path: &Path,
         prefix: &str,
         is_last: bool,
-        tree: &HashMap<PathBuf, Vec<PathBuf>>,
+        tree: &Tree,
         writer: &mut impl Write,
         current_depth: usize,
         max_depth: Option<usize>,
@@ -82,7 +233,21 @@ impl TreeFormatter {
         let node_prefix = if is_last { "└── " } else { "├── " };
         let child_prefix = if is_last { "    " } else { "│   " };
 
-        let formatted_name = self.format_entry(path);
+        // Roots stay on their own line so the listed directory is visible.
+        let mut chain = vec![path];
+        let mut depth = current_depth;
+        if self.options.compact && current_depth > 0 {
+            while let [only] = self.shown_children(chain[chain.len() - 1], tree)[..] {
+                let within_depth = max_depth.map_or(true, |max| depth < max);
+                if !tree.file_counts.contains_key(only) || !within_depth {
+                    break;
+                }
+                chain.push(only);
+                depth += 1;
+            }
+        }
+
+        let formatted_name = self.format_entry(&chain, tree);
         write!(
             writer,
             "{}{}{}\n",
@@ -91,21 +256,35 @@ impl TreeFormatter {
             formatted_name
         )?;
 
-        if let Some(children) = tree.get(path) {
-            let new_prefix = format!("{}{}", prefix, child_prefix);
-            let last_idx = children.len().saturating_sub(1);
-            for (i, child) in children.iter().enumerate() {
-                let is_last_child = i == last_idx;
-                self.write_tree_recursive(
-                    child,
-                    &new_prefix,
-                    is_last_child,
-                    tree,
-                    writer,
-                    current_depth + 1,
-                    max_depth,
-                )?;
-            }
+        let children = self.shown_children(chain[chain.len() - 1], tree);
+        let shown = self
+            .options
+            .max_children
+            .map_or(children.len(), |max| max.min(children.len()));
+        let hidden = children.len() - shown;
+        let new_prefix = format!("{}{}", prefix, child_prefix);
+        for (i, child) in children[..shown].iter().enumerate() {
+            let is_last_child = hidden == 0 && i + 1 == shown;
+            self.write_tree_recursive(
+                child,
+                &new_prefix,
+                is_last_child,
+                tree,
+                writer,
+                depth + 1,
+                max_depth,
+            )?;
+        }
+
+        if hidden > 0 && max_depth.map_or(true, |max| depth < max) {
+            let entries = if hidden == 1 { "entry" } else { "entries" };
+            writeln!(
+                writer,
+                "{}{}{}",
+                new_prefix.bright_black(),
+                "└── ".bright_black(),
+                format!("… {} more {}", hidden, entries).bright_black()
+            )?;
         }
         Ok(())
     }
@@ -138,3 +317,81 @@ impl FileFormatter for TreeFormatter {
         Ok(String::from_utf8_lossy(&buffer).into_owned())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lla_plugin_interface::proto::EntryMetadata;
+
+    fn entry(path: &str, size: u64, is_dir: bool, file_count: Option<u64>) -> DecoratedEntry {
+        let metadata = EntryMetadata {
+            size,
+            is_dir,
+            is_file: !is_dir,
+            ..Default::default()
+        };
+        let custom_fields = file_count
+            .map(|count| (FILE_COUNT_FIELD.to_string(), count.to_string()))
+            .into_iter()
+            .collect();
+        DecoratedEntry::new(Path::new(path), Some(metadata), custom_fields)
+    }
+
+    fn formatter() -> TreeFormatter {
+        colored::control::set_override(false);
+        TreeFormatter::new(
+            false,
+            TreeOptions {
+                sizes: true,
+                percent: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn counts_listed_files() {
+        let entries = [
+            entry("r", 0, true, None),
+            entry("r/a", 0, true, None),
+            entry("r/a/x", 1, false, None),
+            entry("r/y", 1, false, None),
+        ];
+        let (roots, tree) = formatter().build_tree(&entries);
+        assert_eq!(roots, [PathBuf::from("r")]);
+        assert_eq!(tree.file_counts[Path::new("r")], 2);
+        assert_eq!(tree.file_counts[Path::new("r/a")], 1);
+        assert!(!tree.file_counts.contains_key(Path::new("r/y")));
+    }
+
+    #[test]
+    fn prefers_rolled_up_file_counts() {
+        let entries = [
+            entry("r", 100, true, Some(7)),
+            entry("r/a", 75, true, Some(6)),
+            entry("r/y", 25, false, None),
+        ];
+        let (_, tree) = formatter().build_tree(&entries);
+        assert_eq!(tree.file_counts[Path::new("r")], 7);
+        assert_eq!(tree.file_counts[Path::new("r/a")], 6);
+    }
+
+    #[test]
+    fn shows_share_of_parent_and_file_counts() {
+        let entries = [
+            entry("r", 200, true, Some(3)),
+            entry("r/a", 150, true, Some(1)),
+            entry("r/y", 50, false, None),
+            entry("r/e", 0, true, Some(0)),
+            entry("r/e/z", 0, false, None),
+        ];
+        let formatter = formatter();
+        let (_, tree) = formatter.build_tree(&entries);
+        let line = |path: &str| formatter.format_entry(&[Path::new(path)], &tree);
+
+        assert!(line("r/a").ends_with(" 75% 1 file"), "{}", line("r/a"));
+        assert!(line("r/y").ends_with(" 25%"), "{}", line("r/y"));
+        assert!(line("r/e/z").ends_with(" 0%"), "{}", line("r/e/z"));
+        assert!(!line("r").contains('%'), "{}", line("r"));
+    }
+}
@@ -0,0 +1,422 @@
+use crate::utils::color::colorize_file_name;
+use crate::utils::size::{entry_size, format_size};
+use colored::*;
+use lla_plugin_interface::proto::DecoratedEntry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Terminal cells are roughly twice as tall as they are wide, so layouts are
+/// computed in a space stretched vertically by this factor to look square.
+const CELL_ASPECT: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+/// Splits `rect` into one rectangle per size, in order, with areas
+/// proportional to the sizes. Uses the squarified algorithm of Bruls,
+/// Huizing and van Wijk: sizes are taken largest first and laid out in rows
+/// along the shorter side, and a row grows only while that keeps its
+/// rectangles closer to square. `sizes` must be sorted in descending order.
+/// Zero sizes, and every size when `rect` has no area, get an empty
+/// rectangle so that each size still has one.
+fn squarify(sizes: &[f64], rect: Rect) -> Vec<Rect> {
+    let total: f64 = sizes.iter().sum();
+    let empty = |at: Rect| Rect {
+        x: at.x,
+        y: at.y,
+        w: 0.0,
+        h: 0.0,
+    };
+    if total <= 0.0 || rect.w <= 0.0 || rect.h <= 0.0 {
+        return vec![empty(rect); sizes.len()];
+    }
+
+    let scale = rect.w * rect.h / total;
+    let areas: Vec<f64> = sizes
+        .iter()
+        .take_while(|&&size| size > 0.0)
+        .map(|size| size * scale)
+        .collect();
+    let mut rects = Vec::with_capacity(sizes.len());
+    let mut free = rect;
+    let mut start = 0;
+
+    while start < areas.len() {
+        let side = free.w.min(free.h);
+        let mut end = start + 1;
+        while end < areas.len()
+            && worst_ratio(&areas[start..=end], side) <= worst_ratio(&areas[start..end], side)
+        {
+            end += 1;
+        }
+
+        let row = &areas[start..end];
+        let row_area: f64 = row.iter().sum();
+        if free.w >= free.h {
+            let width = row_area / free.h;
+            let mut y = free.y;
+            for area in row {
+                let h = area / width;
+                rects.push(Rect {
+                    x: free.x,
+                    y,
+                    w: width,
+                    h,
+                });
+                y += h;
+            }
+            free.x += width;
+            free.w -= width;
+        } else {
+            let height = row_area / free.w;
+            let mut x = free.x;
+            for area in row {
+                let w = area / height;
+                rects.push(Rect {
+                    x,
+                    y: free.y,
+                    w,
+                    h: height,
+                });
+                x += w;
+            }
+            free.y += height;
+            free.h -= height;
+        }
+        start = end;
+    }
+
+    rects.resize(sizes.len(), empty(free));
+    rects
+}
+
+/// The worst aspect ratio among `row` when laid along a side of length `side`.
+fn worst_ratio(row: &[f64], side: f64) -> f64 {
+    let sum: f64 = row.iter().sum();
+    let max = row.iter().cloned().fold(f64::MIN, f64::max);
+    let min = row.iter().cloned().fold(f64::MAX, f64::min);
+    let (sum2, side2) = (sum * sum, side * side);
+    (side2 * max / sum2).max(sum2 / (side2 * min))
+}
+
+#[derive(Clone, Copy)]
+struct Cell {
+    ch: char,
+    color: Option<Color>,
+}
+
+/// A grid of colored characters the treemap is drawn onto.
+struct Canvas {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![
+                Cell {
+                    ch: ' ',
+                    color: None
+                };
+                width * height
+            ],
+        }
+    }
+
+    fn set(&mut self, x: usize, y: usize, ch: char, color: Option<Color>) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = Cell { ch, color };
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut output = String::new();
+        for row in self.cells.chunks(self.width) {
+            let mut run = String::new();
+            let mut run_color = None;
+            for cell in row {
+                if cell.color != run_color && !run.is_empty() {
+                    output.push_str(&paint(&run, run_color));
+                    run.clear();
+                }
+                run_color = cell.color;
+                run.push(cell.ch);
+            }
+            output.push_str(&paint(run.trim_end(), run_color));
+            output.push('\n');
+        }
+        output
+    }
+}
+
+fn paint(text: &str, color: Option<Color>) -> String {
+    match color {
+        Some(color) => text.color(color).to_string(),
+        None => text.to_string(),
+    }
+}
+
+/// Draws entries as nested rectangles sized by their share of the total.
+/// Directories recurse into their children up to `max_depth`, and blocks
+/// large enough for it are labelled with their name and size.
+pub(super) struct Treemap<'a> {
+    children: HashMap<PathBuf, Vec<&'a DecoratedEntry>>,
+    roots: Vec<&'a DecoratedEntry>,
+    max_depth: Option<usize>,
+}
+
+impl<'a> Treemap<'a> {
+    pub(super) fn new(files: &'a [DecoratedEntry], max_depth: Option<usize>) -> Self {
+        let paths: HashSet<PathBuf> = files.iter().map(|entry| entry.fs_path()).collect();
+        let mut children: HashMap<PathBuf, Vec<&DecoratedEntry>> = HashMap::new();
+        let mut roots = Vec::new();
+
+        for entry in files {
+            match entry.fs_path().parent() {
+                Some(parent) if paths.contains(parent) => children
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(entry),
+                _ => roots.push(entry),
+            }
+        }
+
+        Self {
+            children,
+            roots,
+            max_depth,
+        }
+    }
+
+    /// The entries filling the whole map: the contents of the listed
+    /// directory when there is one, otherwise every root entry.
+    pub(super) fn top_level(&self) -> (Option<&'a DecoratedEntry>, Vec<&'a DecoratedEntry>) {
+        match self.roots[..] {
+            [root] if self.children.contains_key(&root.fs_path()) => {
+                (Some(root), self.children[&root.fs_path()].clone())
+            }
+            _ => (None, self.roots.clone()),
+        }
+    }
+
+    pub(super) fn render(&self, width: usize, height: usize) -> String {
+        let mut canvas = Canvas::new(width, height);
+        let (_, top) = self.top_level();
+        let area = Rect {
+            x: 0.0,
+            y: 0.0,
+            w: width as f64,
+            h: height as f64 * CELL_ASPECT,
+        };
+        self.layout(&mut canvas, top, area, 1);
+        canvas.render()
+    }
+
+    fn layout(&self, canvas: &mut Canvas, entries: Vec<&DecoratedEntry>, area: Rect, depth: usize) {
+        let mut entries: Vec<_> = entries
+            .into_iter()
+            .filter(|entry| entry_size(entry) > 0)
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry_size(entry)));
+
+        let sizes: Vec<f64> = entries
+            .iter()
+            .map(|entry| entry_size(entry) as f64)
+            .collect();
+        for (entry, rect) in entries.into_iter().zip(squarify(&sizes, area)) {
+            self.draw(canvas, entry, rect, depth);
+        }
+    }
+
+    fn draw(&self, canvas: &mut Canvas, entry: &DecoratedEntry, rect: Rect, depth: usize) {
+        let x0 = rect.x.round() as usize;
+        let x1 = (rect.x + rect.w).round() as usize;
+        let y0 = (rect.y / CELL_ASPECT).round() as usize;
+        let y1 = ((rect.y + rect.h) / CELL_ASPECT).round() as usize;
+        if x1 <= x0 || y1 <= y0 {
+            return;
+        }
+
+        let path = entry.fs_path();
+        let color = colorize_file_name(&path).fgcolor();
+        let (w, h) = (x1 - x0, y1 - y0);
+
+        if w < 2 || h < 2 {
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    canvas.set(x, y, '▒', color);
+                }
+            }
+            return;
+        }
+
+        for x in x0 + 1..x1 - 1 {
+            canvas.set(x, y0, '─', color);
+            canvas.set(x, y1 - 1, '─', color);
+        }
+        for y in y0 + 1..y1 - 1 {
+            canvas.set(x0, y, '│', color);
+            canvas.set(x1 - 1, y, '│', color);
+        }
+        canvas.set(x0, y0, '┌', color);
+        canvas.set(x1 - 1, y0, '┐', color);
+        canvas.set(x0, y1 - 1, '└', color);
+        canvas.set(x1 - 1, y1 - 1, '┘', color);
+
+        Self::label(canvas, &path, entry_size(entry), x0 + 1, y0, w - 2, color);
+
+        let within_depth = self.max_depth.map_or(true, |max| depth < max);
+        if let Some(children) = self.children.get(&path) {
+            if within_depth && w > 2 && h > 2 {
+                let inner = Rect {
+                    x: (x0 + 1) as f64,
+                    y: (y0 + 1) as f64 * CELL_ASPECT,
+                    w: (w - 2) as f64,
+                    h: (h - 2) as f64 * CELL_ASPECT,
+                };
+                self.layout(canvas, children.clone(), inner, depth + 1);
+            }
+        }
+    }
+
+    /// Writes `name size` along the top edge, shortening the name to fit and
+    /// leaving blocks too narrow for a useful label unlabelled.
+    fn label(
+        canvas: &mut Canvas,
+        path: &Path,
+        size: u64,
+        x: usize,
+        y: usize,
+        width: usize,
+        color: Option<Color>,
+    ) {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string_lossy().into_owned());
+        let size = format_size(size);
+
+        let full = format!("{} {}", name, size);
+        let text: String = if full.chars().count() <= width {
+            full
+        } else if width >= 4 {
+            let mut short: String = name.chars().take(width - 1).collect();
+            short.push('…');
+            short
+        } else {
+            return;
+        };
+
+        for (i, ch) in text.chars().enumerate() {
+            canvas.set(x + i, y, ch, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-6;
+
+    fn rect(w: f64, h: f64) -> Rect {
+        Rect {
+            x: 3.0,
+            y: 5.0,
+            w,
+            h,
+        }
+    }
+
+    fn overlap(a: &Rect, b: &Rect) -> f64 {
+        let w = (a.x + a.w).min(b.x + b.w) - a.x.max(b.x);
+        let h = (a.y + a.h).min(b.y + b.h) - a.y.max(b.y);
+        w.max(0.0) * h.max(0.0)
+    }
+
+    /// Checks that the rectangles fill `area` in proportion to `sizes`
+    /// without overlapping or leaving it.
+    fn assert_tiles(sizes: &[f64], area: Rect) {
+        let rects = squarify(sizes, area);
+        assert_eq!(rects.len(), sizes.len());
+
+        let total: f64 = sizes.iter().sum();
+        for (size, r) in sizes.iter().zip(&rects) {
+            assert!(r.w >= 0.0 && r.h >= 0.0, "{:?}", r);
+            let expected = size / total * area.w * area.h;
+            assert!(
+                (r.w * r.h - expected).abs() < EPSILON,
+                "{:?} {}",
+                r,
+                expected
+            );
+            if r.w * r.h > 0.0 {
+                assert!(r.x >= area.x - EPSILON && r.y >= area.y - EPSILON);
+                assert!(r.x + r.w <= area.x + area.w + EPSILON, "{:?}", r);
+                assert!(r.y + r.h <= area.y + area.h + EPSILON, "{:?}", r);
+            }
+        }
+        for (i, a) in rects.iter().enumerate() {
+            for b in &rects[i + 1..] {
+                assert!(overlap(a, b) < EPSILON, "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn tiles_the_area() {
+        let cases: [&[f64]; 5] = [
+            &[1.0],
+            &[6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0],
+            &[1000.0, 1.0, 1.0],
+            &[5.0, 5.0, 5.0, 5.0, 5.0],
+            &[9.0, 7.0, 0.5, 0.25, 0.125],
+        ];
+        for sizes in cases {
+            assert_tiles(sizes, rect(80.0, 48.0));
+            assert_tiles(sizes, rect(6.0, 40.0));
+            assert_tiles(sizes, rect(1.0, 2.0));
+        }
+    }
+
+    #[test]
+    fn gives_zero_sizes_empty_rectangles() {
+        assert_tiles(&[4.0, 2.0, 0.0, 0.0], rect(10.0, 10.0));
+        let rects = squarify(&[0.0, 0.0], rect(10.0, 10.0));
+        assert_eq!(rects.len(), 2);
+        assert!(rects.iter().all(|r| r.w == 0.0 && r.h == 0.0));
+    }
+
+    #[test]
+    fn handles_areas_without_room() {
+        for area in [rect(0.0, 0.0), rect(0.0, 10.0), rect(10.0, 0.0)] {
+            let rects = squarify(&[3.0, 1.0], area);
+            assert_eq!(rects.len(), 2);
+            assert!(rects.iter().all(|r| r.w * r.h == 0.0));
+        }
+    }
+
+    #[test]
+    fn renders_into_a_single_cell() {
+        let entry = |path: &str, size: u64| {
+            let metadata = lla_plugin_interface::proto::EntryMetadata {
+                size,
+                is_file: true,
+                ..Default::default()
+            };
+            DecoratedEntry::new(Path::new(path), Some(metadata), Default::default())
+        };
+        let files = [entry("a", 3), entry("b", 1)];
+        let treemap = Treemap::new(&files, None);
+        assert_eq!(treemap.render(1, 1).lines().count(), 1);
+    }
+}
@@ -1,7 +1,8 @@
+use super::walk::{is_filtering, walk_builder};
 use super::FileLister;
 use crate::error::Result;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct BasicLister;
 
@@ -15,6 +16,18 @@ impl FileLister for BasicLister {
         let mut files = Vec::with_capacity(16);
 
         let entries = fs::read_dir(directory)?;
+        if is_filtering() {
+            files.extend(
+                walk_builder(Path::new(directory))
+                    .max_depth(Some(1))
+                    .build()
+                    .flatten()
+                    .filter(|entry| entry.depth() > 0)
+                    .map(|entry| entry.into_path()),
+            );
+            return Ok(files);
+        }
+
         for entry in entries.flatten() {
             files.push(entry.path());
         }
@@ -1,6 +1,8 @@
+use super::walk::walk_builder;
 use super::FileLister;
 use crate::utils::color::*;
 use crate::utils::icons::format_with_icon;
+use crate::utils::size::file_size;
 use crate::{error::Result, theme::color_value_to_color};
 use colored::*;
 use crossbeam_channel::bounded;
@@ -11,7 +13,6 @@ use crossterm::{
     style::{self},
     terminal::{self, ClearType},
 };
-use ignore::WalkBuilder;
 use parking_lot::RwLock;
 use rayon::prelude::*;
 use std::collections::HashMap;
@@ -464,10 +465,12 @@ impl FuzzyLister {
         let directory = directory.to_string();
 
         thread::spawn(move || {
-            let walker = WalkBuilder::new(&directory)
-                .hidden(false)
-                .git_ignore(false)
-                .ignore(false)
+            // Fuzzy search has always honoured the global gitignore and
+            // `.git/info/exclude`, even without `--git-ignore`.
+            let walker = walk_builder(std::path::Path::new(&directory))
+                .parents(true)
+                .git_global(true)
+                .git_exclude(true)
                 .follow_links(false)
                 .same_file_system(false)
                 .threads(num_cpus::get())
@@ -912,7 +915,7 @@ impl ResultList {
                 let path = &result.entry.path;
                 let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
                 let metadata = path.metadata().ok();
-                let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+                let size = metadata.as_ref().map(file_size).unwrap_or(0);
                 let modified = metadata
                     .as_ref()
                     .and_then(|m| m.modified().ok())
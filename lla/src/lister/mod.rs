@@ -13,7 +13,13 @@ pub trait FileLister {
 mod basic;
 mod fuzzy;
 mod recursive;
+mod repos;
+mod walk;
 
 pub use basic::BasicLister;
 pub use fuzzy::FuzzyLister;
 pub use recursive::RecursiveLister;
+pub use repos::RepoLister;
+pub use walk::{
+    is_filtering, set_ignore_options, validate_exclude_pattern, walk_builder, IgnoreOptions,
+};
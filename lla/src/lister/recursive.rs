@@ -1,12 +1,13 @@
+use super::walk::walk_builder;
 use super::FileLister;
 use crate::config::Config;
 use crate::error::Result;
 use crate::lister::BasicLister;
+use ignore::DirEntry;
 use rayon::prelude::*;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use walkdir::{DirEntry, WalkDir};
 
 const PARALLEL_THRESHOLD: usize = 1000;
 const BUFFER_CAPACITY: usize = 1024;
@@ -32,12 +33,14 @@ impl RecursiveLister {
         entry: &DirEntry,
         counter: &Arc<AtomicUsize>,
         max_entries: usize,
+        truncated: &AtomicBool,
     ) -> bool {
         if counter.load(Ordering::Relaxed) >= max_entries {
+            truncated.store(true, Ordering::Relaxed);
             return false;
         }
 
-        if !entry.file_type().is_file() {
+        if !entry.file_type().map_or(false, |t| t.is_file()) {
             return true;
         }
 
@@ -70,18 +73,25 @@ impl FileLister for RecursiveLister {
             .unwrap_or(usize::MAX);
 
         let counter = Arc::new(AtomicUsize::new(0));
+        let truncated = AtomicBool::new(false);
         let mut entries = Vec::with_capacity(BUFFER_CAPACITY);
 
-        let walker = WalkDir::new(directory)
-            .min_depth(0)
-            .max_depth(max_depth)
+        let walker = walk_builder(Path::new(directory))
+            .max_depth(Some(max_depth))
             .follow_links(false)
             .same_file_system(true)
-            .into_iter()
+            .build()
             .filter_map(|e| e.ok())
-            .filter(|e| Self::should_process_entry(e, &counter, max_entries))
+            .filter(|e| Self::should_process_entry(e, &counter, max_entries, &truncated))
             .collect::<Vec<_>>();
 
+        if truncated.into_inner() {
+            eprintln!(
+                "Warning: listing stopped after {} files; raise listers.recursive.max_entries to see more",
+                max_entries
+            );
+        }
+
         if walker.len() > PARALLEL_THRESHOLD {
             entries.par_extend(walker.into_par_iter().map(|e| e.into_path()));
         } else {
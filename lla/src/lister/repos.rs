@@ -0,0 +1,51 @@
+use super::walk::walk_builder;
+use super::FileLister;
+use crate::error::Result;
+use ignore::WalkState;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Lists the git work trees at or below a directory: every directory with a
+/// `.git` directory or file, which covers nested repositories, submodules and
+/// linked work trees.
+pub struct RepoLister;
+
+impl FileLister for RepoLister {
+    fn list_files(
+        &self,
+        directory: &str,
+        _recursive: bool,
+        depth: Option<usize>,
+    ) -> Result<Vec<PathBuf>> {
+        let repos = Mutex::new(Vec::new());
+
+        // Each repository's ignore rules prune its `node_modules/`, `target/`
+        // and the like; repositories nested anywhere else are still found.
+        walk_builder(Path::new(directory))
+            .parents(true)
+            .git_ignore(true)
+            .git_global(true)
+            .git_exclude(true)
+            .max_depth(depth)
+            .follow_links(false)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build_parallel()
+            .run(|| {
+                let repos = &repos;
+                Box::new(move |entry| {
+                    let entry = match entry {
+                        Ok(entry) => entry,
+                        Err(_) => return WalkState::Continue,
+                    };
+                    if entry.file_type().map_or(false, |kind| kind.is_dir())
+                        && entry.path().join(".git").exists()
+                    {
+                        repos.lock().unwrap().push(entry.into_path());
+                    }
+                    WalkState::Continue
+                })
+            });
+
+        Ok(repos.into_inner().unwrap())
+    }
+}
@@ -0,0 +1,79 @@
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+static IGNORE_OPTIONS: OnceLock<IgnoreOptions> = OnceLock::new();
+
+/// Which entries the listers should skip: paths matched by gitignore-style
+/// files and by the `filter.exclude` patterns from the config.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreOptions {
+    pub git_ignore: bool,
+    pub ignore_files: Vec<PathBuf>,
+    pub exclude: Vec<String>,
+}
+
+impl IgnoreOptions {
+    fn is_active(&self) -> bool {
+        self.git_ignore || !self.ignore_files.is_empty() || !self.exclude.is_empty()
+    }
+}
+
+pub fn set_ignore_options(options: IgnoreOptions) {
+    let _ = IGNORE_OPTIONS.set(options);
+}
+
+fn ignore_options() -> &'static IgnoreOptions {
+    IGNORE_OPTIONS.get_or_init(IgnoreOptions::default)
+}
+
+/// Whether any ignore rules are in effect, so callers can keep their cheaper
+/// `read_dir` based paths when nothing needs to be skipped.
+pub fn is_filtering() -> bool {
+    ignore_options().is_active()
+}
+
+/// Checks that an exclude pattern is a valid gitignore-style glob.
+pub fn validate_exclude_pattern(pattern: &str) -> Result<(), String> {
+    OverrideBuilder::new("/")
+        .add(&format!("!{}", pattern))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Builds a walker rooted at `root` that shows hidden files and applies only
+/// the ignore rules the user asked for. With `--git-ignore` this covers
+/// `.gitignore`, `.ignore`, `.git/info/exclude` and the global gitignore,
+/// including those in parent directories of `root`, and skips `.git` itself.
+pub fn walk_builder(root: &Path) -> WalkBuilder {
+    let options = ignore_options();
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .standard_filters(false)
+        .parents(options.git_ignore)
+        .ignore(options.git_ignore)
+        .git_ignore(options.git_ignore)
+        .git_global(options.git_ignore)
+        .git_exclude(options.git_ignore);
+
+    if options.git_ignore {
+        builder.filter_entry(|entry| entry.depth() == 0 || entry.file_name() != ".git");
+    }
+
+    for file in &options.ignore_files {
+        builder.add_ignore(file);
+    }
+
+    if !options.exclude.is_empty() {
+        let mut overrides = OverrideBuilder::new(root);
+        for pattern in &options.exclude {
+            let _ = overrides.add(&format!("!{}", pattern));
+        }
+        if let Ok(overrides) = overrides.build() {
+            builder.overrides(overrides);
+        }
+    }
+
+    builder
+}
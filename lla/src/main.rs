@@ -24,6 +24,16 @@ fn main() -> Result<()> {
 
     let args = Args::parse(&config);
     theme::set_no_color(args.no_color);
+    utils::time::set_time_options(args.time_field, args.time_style.clone());
+    utils::size::set_size_options(args.size_style, args.disk_usage);
+    utils::symlink::set_dereference(args.dereference);
+    utils::color::set_classify(args.classify);
+    utils::quoting::set_quoting_style(args.quoting_style);
+    lister::set_ignore_options(lister::IgnoreOptions {
+        git_ignore: args.git_ignore,
+        ignore_files: args.ignore_files.clone(),
+        exclude: config.filter.exclude.clone(),
+    });
 
     if let Some(Command::Clean) = args.command {
         println!("🔄 Starting plugin cleaning...");
@@ -1,5 +1,6 @@
 use super::{compare_dirs_first, FileSorter, SortOptions};
 use crate::error::Result;
+use crate::utils::time::time_field;
 use lla_plugin_interface::proto::DecoratedEntry;
 use rayon::prelude::*;
 use std::path::PathBuf;
@@ -12,6 +13,7 @@ impl FileSorter for DateSorter {
         entries: &mut [(PathBuf, &DecoratedEntry)],
         options: SortOptions,
     ) -> Result<()> {
+        let field = time_field();
         entries.par_sort_unstable_by(|(path_a, entry_a), (path_b, entry_b)| {
             let dir_order = compare_dirs_first(path_a, path_b, options.dirs_first);
             if dir_order != std::cmp::Ordering::Equal {
@@ -22,8 +24,8 @@ impl FileSorter for DateSorter {
                 };
             }
 
-            let time_a = entry_a.metadata.as_ref().map_or(0, |m| m.modified);
-            let time_b = entry_b.metadata.as_ref().map_or(0, |m| m.modified);
+            let time_a = field.timestamp(entry_a);
+            let time_b = field.timestamp(entry_b);
             let date_order = time_a.cmp(&time_b);
 
             if options.reverse {
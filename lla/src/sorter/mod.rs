@@ -19,11 +19,13 @@ pub trait FileSorter: Send + Sync {
 
 mod alphabetical;
 mod date;
+mod multi;
 mod size;
 
 pub use alphabetical::AlphabeticalSorter;
 pub use date::DateSorter;
 use lla_plugin_interface::proto::DecoratedEntry;
+pub use multi::{MultiSorter, SortField, SortKey};
 pub use size::SizeSorter;
 
 pub(crate) fn compare_dirs_first(a: &PathBuf, b: &PathBuf, dirs_first: bool) -> std::cmp::Ordering {
@@ -74,3 +76,64 @@ pub(crate) fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
         }
     }
 }
+
+/// Compares names like `v1.10.0` and `v1.9.2` the way versions are ordered:
+/// runs of digits compare by value, everything else character by character.
+pub(crate) fn version_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    fn chunks(s: &str) -> Vec<(bool, &str)> {
+        let mut chunks = Vec::new();
+        let mut rest = s;
+        while let Some(first) = rest.chars().next() {
+            let digits = first.is_ascii_digit();
+            let end = rest
+                .find(|c: char| c.is_ascii_digit() != digits)
+                .unwrap_or(rest.len());
+            chunks.push((digits, &rest[..end]));
+            rest = &rest[end..];
+        }
+        chunks
+    }
+
+    let a_chunks = chunks(a);
+    let b_chunks = chunks(b);
+    for (a_chunk, b_chunk) in a_chunks.iter().zip(&b_chunks) {
+        let order = match (a_chunk, b_chunk) {
+            ((true, a_digits), (true, b_digits)) => {
+                let a_digits = a_digits.trim_start_matches('0');
+                let b_digits = b_digits.trim_start_matches('0');
+                a_digits
+                    .len()
+                    .cmp(&b_digits.len())
+                    .then_with(|| a_digits.cmp(b_digits))
+            }
+            ((_, a_text), (_, b_text)) => a_text.cmp(b_text),
+        };
+        if order != std::cmp::Ordering::Equal {
+            return order;
+        }
+    }
+    a_chunks.len().cmp(&b_chunks.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn orders_numbers_by_value() {
+        assert_eq!(version_cmp("v1.9", "v1.10"), Ordering::Less);
+        assert_eq!(version_cmp("v1.10.0", "v1.9.2"), Ordering::Greater);
+        assert_eq!(version_cmp("v2", "v10"), Ordering::Less);
+        assert_eq!(version_cmp("v1.2", "v1.2.1"), Ordering::Less);
+        assert_eq!(version_cmp("v1.2", "v1.2"), Ordering::Equal);
+    }
+
+    #[test]
+    fn ignores_leading_zeros() {
+        assert_eq!(version_cmp("img002", "img10"), Ordering::Less);
+        assert_eq!(version_cmp("v1.010", "v1.9"), Ordering::Greater);
+        assert_eq!(version_cmp("v007", "v7"), Ordering::Equal);
+        assert_eq!(version_cmp("v0", "v00"), Ordering::Equal);
+    }
+}
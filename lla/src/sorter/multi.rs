@@ -0,0 +1,319 @@
+use super::{natural_cmp, version_cmp, FileSorter, SortOptions};
+use crate::error::Result;
+use crate::formatter::{group_name, user_name};
+use crate::utils::git::{self, StatusRanks};
+use crate::utils::size::entry_size;
+use crate::utils::time::{time_field, TimeField};
+use lla_plugin_interface::proto::DecoratedEntry;
+use lla_plugin_interface::FileType;
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortField {
+    Name,
+    Extension,
+    Size,
+    /// `None` uses the timestamp selected with `--time`.
+    Time(Option<TimeField>),
+    Type,
+    Version,
+    Depth,
+    Owner,
+    Group,
+    GitStatus,
+    Field(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl SortKey {
+    pub const NAMES: [&'static str; 14] = [
+        "name",
+        "extension",
+        "size",
+        "date",
+        "modified",
+        "accessed",
+        "created",
+        "changed",
+        "type",
+        "version",
+        "depth",
+        "owner",
+        "group",
+        "git",
+    ];
+
+    /// Parses a comma-separated list such as `ext,size:desc,name`. Each key
+    /// may end in `:asc` or `:desc`; sizes sort largest first by default and
+    /// everything else ascending. `field:NAME` sorts by a plugin field.
+    pub fn parse_list(spec: &str) -> std::result::Result<Vec<SortKey>, String> {
+        let keys = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(Self::parse)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Err("expected at least one sort key".to_string());
+        }
+        Ok(keys)
+    }
+
+    fn parse(spec: &str) -> std::result::Result<SortKey, String> {
+        let (name, direction) = match spec.rsplit_once(':') {
+            Some((name, direction @ ("asc" | "desc"))) => (name, Some(direction == "desc")),
+            _ => (spec, None),
+        };
+
+        let field = match name {
+            "name" => SortField::Name,
+            "extension" | "ext" => SortField::Extension,
+            "size" => SortField::Size,
+            "date" | "time" => SortField::Time(None),
+            "type" => SortField::Type,
+            "version" => SortField::Version,
+            "depth" => SortField::Depth,
+            "owner" | "user" => SortField::Owner,
+            "group" => SortField::Group,
+            "git" | "git_status" => SortField::GitStatus,
+            name => match (name.strip_prefix("field:"), TimeField::parse(name)) {
+                (Some(field), _) if !field.is_empty() => SortField::Field(field.to_string()),
+                (_, Some(time)) => SortField::Time(Some(time)),
+                _ => {
+                    return Err(format!(
+                        "unknown sort key '{}' (use {}, or field:NAME)",
+                        name,
+                        Self::NAMES.join(", ")
+                    ))
+                }
+            },
+        };
+
+        let descending = direction.unwrap_or(field == SortField::Size);
+        Ok(SortKey { field, descending })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum SortValue {
+    Text(String),
+    Version(String),
+    Number(f64),
+    Missing,
+}
+
+/// Sorts by several keys in turn, falling through to the next key on ties.
+/// Sort values are computed once per entry before sorting.
+pub struct MultiSorter {
+    keys: Vec<SortKey>,
+}
+
+impl MultiSorter {
+    pub fn new(keys: Vec<SortKey>) -> Self {
+        Self { keys }
+    }
+
+    fn value(
+        &self,
+        field: &SortField,
+        path: &Path,
+        entry: &DecoratedEntry,
+        git: Option<&StatusRanks>,
+        options: SortOptions,
+    ) -> SortValue {
+        let metadata = entry.metadata.clone().unwrap_or_default();
+        let fold = |text: String| {
+            if options.case_sensitive {
+                text
+            } else {
+                text.to_lowercase()
+            }
+        };
+
+        match field {
+            SortField::Name => SortValue::Text(fold(
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            )),
+            SortField::Extension => SortValue::Text(fold(
+                path.extension()
+                    .map(|ext| ext.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            )),
+            SortField::Size => SortValue::Number(entry_size(entry) as f64),
+            SortField::Time(field) => {
+                SortValue::Number(field.unwrap_or_else(time_field).timestamp(entry) as f64)
+            }
+            SortField::Type => SortValue::Number(type_rank(metadata.kind()) as f64),
+            SortField::Version => SortValue::Version(
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            SortField::Depth => SortValue::Number(
+                path.components()
+                    .filter(|c| matches!(c, Component::Normal(_)))
+                    .count() as f64,
+            ),
+            SortField::Owner => SortValue::Text(user_name(metadata.uid)),
+            SortField::Group => SortValue::Text(group_name(metadata.gid)),
+            SortField::GitStatus => SortValue::Number(
+                git.map_or_else(|| git::status_rank(None), |git| git.rank(path)) as f64,
+            ),
+            SortField::Field(name) => match entry.custom_fields.get(name) {
+                Some(value) => match value.trim().parse::<f64>() {
+                    Ok(number) => SortValue::Number(number),
+                    Err(_) => SortValue::Text(fold(value.clone())),
+                },
+                None => SortValue::Missing,
+            },
+        }
+    }
+}
+
+fn type_rank(file_type: FileType) -> u8 {
+    match file_type {
+        FileType::Directory => 0,
+        FileType::Symlink => 1,
+        FileType::Regular => 2,
+        FileType::Fifo => 3,
+        FileType::Socket => 4,
+        FileType::BlockDevice => 5,
+        FileType::CharDevice => 6,
+        FileType::Unknown => 7,
+    }
+}
+
+/// Missing values always sort last, whatever the direction.
+fn compare_values(a: &SortValue, b: &SortValue, key: &SortKey, options: SortOptions) -> Ordering {
+    let order = match (a, b) {
+        (SortValue::Missing, SortValue::Missing) => return Ordering::Equal,
+        (SortValue::Missing, _) => return Ordering::Greater,
+        (_, SortValue::Missing) => return Ordering::Less,
+        (SortValue::Number(a), SortValue::Number(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (SortValue::Number(_), _) => Ordering::Less,
+        (_, SortValue::Number(_)) => Ordering::Greater,
+        (SortValue::Text(a), SortValue::Text(b)) if options.natural => natural_cmp(a, b),
+        (SortValue::Text(a), SortValue::Text(b)) => a.cmp(b),
+        (SortValue::Version(a), SortValue::Version(b)) => version_cmp(a, b),
+        (SortValue::Text(a), SortValue::Version(b))
+        | (SortValue::Version(a), SortValue::Text(b)) => a.cmp(b),
+    };
+
+    if key.descending {
+        order.reverse()
+    } else {
+        order
+    }
+}
+
+impl FileSorter for MultiSorter {
+    fn sort_files_with_metadata(
+        &self,
+        entries: &mut [(PathBuf, &DecoratedEntry)],
+        options: SortOptions,
+    ) -> Result<()> {
+        let git = self
+            .keys
+            .iter()
+            .any(|key| key.field == SortField::GitStatus)
+            .then(|| StatusRanks::load(entries.first()?.0.as_path()))
+            .flatten();
+        let values: Vec<(bool, Vec<SortValue>)> = entries
+            .par_iter()
+            .map(|(path, entry)| {
+                let is_dir = options.dirs_first && path.is_dir();
+                let values = self
+                    .keys
+                    .iter()
+                    .map(|key| self.value(&key.field, path, entry, git.as_ref(), options))
+                    .collect();
+                (is_dir, values)
+            })
+            .collect();
+
+        let mut order: Vec<usize> = (0..entries.len()).collect();
+        order.par_sort_by(|&a, &b| {
+            let (a_is_dir, a_values) = &values[a];
+            let (b_is_dir, b_values) = &values[b];
+            let order = b_is_dir.cmp(a_is_dir).then_with(|| {
+                self.keys
+                    .iter()
+                    .zip(a_values.iter().zip(b_values))
+                    .map(|(key, (a, b))| compare_values(a, b, key, options))
+                    .find(|order| *order != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+
+            if options.reverse {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+
+        let sorted: Vec<_> = order.iter().map(|&i| entries[i].clone()).collect();
+        entries.clone_from_slice(&sorted);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(field: SortField, descending: bool) -> SortKey {
+        SortKey { field, descending }
+    }
+
+    #[test]
+    fn parses_keys_with_directions() {
+        assert_eq!(
+            SortKey::parse_list("ext, size, name:desc,date:asc").unwrap(),
+            [
+                key(SortField::Extension, false),
+                key(SortField::Size, true),
+                key(SortField::Name, true),
+                key(SortField::Time(None), false),
+            ]
+        );
+        assert_eq!(
+            SortKey::parse_list("size:asc,accessed:desc").unwrap(),
+            [
+                key(SortField::Size, false),
+                key(SortField::Time(Some(TimeField::Accessed)), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_plugin_fields() {
+        assert_eq!(
+            SortKey::parse_list("field:git_status:desc,field:a:b").unwrap(),
+            [
+                key(SortField::Field("git_status".to_string()), true),
+                key(SortField::Field("a:b".to_string()), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_missing_keys() {
+        assert!(SortKey::parse_list("nmae")
+            .unwrap_err()
+            .contains("unknown sort key 'nmae'"));
+        assert!(SortKey::parse_list("name:up").is_err());
+        assert!(SortKey::parse_list("field:").is_err());
+        assert!(SortKey::parse_list(" , ").is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use super::{compare_dirs_first, FileSorter, SortOptions};
 use crate::error::Result;
+use crate::utils::size::entry_size;
 use lla_plugin_interface::proto::DecoratedEntry;
 use rayon::prelude::*;
 use std::path::PathBuf;
@@ -22,9 +23,7 @@ impl FileSorter for SizeSorter {
                 };
             }
 
-            let size_a = entry_a.metadata.as_ref().map_or(0, |m| m.size);
-            let size_b = entry_b.metadata.as_ref().map_or(0, |m| m.size);
-            let size_order = size_a.cmp(&size_b);
+            let size_order = entry_size(entry_a).cmp(&entry_size(entry_b));
 
             if options.reverse {
                 size_order
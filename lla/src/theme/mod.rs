@@ -76,6 +76,8 @@ pub struct ThemeColors {
     pub directory: ColorValue,
     #[serde(default = "default_symlink_color")]
     pub symlink: ColorValue,
+    #[serde(default = "default_symlink_broken_color")]
+    pub symlink_broken: ColorValue,
     #[serde(default = "default_executable_color")]
     pub executable: ColorValue,
     #[serde(default = "default_size_color")]
@@ -117,6 +119,7 @@ impl Default for ThemeColors {
             file: default_file_color(),
             directory: default_directory_color(),
             symlink: default_symlink_color(),
+            symlink_broken: default_symlink_broken_color(),
             executable: default_executable_color(),
             size: default_size_color(),
             date: default_date_color(),
@@ -140,6 +143,9 @@ fn default_directory_color() -> ColorValue {
 fn default_symlink_color() -> ColorValue {
     ColorValue::Named("bright_cyan".to_string())
 }
+fn default_symlink_broken_color() -> ColorValue {
+    ColorValue::Named("bright_red".to_string())
+}
 fn default_executable_color() -> ColorValue {
     ColorValue::Named("bright_green".to_string())
 }
@@ -1,10 +1,17 @@
 use crate::commands::args::Args;
 use crate::theme::{color_value_to_color, get_file_color, is_no_color, ColorValue, Theme};
+use crate::utils::quoting::quote_name;
+use crate::utils::size::format_size;
+use crate::utils::symlink::{dereference, link_status, LinkStatus};
+use crate::utils::time::{time_style, TimeStyle};
 use colored::*;
+use lla_plugin_interface::FileType;
+use std::os::unix::fs::MetadataExt;
 use std::path::Path;
 use std::sync::OnceLock;
 
 static CURRENT_THEME: OnceLock<Theme> = OnceLock::new();
+static CLASSIFY: OnceLock<bool> = OnceLock::new();
 
 pub fn set_theme(theme: Theme) {
     let _ = CURRENT_THEME.set(theme);
@@ -14,19 +21,56 @@ pub fn get_theme() -> &'static Theme {
     CURRENT_THEME.get_or_init(Theme::default)
 }
 
+pub fn set_classify(classify: bool) {
+    let _ = CLASSIFY.set(classify);
+}
+
+fn classify() -> bool {
+    *CLASSIFY.get_or_init(|| false)
+}
+
+/// The `ls -F` indicator appended to names when classifying entries.
+fn classify_indicator(path: &Path) -> &'static str {
+    let metadata = if dereference() {
+        path.metadata().or_else(|_| path.symlink_metadata())
+    } else {
+        path.symlink_metadata()
+    };
+    let mode = match metadata {
+        Ok(metadata) => metadata.mode(),
+        Err(_) => return "",
+    };
+
+    match FileType::from_mode(mode) {
+        FileType::Directory => "/",
+        FileType::Symlink => "@",
+        FileType::Fifo => "|",
+        FileType::Socket => "=",
+        FileType::Regular if mode & 0o111 != 0 => "*",
+        _ => "",
+    }
+}
+
 fn get_color(color_value: &ColorValue) -> Color {
     color_value_to_color(color_value)
 }
 
 pub fn colorize_file_name(path: &Path) -> ColoredString {
-    let name = path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or_else(|| path.to_str().unwrap_or(""));
+    let name = quote_name(path.file_name().unwrap_or(path.as_os_str()));
+    let name = if classify() {
+        format!("{}{}", name, classify_indicator(path))
+    } else {
+        name
+    };
+    let dir_name = if classify() {
+        name.to_string()
+    } else {
+        format!("{}/", name)
+    };
 
     if is_no_color() {
         return if path.is_dir() {
-            format!("{}/", name).normal()
+            dir_name.normal()
         } else {
             name.normal()
         };
@@ -36,12 +80,16 @@ pub fn colorize_file_name(path: &Path) -> ColoredString {
 
     if path.is_dir() {
         if let Some(color) = get_file_color(path) {
-            format!("{}/", name).color(color).bold()
+            dir_name.color(color).bold()
         } else {
             name.to_string()
                 .color(get_color(&theme.colors.directory))
                 .bold()
         }
+    } else if path.is_symlink() && link_status(path) != LinkStatus::Valid {
+        name.color(get_color(&theme.colors.symlink_broken))
+            .italic()
+            .strikethrough()
     } else if path.is_symlink() {
         name.color(get_color(&theme.colors.symlink))
             .italic()
@@ -86,6 +134,11 @@ pub fn colorize_file_name_with_icon(path: &Path, content: String) -> ColoredStri
                 .color(get_color(&theme.colors.directory))
                 .bold()
         }
+    } else if path.is_symlink() && link_status(path) != LinkStatus::Valid {
+        format!("{} {}", icon, name)
+            .color(get_color(&theme.colors.symlink_broken))
+            .italic()
+            .strikethrough()
     } else if path.is_symlink() {
         format!("{} {}", icon, name)
             .color(get_color(&theme.colors.symlink))
@@ -103,15 +156,7 @@ pub fn colorize_file_name_with_icon(path: &Path, content: String) -> ColoredStri
 }
 
 pub fn colorize_size(size: u64) -> ColoredString {
-    let formatted = if size < 1024 {
-        format!("{}B", size)
-    } else if size < 1024 * 1024 {
-        format!("{:.1}K", size as f64 / 1024.0)
-    } else if size < 1024 * 1024 * 1024 {
-        format!("{:.1}M", size as f64 / (1024.0 * 1024.0))
-    } else {
-        format!("{:.1}G", size as f64 / (1024.0 * 1024.0 * 1024.0))
-    };
+    let formatted = format_size(size);
 
     if is_no_color() {
         formatted.normal()
@@ -149,10 +194,11 @@ pub fn colorize_permissions(permissions: &Permissions) -> String {
 
     let theme = get_theme();
 
-    let file_type = if mode & 0o40000 != 0 {
-        "d".color(get_color(&theme.colors.permission_dir))
-    } else {
-        "-".color(get_color(&theme.colors.permission_none))
+    let file_type = match file_type_char(mode) {
+        "d" => "d".color(get_color(&theme.colors.permission_dir)),
+        "l" => "l".color(get_color(&theme.colors.symlink)),
+        "-" => "-".color(get_color(&theme.colors.permission_none)),
+        other => other.color(get_color(&theme.colors.permission_read)),
     };
     let user = triplet(mode, 6);
     let group = triplet(mode, 3);
@@ -160,8 +206,8 @@ pub fn colorize_permissions(permissions: &Permissions) -> String {
     format!("{}{}{}{}", file_type, user, group, other)
 }
 
-fn format_permissions_no_color(mode: u32) -> String {
-    let file_type = if mode & 0o40000u32 != 0u32 { "d" } else { "-" };
+pub fn format_permissions_no_color(mode: u32) -> String {
+    let file_type = file_type_char(mode);
     let read = |shift| {
         if mode >> shift & 0o4u32 != 0u32 {
             "r"
@@ -199,6 +245,18 @@ fn format_permissions_no_color(mode: u32) -> String {
     )
 }
 
+fn file_type_char(mode: u32) -> &'static str {
+    match mode & 0o170000 {
+        0o040000 => "d",
+        0o120000 => "l",
+        0o010000 => "p",
+        0o140000 => "s",
+        0o020000 => "c",
+        0o060000 => "b",
+        _ => "-",
+    }
+}
+
 fn triplet(mode: u32, shift: u32) -> String {
     let theme = get_theme();
     let r = if mode >> (shift + 2) & 1u32 != 0 {
@@ -226,8 +284,7 @@ fn triplet(mode: u32, shift: u32) -> String {
 }
 
 pub fn colorize_date(date: &std::time::SystemTime) -> ColoredString {
-    let datetime: chrono::DateTime<chrono::Local> = (*date).into();
-    let formatted = datetime.format("%b %d %H:%M").to_string();
+    let formatted = time_style().unwrap_or(&TimeStyle::Default).format(date);
 
     if is_no_color() {
         formatted.normal()
@@ -0,0 +1,148 @@
+use lla_plugin_utils::git::{History, Repository};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Finds the root of the work tree containing `path`, if any.
+pub fn workspace_root(path: &Path) -> Option<PathBuf> {
+    Repository::discover(path).map(|repo| repo.work_dir().to_path_buf())
+}
+
+/// Maps paths relative to `workspace_root` to their porcelain `XY` status.
+/// Tracked, unmodified files map to `"."`.
+pub fn status_map(workspace_root: &Path) -> HashMap<String, String> {
+    Repository::discover(workspace_root)
+        .map(|repo| repo.status().entries().clone())
+        .unwrap_or_default()
+}
+
+/// Orders porcelain statuses from most to least interesting: conflicts,
+/// staged or unstaged changes, untracked, unchanged, ignored, and finally
+/// paths git knows nothing about.
+pub fn status_rank(status: Option<&str>) -> u8 {
+    match status {
+        Some(s) if s.contains('U') => 0,
+        Some("??") => 3,
+        Some("!!") => 5,
+        Some(".") => 4,
+        Some(s) if s.starts_with('.') => 2,
+        Some(_) => 1,
+        None => 6,
+    }
+}
+
+/// The `status_rank` of every path in a work tree, where each directory takes
+/// the most interesting rank found beneath it.
+pub struct StatusRanks {
+    root: PathBuf,
+    current_dir: PathBuf,
+    ranks: HashMap<PathBuf, u8>,
+}
+
+impl StatusRanks {
+    /// Loads the ranks for the work tree containing `path`, if any.
+    pub fn load(path: &Path) -> Option<Self> {
+        let root = workspace_root(path)?;
+        let mut ranks = HashMap::new();
+        for (path, status) in status_map(&root) {
+            let rank = status_rank(Some(&status));
+            for path in Path::new(&path).ancestors() {
+                if path.as_os_str().is_empty() {
+                    break;
+                }
+                let current = ranks.entry(path.to_path_buf()).or_insert(rank);
+                *current = (*current).min(rank);
+            }
+        }
+        let current_dir = std::env::current_dir().ok()?;
+        Some(Self {
+            root,
+            current_dir,
+            ranks,
+        })
+    }
+
+    /// Ranks `path` itself rather than what it links to, so a symlink takes
+    /// its own status. Only a path reached through a symlinked directory
+    /// outside the work tree has its parent resolved.
+    pub fn rank(&self, path: &Path) -> u8 {
+        let path = self.absolute(path);
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) => Some(relative.to_path_buf()),
+            Err(_) => path
+                .parent()
+                .zip(path.file_name())
+                .and_then(|(parent, name)| {
+                    let parent = parent.canonicalize().ok()?;
+                    Some(parent.strip_prefix(&self.root).ok()?.join(name))
+                }),
+        };
+        relative
+            .and_then(|relative| self.ranks.get(&relative).copied())
+            .unwrap_or_else(|| status_rank(None))
+    }
+
+    /// Joins a relative `path` onto the current directory and drops `.` and
+    /// `..` components, without resolving symlinks.
+    fn absolute(&self, path: &Path) -> PathBuf {
+        let mut absolute = if path.is_absolute() {
+            PathBuf::new()
+        } else {
+            self.current_dir.clone()
+        };
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    absolute.pop();
+                }
+                component => absolute.push(component),
+            }
+        }
+        absolute
+    }
+}
+
+/// A heading for each `status_rank`.
+pub fn rank_title(rank: u8) -> &'static str {
+    match rank {
+        0 => "Conflicted",
+        1 => "Staged",
+        2 => "Modified",
+        3 => "Untracked",
+        4 => "Unchanged",
+        5 => "Ignored",
+        _ => "Other",
+    }
+}
+
+/// The time of the last commit touching each path in a work tree, where each
+/// directory takes the latest time found beneath it.
+pub struct CommitTimes {
+    repo: Arc<Repository>,
+    history: Arc<History>,
+}
+
+impl CommitTimes {
+    /// Loads the commit times of `paths`, which share a work tree, if any.
+    pub fn load(paths: &[PathBuf]) -> Option<Self> {
+        let repo = Repository::discover(paths.first()?)?;
+        let relative: Vec<String> = paths
+            .iter()
+            .filter_map(|path| repo.relative_path(path))
+            .collect();
+        let history = repo.history_of(relative.iter().map(String::as_str));
+        Some(Self { repo, history })
+    }
+
+    /// The last commit time of `path`, or `None` when it was never committed.
+    pub fn time(&self, path: &Path) -> Option<u64> {
+        let index = self
+            .history
+            .last_commit_index(&self.repo.relative_path(path)?)?;
+        self.repo
+            .commits()
+            .get(index)
+            .map(|commit| commit.commit_time.max(0) as u64)
+    }
+}
@@ -0,0 +1,37 @@
+use crate::utils::size::file_size;
+use lla_plugin_interface::proto::{self, EntryMetadata};
+use lla_plugin_interface::FileType;
+use std::os::unix::fs::MetadataExt;
+use std::time::UNIX_EPOCH;
+
+pub fn convert_metadata(metadata: &std::fs::Metadata) -> EntryMetadata {
+    EntryMetadata {
+        size: file_size(metadata),
+        modified: metadata
+            .modified()
+            .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())
+            .unwrap_or(0),
+        accessed: metadata
+            .accessed()
+            .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())
+            .unwrap_or(0),
+        created: metadata
+            .created()
+            .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())
+            .unwrap_or(0),
+        is_dir: metadata.is_dir(),
+        is_file: metadata.is_file(),
+        is_symlink: metadata.is_symlink(),
+        permissions: metadata.mode(),
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        inode: metadata.ino(),
+        nlink: metadata.nlink(),
+        dev: metadata.dev(),
+        rdev: metadata.rdev(),
+        blocks: metadata.blocks(),
+        blksize: metadata.blksize(),
+        ctime: metadata.ctime().max(0) as u64,
+        file_type: proto::FileType::from(FileType::from_mode(metadata.mode())) as i32,
+    }
+}
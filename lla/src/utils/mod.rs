@@ -1,2 +1,8 @@
 pub mod color;
+pub mod git;
 pub mod icons;
+pub mod metadata;
+pub mod quoting;
+pub mod size;
+pub mod symlink;
+pub mod time;
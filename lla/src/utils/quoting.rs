@@ -0,0 +1,169 @@
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::sync::OnceLock;
+
+static QUOTING_STYLE: OnceLock<QuotingStyle> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuotingStyle {
+    #[default]
+    Literal,
+    Shell,
+    Escape,
+    C,
+}
+
+enum Piece {
+    Char(char),
+    Byte(u8),
+}
+
+impl QuotingStyle {
+    pub const VALUES: [&'static str; 4] = ["literal", "shell", "escape", "c"];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "literal" => Some(QuotingStyle::Literal),
+            "shell" => Some(QuotingStyle::Shell),
+            "escape" => Some(QuotingStyle::Escape),
+            "c" => Some(QuotingStyle::C),
+            _ => None,
+        }
+    }
+
+    /// Renders a file name for display. `literal` prints it as-is (invalid
+    /// UTF-8 becomes U+FFFD), `escape` and `c` use backslash escapes, and
+    /// `shell` produces a string that can be pasted back into a POSIX shell.
+    pub fn quote(&self, name: &OsStr) -> String {
+        let bytes = name.as_bytes();
+        match self {
+            QuotingStyle::Literal => String::from_utf8_lossy(bytes).into_owned(),
+            QuotingStyle::Escape => escape(bytes, |c| c == ' '),
+            QuotingStyle::C => format!("\"{}\"", escape(bytes, |c| c == '"')),
+            QuotingStyle::Shell => shell_quote(bytes),
+        }
+    }
+}
+
+pub fn set_quoting_style(style: QuotingStyle) {
+    let _ = QUOTING_STYLE.set(style);
+}
+
+pub fn quoting_style() -> QuotingStyle {
+    *QUOTING_STYLE.get_or_init(QuotingStyle::default)
+}
+
+pub fn quote_name(name: &OsStr) -> String {
+    quoting_style().quote(name)
+}
+
+fn for_each_piece(mut bytes: &[u8], mut f: impl FnMut(Piece)) {
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                valid.chars().for_each(|c| f(Piece::Char(c)));
+                return;
+            }
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                // The prefix was just validated by `from_utf8`.
+                let valid = std::str::from_utf8(valid).unwrap_or_default();
+                valid.chars().for_each(|c| f(Piece::Char(c)));
+
+                let invalid_len = e.error_len().unwrap_or(rest.len());
+                rest[..invalid_len].iter().for_each(|&b| f(Piece::Byte(b)));
+                bytes = &rest[invalid_len..];
+            }
+        }
+    }
+}
+
+fn push_octal(out: &mut String, c: char) {
+    let mut buf = [0; 4];
+    for b in c.encode_utf8(&mut buf).bytes() {
+        out.push_str(&format!("\\{:03o}", b));
+    }
+}
+
+fn escape(bytes: &[u8], needs_backslash: impl Fn(char) -> bool) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for_each_piece(bytes, |piece| match piece {
+        Piece::Char('\\') => out.push_str("\\\\"),
+        Piece::Char('\n') => out.push_str("\\n"),
+        Piece::Char('\t') => out.push_str("\\t"),
+        Piece::Char('\r') => out.push_str("\\r"),
+        Piece::Char(c) if c.is_control() => push_octal(&mut out, c),
+        Piece::Char(c) if needs_backslash(c) => {
+            out.push('\\');
+            out.push(c);
+        }
+        Piece::Char(c) => out.push(c),
+        Piece::Byte(b) => out.push_str(&format!("\\{:03o}", b)),
+    });
+    out
+}
+
+fn shell_quote(bytes: &[u8]) -> String {
+    let is_safe = |b: &u8| b.is_ascii_alphanumeric() || b"._-+/=:@,%^".contains(b) || *b >= 0x80;
+    let printable = match std::str::from_utf8(bytes) {
+        Ok(s) => !s.chars().any(char::is_control),
+        Err(_) => false,
+    };
+
+    if !bytes.is_empty() && printable && bytes.iter().all(is_safe) {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+
+    if printable {
+        let name = String::from_utf8_lossy(bytes);
+        return format!("'{}'", name.replace('\'', "'\\''"));
+    }
+
+    format!("$'{}'", escape(bytes, |c| c == '\''))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(style: QuotingStyle, name: &[u8]) -> String {
+        style.quote(OsStr::from_bytes(name))
+    }
+
+    #[test]
+    fn parses_style_names() {
+        for name in QuotingStyle::VALUES {
+            assert!(QuotingStyle::parse(name).is_some());
+        }
+        assert_eq!(QuotingStyle::parse("c"), Some(QuotingStyle::C));
+        assert_eq!(QuotingStyle::parse("locale"), None);
+    }
+
+    #[test]
+    fn literal_replaces_invalid_utf8() {
+        assert_eq!(quote(QuotingStyle::Literal, b"a b\n"), "a b\n");
+        assert_eq!(quote(QuotingStyle::Literal, b"caf\xe9"), "caf\u{fffd}");
+    }
+
+    #[test]
+    fn escape_and_c_use_backslashes() {
+        assert_eq!(quote(QuotingStyle::Escape, b"a b\tc"), "a\\ b\\tc");
+        assert_eq!(quote(QuotingStyle::Escape, b"caf\xe9\x01"), "caf\\351\\001");
+        assert_eq!(
+            quote(QuotingStyle::C, b"say \"hi\"\n"),
+            "\"say \\\"hi\\\"\\n\""
+        );
+    }
+
+    #[test]
+    fn shell_quotes_only_when_needed() {
+        assert_eq!(
+            quote(QuotingStyle::Shell, b"plain-name.txt"),
+            "plain-name.txt"
+        );
+        assert_eq!(quote(QuotingStyle::Shell, b""), "''");
+        assert_eq!(quote(QuotingStyle::Shell, b"it's here"), "'it'\\''s here'");
+        assert_eq!(quote(QuotingStyle::Shell, b"a\nb"), "$'a\\nb'");
+        assert_eq!(quote(QuotingStyle::Shell, b"caf\xe9"), "$'caf\\351'");
+    }
+}
@@ -0,0 +1,101 @@
+use lla_plugin_interface::proto::DecoratedEntry;
+use lla_plugin_interface::size::SizeStyle;
+use std::fs::Metadata;
+use std::os::unix::fs::MetadataExt;
+use std::sync::OnceLock;
+
+static SIZE_STYLE: OnceLock<SizeStyle> = OnceLock::new();
+static DISK_USAGE: OnceLock<bool> = OnceLock::new();
+
+pub fn set_size_options(style: SizeStyle, disk_usage: bool) {
+    let _ = SIZE_STYLE.set(style);
+    let _ = DISK_USAGE.set(disk_usage);
+}
+
+pub fn size_style() -> SizeStyle {
+    *SIZE_STYLE.get_or_init(SizeStyle::default)
+}
+
+pub fn disk_usage() -> bool {
+    *DISK_USAGE.get_or_init(|| false)
+}
+
+pub fn format_size(size: u64) -> String {
+    size_style().format(size)
+}
+
+/// Size of a single file as reported to every view: the apparent length, or
+/// the space allocated on disk (`st_blocks` 512-byte units) with `--disk-usage`.
+pub fn file_size(metadata: &Metadata) -> u64 {
+    if disk_usage() {
+        metadata.blocks() * 512
+    } else {
+        metadata.len()
+    }
+}
+
+pub fn entry_size(entry: &DecoratedEntry) -> u64 {
+    entry.metadata.as_ref().map_or(0, |m| m.size)
+}
+
+/// Parses a size such as `512`, `10K` or `1.5G` into bytes. A bare unit or an
+/// IEC one (`KiB`, `MiB`, ...) is a power of 1024, while `KB`, `MB`, ... are
+/// powers of 1000, matching `--size-style iec` and `si`.
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let end = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let number = value[..end]
+        .parse::<f64>()
+        .map_err(|_| format!("expected a number in '{}'", value))?;
+    let multiplier: u64 = match value[end..].trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        unit => {
+            return Err(format!(
+                "unknown size unit '{}' (use B, K, M, G, T, KiB, ... or KB, ...)",
+                unit
+            ))
+        }
+    };
+    let bytes = number * multiplier as f64;
+    // `u64::MAX as f64` rounds up to 2^64, which no size may reach.
+    if bytes >= u64::MAX as f64 {
+        return Err(format!("size '{}' is too large", value));
+    }
+    Ok(bytes as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_binary_and_decimal_units() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("512b"), Ok(512));
+        assert_eq!(parse_size("10K"), Ok(10 * 1024));
+        assert_eq!(parse_size("1.5G"), Ok(3 << 29));
+        assert_eq!(parse_size("2 MiB"), Ok(2 << 20));
+        assert_eq!(parse_size("1MB"), Ok(1_000_000));
+        assert_eq!(parse_size("1.5kb"), Ok(1_500));
+        assert_eq!(parse_size("3TB"), Ok(3_000_000_000_000));
+    }
+
+    #[test]
+    fn rejects_bad_sizes() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("M").is_err());
+        assert!(parse_size("10X")
+            .unwrap_err()
+            .contains("unknown size unit 'x'"));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::theme::{color_value_to_color, is_no_color};
+use crate::utils::color::get_theme;
+use crate::utils::quoting::quote_name;
+use colored::*;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const MAX_LINK_HOPS: usize = 40;
+
+static DEREFERENCE: OnceLock<bool> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStatus {
+    Valid,
+    Broken,
+    Cyclic,
+}
+
+pub fn set_dereference(dereference: bool) {
+    let _ = DEREFERENCE.set(dereference);
+}
+
+pub fn dereference() -> bool {
+    *DEREFERENCE.get_or_init(|| false)
+}
+
+/// A link is broken when its target is missing or a component of the target
+/// path is not a directory. Other failures, such as a target in a directory
+/// we may not search, leave the link valid; only too many levels of links
+/// lead to the check for a link that points back to one already seen.
+pub fn link_status(path: &Path) -> LinkStatus {
+    match fs::metadata(path) {
+        Ok(_) => return LinkStatus::Valid,
+        Err(e)
+            if e.kind() == io::ErrorKind::NotFound || e.raw_os_error() == Some(libc::ENOTDIR) =>
+        {
+            return LinkStatus::Broken
+        }
+        Err(e) if e.raw_os_error() != Some(libc::ELOOP) => return LinkStatus::Valid,
+        Err(_) => {}
+    }
+
+    let mut seen = HashSet::new();
+    let mut current = path.to_path_buf();
+    for _ in 0..MAX_LINK_HOPS {
+        if !seen.insert(current.clone()) {
+            return LinkStatus::Cyclic;
+        }
+        match fs::read_link(&current) {
+            Ok(target) => current = resolve_target(&current, &target),
+            Err(_) => return LinkStatus::Valid,
+        }
+    }
+    LinkStatus::Cyclic
+}
+
+fn resolve_target(link: &Path, target: &Path) -> PathBuf {
+    link.parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(target)
+        .components()
+        .collect()
+}
+
+/// Renders ` -> target` for symlinks, or an empty string for anything else and
+/// when links are being dereferenced.
+pub fn symlink_suffix(path: &Path) -> String {
+    if dereference() || !path.is_symlink() {
+        return String::new();
+    }
+    let target = match fs::read_link(path) {
+        Ok(target) => target,
+        Err(_) => return String::new(),
+    };
+
+    let target = quote_name(target.as_os_str());
+    let (text, color) = match link_status(path) {
+        LinkStatus::Valid => {
+            let theme = get_theme();
+            let color = if path.is_dir() {
+                &theme.colors.directory
+            } else {
+                &theme.colors.file
+            };
+            (target, color)
+        }
+        LinkStatus::Broken => (
+            format!("{} [broken]", target),
+            &get_theme().colors.symlink_broken,
+        ),
+        LinkStatus::Cyclic => (
+            format!("{} [cycle]", target),
+            &get_theme().colors.symlink_broken,
+        ),
+    };
+
+    if is_no_color() {
+        format!(" -> {}", text)
+    } else {
+        format!(
+            " {} {}",
+            "->".bright_black(),
+            text.color(color_value_to_color(color))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn reports_valid_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("file"), "").unwrap();
+        symlink("file", root.join("to_file")).unwrap();
+        symlink("sub", root.join("to_dir")).unwrap();
+        symlink("../file", root.join("sub/up")).unwrap();
+        symlink("to_file", root.join("chain")).unwrap();
+
+        for name in ["to_file", "to_dir", "sub/up", "chain"] {
+            assert_eq!(link_status(&root.join(name)), LinkStatus::Valid, "{}", name);
+        }
+    }
+
+    #[test]
+    fn reports_broken_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("file"), "").unwrap();
+        symlink("missing", root.join("missing_target")).unwrap();
+        symlink("file/inside", root.join("through_file")).unwrap();
+        symlink("missing_target", root.join("to_broken")).unwrap();
+
+        for name in ["missing_target", "through_file", "to_broken"] {
+            assert_eq!(
+                link_status(&root.join(name)),
+                LinkStatus::Broken,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn reports_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        symlink("self", root.join("self")).unwrap();
+        symlink("b", root.join("a")).unwrap();
+        symlink("a", root.join("b")).unwrap();
+        symlink("a", root.join("into_cycle")).unwrap();
+
+        for name in ["self", "a", "b", "into_cycle"] {
+            assert_eq!(
+                link_status(&root.join(name)),
+                LinkStatus::Cyclic,
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use lla_plugin_interface::proto::DecoratedEntry;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static TIME_FIELD: OnceLock<TimeField> = OnceLock::new();
+static TIME_STYLE: OnceLock<Option<TimeStyle>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeField {
+    #[default]
+    Modified,
+    Accessed,
+    Created,
+    Changed,
+}
+
+impl TimeField {
+    pub const VALUES: [&'static str; 4] = ["modified", "accessed", "created", "changed"];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "modified" | "mtime" => Some(TimeField::Modified),
+            "accessed" | "atime" => Some(TimeField::Accessed),
+            "created" | "btime" => Some(TimeField::Created),
+            "changed" | "ctime" => Some(TimeField::Changed),
+            _ => None,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            TimeField::Modified => "Modified",
+            TimeField::Accessed => "Accessed",
+            TimeField::Created => "Created",
+            TimeField::Changed => "Changed",
+        }
+    }
+
+    pub fn timestamp(&self, entry: &DecoratedEntry) -> u64 {
+        let metadata = entry.metadata.as_ref();
+        match self {
+            TimeField::Modified => metadata.map_or(0, |m| m.modified),
+            TimeField::Accessed => metadata.map_or(0, |m| m.accessed),
+            TimeField::Created => metadata.map_or(0, |m| m.created),
+            TimeField::Changed => metadata.map_or(0, |m| m.ctime),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeStyle {
+    Default,
+    Relative,
+    Iso,
+    LongIso,
+    Full,
+    Custom(String),
+}
+
+impl TimeStyle {
+    pub const VALUES: [&'static str; 5] = ["default", "relative", "iso", "long-iso", "full"];
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "default" => Ok(TimeStyle::Default),
+            "relative" => Ok(TimeStyle::Relative),
+            "iso" => Ok(TimeStyle::Iso),
+            "long-iso" => Ok(TimeStyle::LongIso),
+            "full" | "full-iso" => Ok(TimeStyle::Full),
+            custom => match custom.strip_prefix('+').filter(|format| !format.is_empty()) {
+                Some(format) => {
+                    check_strftime(format)?;
+                    Ok(TimeStyle::Custom(format.to_string()))
+                }
+                None => Err(format!(
+                    "must be one of: {}, or +FORMAT",
+                    TimeStyle::VALUES.join(", ")
+                )),
+            },
+        }
+    }
+
+    pub fn format(&self, time: &SystemTime) -> String {
+        let datetime: DateTime<Local> = (*time).into();
+        match self {
+            TimeStyle::Default => datetime.format("%b %d %H:%M").to_string(),
+            TimeStyle::Relative => format_relative_time(datetime),
+            TimeStyle::Iso => datetime.format("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+            TimeStyle::LongIso => datetime.format("%Y-%m-%d %H:%M").to_string(),
+            TimeStyle::Full => datetime.format("%Y-%m-%d %H:%M:%S %z").to_string(),
+            TimeStyle::Custom(format) => datetime.format(format).to_string(),
+        }
+    }
+}
+
+/// Checks a strftime format up front, since chrono only reports unknown
+/// specifiers by failing while the date is being written.
+pub fn check_strftime(format: &str) -> Result<(), String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        Err(format!("invalid date format '{}'", format))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn set_time_options(field: TimeField, style: Option<TimeStyle>) {
+    let _ = TIME_FIELD.set(field);
+    let _ = TIME_STYLE.set(style);
+}
+
+pub fn time_field() -> TimeField {
+    *TIME_FIELD.get_or_init(TimeField::default)
+}
+
+pub fn time_style() -> Option<&'static TimeStyle> {
+    TIME_STYLE.get_or_init(|| None).as_ref()
+}
+
+pub fn to_system_time(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+pub fn format_relative_time(dt: DateTime<Local>) -> String {
+    let now = Local::now();
+    let duration = now.signed_duration_since(dt);
+
+    if duration.num_seconds() < 60 {
+        "just now".to_string()
+    } else if duration.num_minutes() < 60 {
+        format!("{} mins ago", duration.num_minutes())
+    } else if duration.num_hours() < 24 {
+        format!("{} hours ago", duration.num_hours())
+    } else if duration.num_days() < 7 {
+        format!("{} days ago", duration.num_days())
+    } else if duration.num_days() < 30 {
+        format!("{} weeks ago", duration.num_weeks())
+    } else if duration.num_days() < 365 {
+        dt.format("%b %d").to_string()
+    } else {
+        dt.format("%b %d, %Y").to_string()
+    }
+}
+
+const DURATION_UNITS: [(&str, u64); 6] = [
+    ("y", 365 * 86_400),
+    ("w", 7 * 86_400),
+    ("d", 86_400),
+    ("h", 3_600),
+    ("m", 60),
+    ("s", 1),
+];
+
+/// Parses a duration such as `90s`, `15m`, `6h`, `2d`, `1w` or `1y` into
+/// seconds. A bare number is taken as seconds.
+pub fn parse_duration(value: &str) -> std::result::Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", value))?;
+    let multiplier = match unit.trim() {
+        "" => 1,
+        unit => DURATION_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, secs)| *secs)
+            .ok_or_else(|| format!("invalid duration unit '{}' (use s, m, h, d, w or y)", unit))?,
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("duration '{}' is too large", value))
+}
+
+/// Formats seconds in the largest unit that divides them evenly, so that
+/// `parse_duration` round-trips: 3600 becomes `1h`, 5400 becomes `90m`.
+pub fn format_duration(secs: u64) -> String {
+    DURATION_UNITS
+        .iter()
+        .find(|(_, unit)| secs > 0 && secs % unit == 0)
+        .map_or_else(
+            || format!("{}s", secs),
+            |(name, unit)| format!("{}{}", secs / unit, name),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_named_styles() {
+        assert_eq!(TimeStyle::parse("default"), Ok(TimeStyle::Default));
+        assert_eq!(TimeStyle::parse("relative"), Ok(TimeStyle::Relative));
+        assert_eq!(TimeStyle::parse("iso"), Ok(TimeStyle::Iso));
+        assert_eq!(TimeStyle::parse("long-iso"), Ok(TimeStyle::LongIso));
+        assert_eq!(TimeStyle::parse("full"), Ok(TimeStyle::Full));
+        assert_eq!(TimeStyle::parse("full-iso"), Ok(TimeStyle::Full));
+    }
+
+    #[test]
+    fn parses_custom_formats() {
+        assert_eq!(
+            TimeStyle::parse("+%Y-%m-%d %H:%M"),
+            Ok(TimeStyle::Custom("%Y-%m-%d %H:%M".to_string()))
+        );
+        assert!(TimeStyle::parse("+%Q")
+            .unwrap_err()
+            .contains("invalid date format '%Q'"));
+    }
+
+    #[test]
+    fn rejects_unknown_styles() {
+        assert!(TimeStyle::parse("+").is_err());
+        assert!(TimeStyle::parse("").is_err());
+        assert!(TimeStyle::parse("ISO")
+            .unwrap_err()
+            .contains("must be one of"));
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90"), Ok(90));
+        assert_eq!(parse_duration("15m"), Ok(15 * 60));
+        assert_eq!(parse_duration(" 2d "), Ok(2 * 86400));
+        assert_eq!(parse_duration("1w"), Ok(7 * 86400));
+        assert!(parse_duration("1x").unwrap_err().contains("unit 'x'"));
+        assert!(parse_duration("h")
+            .unwrap_err()
+            .contains("invalid duration"));
+        assert!(parse_duration("-1h").is_err());
+    }
+
+    #[test]
+    fn rejects_durations_that_overflow() {
+        let max_years = u64::MAX / (365 * 86400);
+        assert!(parse_duration(&format!("{}y", max_years)).is_ok());
+        assert!(parse_duration(&format!("{}y", max_years + 1))
+            .unwrap_err()
+            .contains("too large"));
+        assert!(parse_duration("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn formats_durations_in_the_largest_even_unit() {
+        for secs in [1, 59, 60, 90 * 60, 3600, 86400, 7 * 86400, 365 * 86400] {
+            assert_eq!(parse_duration(&format_duration(secs)), Ok(secs));
+        }
+        assert_eq!(format_duration(5400), "90m");
+        assert_eq!(format_duration(0), "0s");
+    }
+}
@@ -22,6 +22,22 @@ pub struct EntryMetadata {
     pub uid: u32,
     #[prost(uint32, tag = "10")]
     pub gid: u32,
+    #[prost(uint64, tag = "11")]
+    pub inode: u64,
+    #[prost(uint64, tag = "12")]
+    pub nlink: u64,
+    #[prost(uint64, tag = "13")]
+    pub dev: u64,
+    #[prost(uint64, tag = "14")]
+    pub rdev: u64,
+    #[prost(uint64, tag = "15")]
+    pub blocks: u64,
+    #[prost(uint64, tag = "16")]
+    pub blksize: u64,
+    #[prost(uint64, tag = "17")]
+    pub ctime: u64,
+    #[prost(enumeration = "FileType", tag = "18")]
+    pub file_type: i32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -33,6 +49,8 @@ pub struct DecoratedEntry {
     #[prost(map = "string, string", tag = "3")]
     pub custom_fields:
         ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    #[prost(bytes = "vec", optional, tag = "4")]
+    pub raw_path: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -116,3 +134,47 @@ pub struct ActionResponse {
     #[prost(string, optional, tag = "2")]
     pub error: ::core::option::Option<::prost::alloc::string::String>,
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum FileType {
+    Unknown = 0,
+    Regular = 1,
+    Directory = 2,
+    Symlink = 3,
+    Fifo = 4,
+    Socket = 5,
+    BlockDevice = 6,
+    CharDevice = 7,
+}
+impl FileType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            FileType::Unknown => "FILE_TYPE_UNKNOWN",
+            FileType::Regular => "FILE_TYPE_REGULAR",
+            FileType::Directory => "FILE_TYPE_DIRECTORY",
+            FileType::Symlink => "FILE_TYPE_SYMLINK",
+            FileType::Fifo => "FILE_TYPE_FIFO",
+            FileType::Socket => "FILE_TYPE_SOCKET",
+            FileType::BlockDevice => "FILE_TYPE_BLOCK_DEVICE",
+            FileType::CharDevice => "FILE_TYPE_CHAR_DEVICE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "FILE_TYPE_UNKNOWN" => Some(Self::Unknown),
+            "FILE_TYPE_REGULAR" => Some(Self::Regular),
+            "FILE_TYPE_DIRECTORY" => Some(Self::Directory),
+            "FILE_TYPE_SYMLINK" => Some(Self::Symlink),
+            "FILE_TYPE_FIFO" => Some(Self::Fifo),
+            "FILE_TYPE_SOCKET" => Some(Self::Socket),
+            "FILE_TYPE_BLOCK_DEVICE" => Some(Self::BlockDevice),
+            "FILE_TYPE_CHAR_DEVICE" => Some(Self::CharDevice),
+            _ => None,
+        }
+    }
+}
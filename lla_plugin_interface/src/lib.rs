@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+pub mod size;
 
 pub mod proto {
     #[cfg(not(feature = "regenerate-protobuf"))]
@@ -33,6 +35,111 @@ pub struct EntryMetadata {
     pub permissions: u32,
     pub uid: u32,
     pub gid: u32,
+    #[serde(default)]
+    pub inode: u64,
+    #[serde(default)]
+    pub nlink: u64,
+    #[serde(default)]
+    pub dev: u64,
+    #[serde(default)]
+    pub rdev: u64,
+    #[serde(default)]
+    pub blocks: u64,
+    #[serde(default)]
+    pub blksize: u64,
+    #[serde(default)]
+    pub ctime: u64,
+    #[serde(default)]
+    pub file_type: FileType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileType {
+    #[default]
+    Unknown,
+    #[serde(rename = "file")]
+    Regular,
+    Directory,
+    Symlink,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+}
+
+impl FileType {
+    pub fn from_mode(mode: u32) -> Self {
+        match mode & 0o170000 {
+            0o100000 => FileType::Regular,
+            0o040000 => FileType::Directory,
+            0o120000 => FileType::Symlink,
+            0o010000 => FileType::Fifo,
+            0o140000 => FileType::Socket,
+            0o060000 => FileType::BlockDevice,
+            0o020000 => FileType::CharDevice,
+            _ => FileType::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileType::Unknown => "unknown",
+            FileType::Regular => "file",
+            FileType::Directory => "directory",
+            FileType::Symlink => "symlink",
+            FileType::Fifo => "fifo",
+            FileType::Socket => "socket",
+            FileType::BlockDevice => "block_device",
+            FileType::CharDevice => "char_device",
+        }
+    }
+}
+
+impl From<proto::FileType> for FileType {
+    fn from(file_type: proto::FileType) -> Self {
+        match file_type {
+            proto::FileType::Unknown => FileType::Unknown,
+            proto::FileType::Regular => FileType::Regular,
+            proto::FileType::Directory => FileType::Directory,
+            proto::FileType::Symlink => FileType::Symlink,
+            proto::FileType::Fifo => FileType::Fifo,
+            proto::FileType::Socket => FileType::Socket,
+            proto::FileType::BlockDevice => FileType::BlockDevice,
+            proto::FileType::CharDevice => FileType::CharDevice,
+        }
+    }
+}
+
+impl From<FileType> for proto::FileType {
+    fn from(file_type: FileType) -> Self {
+        match file_type {
+            FileType::Unknown => proto::FileType::Unknown,
+            FileType::Regular => proto::FileType::Regular,
+            FileType::Directory => proto::FileType::Directory,
+            FileType::Symlink => proto::FileType::Symlink,
+            FileType::Fifo => proto::FileType::Fifo,
+            FileType::Socket => proto::FileType::Socket,
+            FileType::BlockDevice => proto::FileType::BlockDevice,
+            FileType::CharDevice => proto::FileType::CharDevice,
+        }
+    }
+}
+
+impl proto::EntryMetadata {
+    /// The entry's file type. Metadata from older hosts leaves `file_type`
+    /// unset, so it is recovered from the mode bits or the boolean flags.
+    pub fn kind(&self) -> FileType {
+        match FileType::from(self.file_type()) {
+            FileType::Unknown => match FileType::from_mode(self.permissions) {
+                FileType::Unknown if self.is_symlink => FileType::Symlink,
+                FileType::Unknown if self.is_dir => FileType::Directory,
+                FileType::Unknown if self.is_file => FileType::Regular,
+                file_type => file_type,
+            },
+            file_type => file_type,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -71,12 +178,21 @@ impl From<EntryMetadata> for proto::EntryMetadata {
             permissions: meta.permissions,
             uid: meta.uid,
             gid: meta.gid,
+            inode: meta.inode,
+            nlink: meta.nlink,
+            dev: meta.dev,
+            rdev: meta.rdev,
+            blocks: meta.blocks,
+            blksize: meta.blksize,
+            ctime: meta.ctime,
+            file_type: proto::FileType::from(meta.file_type) as i32,
         }
     }
 }
 
 impl From<proto::EntryMetadata> for EntryMetadata {
     fn from(meta: proto::EntryMetadata) -> Self {
+        let file_type = meta.kind();
         EntryMetadata {
             size: meta.size,
             modified: meta.modified,
@@ -88,18 +204,75 @@ impl From<proto::EntryMetadata> for EntryMetadata {
             permissions: meta.permissions,
             uid: meta.uid,
             gid: meta.gid,
+            inode: meta.inode,
+            nlink: meta.nlink,
+            dev: meta.dev,
+            rdev: meta.rdev,
+            blocks: meta.blocks,
+            blksize: meta.blksize,
+            ctime: meta.ctime,
+            file_type,
         }
     }
 }
 
-impl From<DecoratedEntry> for proto::DecoratedEntry {
-    fn from(entry: DecoratedEntry) -> Self {
+impl proto::DecoratedEntry {
+    pub fn new(
+        path: &Path,
+        metadata: Option<proto::EntryMetadata>,
+        custom_fields: HashMap<String, String>,
+    ) -> Self {
         proto::DecoratedEntry {
-            path: entry.path.to_string_lossy().to_string(),
-            metadata: Some(entry.metadata.into()),
-            custom_fields: entry.custom_fields,
+            path: path.to_string_lossy().into_owned(),
+            metadata,
+            custom_fields,
+            raw_path: raw_path_bytes(path),
         }
     }
+
+    /// The entry's path exactly as it exists on disk. `path` is a lossy UTF-8
+    /// rendering; names that are not valid UTF-8 also carry their raw bytes.
+    pub fn fs_path(&self) -> PathBuf {
+        match &self.raw_path {
+            Some(bytes) => path_from_bytes(bytes),
+            None => PathBuf::from(&self.path),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn raw_path_bytes(path: &Path) -> Option<Vec<u8>> {
+    use std::os::unix::ffi::OsStrExt;
+    match path.to_str() {
+        Some(_) => None,
+        None => Some(path.as_os_str().as_bytes().to_vec()),
+    }
+}
+
+#[cfg(not(unix))]
+fn raw_path_bytes(_path: &Path) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+impl From<DecoratedEntry> for proto::DecoratedEntry {
+    fn from(entry: DecoratedEntry) -> Self {
+        proto::DecoratedEntry::new(
+            &entry.path,
+            Some(entry.metadata.into()),
+            entry.custom_fields,
+        )
+    }
 }
 
 impl TryFrom<proto::DecoratedEntry> for DecoratedEntry {
@@ -107,7 +280,7 @@ impl TryFrom<proto::DecoratedEntry> for DecoratedEntry {
 
     fn try_from(entry: proto::DecoratedEntry) -> Result<Self, Self::Error> {
         Ok(DecoratedEntry {
-            path: PathBuf::from(entry.path),
+            path: entry.fs_path(),
             metadata: entry.metadata.unwrap_or_default().into(),
             custom_fields: entry.custom_fields,
         })
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+const BLOCK_SIZE: u64 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeStyle {
+    #[default]
+    Iec,
+    Si,
+    Bytes,
+    Blocks,
+}
+
+impl SizeStyle {
+    pub const VALUES: [&'static str; 4] = ["iec", "si", "bytes", "blocks"];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "iec" | "human" => Some(SizeStyle::Iec),
+            "si" => Some(SizeStyle::Si),
+            "bytes" | "exact" => Some(SizeStyle::Bytes),
+            "blocks" => Some(SizeStyle::Blocks),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SizeStyle::Iec => "iec",
+            SizeStyle::Si => "si",
+            SizeStyle::Bytes => "bytes",
+            SizeStyle::Blocks => "blocks",
+        }
+    }
+
+    /// Renders a byte count: `iec` uses powers of 1024 (`4.0K`), `si` powers
+    /// of 1000 (`4.1k`), `bytes` the exact count and `blocks` the number of
+    /// 1K blocks rounded up, matching `du -k`.
+    pub fn format(&self, size: u64) -> String {
+        match self {
+            SizeStyle::Iec => scaled(size, 1024.0, &["K", "M", "G", "T", "P", "E"]),
+            SizeStyle::Si => scaled(size, 1000.0, &["k", "M", "G", "T", "P", "E"]),
+            SizeStyle::Bytes => size.to_string(),
+            SizeStyle::Blocks => {
+                (size / BLOCK_SIZE + u64::from(size % BLOCK_SIZE != 0)).to_string()
+            }
+        }
+    }
+}
+
+fn scaled(size: u64, base: f64, units: &[&str]) -> String {
+    if (size as f64) < base {
+        return format!("{}B", size);
+    }
+
+    let mut value = size as f64 / base;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, units[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_each_style() {
+        assert_eq!(SizeStyle::Iec.format(1023), "1023B");
+        assert_eq!(SizeStyle::Iec.format(4096), "4.0K");
+        assert_eq!(SizeStyle::Iec.format(3 << 29), "1.5G");
+        assert_eq!(SizeStyle::Si.format(999), "999B");
+        assert_eq!(SizeStyle::Si.format(4096), "4.1k");
+        assert_eq!(SizeStyle::Si.format(1_000_000), "1.0M");
+        assert_eq!(SizeStyle::Bytes.format(123_456), "123456");
+        assert_eq!(SizeStyle::Blocks.format(0), "0");
+        assert_eq!(SizeStyle::Blocks.format(1), "1");
+        assert_eq!(SizeStyle::Blocks.format(2048), "2");
+        assert_eq!(SizeStyle::Blocks.format(2049), "3");
+    }
+
+    #[test]
+    fn largest_unit_absorbs_bigger_sizes() {
+        assert_eq!(SizeStyle::Iec.format(u64::MAX), "16.0E");
+        assert_eq!(SizeStyle::Si.format(u64::MAX), "18.4E");
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+/// Past this many edits the shortest edit script is not worth searching for
+/// and lines are matched by content alone, ignoring their order.
+const MAX_EDITS: usize = 1000;
+
+/// Counts the lines added and removed going from `old` to `new`, like
+/// `git diff --numstat`. Binary contents, holding a NUL byte, have no lines
+/// to count.
+pub fn line_changes(old: &[u8], new: &[u8]) -> Option<(usize, usize)> {
+    if old.contains(&0) || new.contains(&0) {
+        return None;
+    }
+    let old: Vec<&[u8]> = lines(old);
+    let new: Vec<&[u8]> = lines(new);
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+
+    let edits = edit_distance(old, new).unwrap_or_else(|| unmatched(old, new));
+    // Every edit adds or removes one line, and the difference between the
+    // two is the change in length.
+    let added = (edits + new.len() - old.len()) / 2;
+    Some((added, edits - added))
+}
+
+fn lines(data: &[u8]) -> Vec<&[u8]> {
+    data.split_inclusive(|&byte| byte == b'\n').collect()
+}
+
+/// The length of the shortest edit script, found with Myers' O(ND) greedy
+/// search, or `None` when it is longer than `MAX_EDITS`.
+fn edit_distance(old: &[&[u8]], new: &[&[u8]]) -> Option<usize> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let limit = (old.len() + new.len()).min(MAX_EDITS) as isize;
+    let offset = limit + 1;
+    let mut furthest = vec![0isize; 2 * offset as usize + 1];
+
+    for edits in 0..=limit {
+        for diagonal in (-edits..=edits).step_by(2) {
+            let at = (diagonal + offset) as usize;
+            let mut x = if diagonal == -edits
+                || (diagonal != edits && furthest[at - 1] < furthest[at + 1])
+            {
+                furthest[at + 1]
+            } else {
+                furthest[at - 1] + 1
+            };
+            let mut y = x - diagonal;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[at] = x;
+            if x >= n && y >= m {
+                return Some(edits as usize);
+            }
+        }
+    }
+    None
+}
+
+/// Edits needed when lines are matched by content regardless of order: a
+/// lower bound on the edit script, used for heavily rewritten files.
+fn unmatched(old: &[&[u8]], new: &[&[u8]]) -> usize {
+    let mut counts: HashMap<&[u8], isize> = HashMap::new();
+    for line in old {
+        *counts.entry(line).or_insert(0) += 1;
+    }
+    for line in new {
+        *counts.entry(line).or_insert(0) -= 1;
+    }
+    counts.values().map(|count| count.unsigned_abs()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_added_and_removed_lines() {
+        assert_eq!(line_changes(b"a\nb\nc\n", b"a\nb\nc\n"), Some((0, 0)));
+        assert_eq!(line_changes(b"a\nb\nc\n", b"a\nB\nc\nd\n"), Some((2, 1)));
+        assert_eq!(line_changes(b"a\nb\n", b""), Some((0, 2)));
+        assert_eq!(line_changes(b"a\nb", b"a\nb\n"), Some((1, 1)));
+    }
+
+    #[test]
+    fn binary_contents_have_no_lines() {
+        assert_eq!(line_changes(b"a\n", b"a\0b\n"), None);
+    }
+}
@@ -0,0 +1,165 @@
+use super::object::{Commit, ObjectDb, Oid};
+use std::collections::{HashMap, HashSet};
+
+const TREE: u32 = 0o40000;
+
+/// For each path, the last commit that changed it and how many commits did,
+/// following `git log -- <path>`: merges count only for changes that none of
+/// their parents had. Directories count every commit changing something
+/// beneath them.
+pub struct History {
+    files: HashMap<String, (usize, usize)>,
+}
+
+impl History {
+    /// The index into `commits` of the last commit changing `path`.
+    pub fn last_commit_index(&self, path: &str) -> Option<usize> {
+        self.files.get(path).map(|&(last, _)| last)
+    }
+
+    /// How many commits changed `path`.
+    pub fn churn(&self, path: &str) -> usize {
+        self.files.get(path).map_or(0, |&(_, count)| count)
+    }
+
+    /// Walks `commits`, newest first, looking only at changes within the
+    /// `scope` directory so that listing a corner of a large repository
+    /// does not diff the whole tree of every commit.
+    pub(super) fn load(odb: &ObjectDb, commits: &[Commit], scope: &str) -> Self {
+        let positions: HashMap<Oid, usize> = commits
+            .iter()
+            .enumerate()
+            .map(|(i, commit)| (commit.oid, i))
+            .collect();
+        let mut scoped: HashMap<usize, Option<(u32, Oid)>> = HashMap::new();
+        let mut scoped_entry = |i: usize| {
+            *scoped
+                .entry(i)
+                .or_insert_with(|| subtree(odb, &commits[i].tree, scope))
+        };
+
+        let mut files: HashMap<String, (usize, usize)> = HashMap::new();
+        for (i, commit) in commits.iter().enumerate() {
+            let own = scoped_entry(i);
+            let parents: Vec<_> = commit
+                .parents
+                .iter()
+                .map(|parent| match positions.get(parent) {
+                    Some(&j) => scoped_entry(j),
+                    None => subtree(odb, &odb.commit(parent).map_or(*parent, |c| c.tree), scope),
+                })
+                .collect();
+            if parents.iter().any(|parent| *parent == own) {
+                continue;
+            }
+
+            let mut changed = match parents.split_first() {
+                None => diff(odb, None, own, scope),
+                Some((first, rest)) => {
+                    let mut changed = diff(odb, *first, own, scope);
+                    for parent in rest {
+                        let other = diff(odb, *parent, own, scope);
+                        changed.retain(|path| other.contains(path));
+                    }
+                    changed
+                }
+            };
+
+            let ancestors: Vec<String> = changed
+                .iter()
+                .flat_map(|path| {
+                    path.match_indices('/')
+                        .map(|(at, _)| path[..at].to_string())
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            changed.extend(ancestors);
+            for path in changed {
+                let file = files.entry(path).or_insert((i, 0));
+                file.1 += 1;
+            }
+        }
+
+        Self { files }
+    }
+}
+
+/// Finds the entry at `path` in a tree; an empty path is the tree itself.
+pub(super) fn subtree(odb: &ObjectDb, tree: &Oid, path: &str) -> Option<(u32, Oid)> {
+    let mut current = (TREE, *tree);
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+        if current.0 != TREE {
+            return None;
+        }
+        let entry = odb
+            .tree(&current.1)?
+            .into_iter()
+            .find(|entry| entry.name == name)?;
+        current = (entry.mode, entry.oid);
+    }
+    Some(current)
+}
+
+/// The files that differ between two versions of the entry at `path`.
+fn diff(
+    odb: &ObjectDb,
+    old: Option<(u32, Oid)>,
+    new: Option<(u32, Oid)>,
+    path: &str,
+) -> HashSet<String> {
+    let mut changed = HashSet::new();
+    diff_into(odb, old, new, path, &mut changed);
+    changed
+}
+
+fn diff_into(
+    odb: &ObjectDb,
+    old: Option<(u32, Oid)>,
+    new: Option<(u32, Oid)>,
+    path: &str,
+    changed: &mut HashSet<String>,
+) {
+    if old == new {
+        return;
+    }
+
+    let children = |entry: Option<(u32, Oid)>| -> HashMap<String, (u32, Oid)> {
+        match entry {
+            Some((TREE, oid)) => odb
+                .tree(&oid)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|entry| (entry.name, (entry.mode, entry.oid)))
+                .collect(),
+            _ => HashMap::new(),
+        }
+    };
+    let is_tree = |entry: Option<(u32, Oid)>| matches!(entry, Some((TREE, _)));
+
+    if !is_tree(old) || !is_tree(new) {
+        if old.is_some() && !is_tree(old) || new.is_some() && !is_tree(new) {
+            changed.insert(path.to_string());
+        }
+        if !is_tree(old) && !is_tree(new) {
+            return;
+        }
+    }
+
+    let old_children = children(old);
+    let new_children = children(new);
+    let names: HashSet<&String> = old_children.keys().chain(new_children.keys()).collect();
+    for name in names {
+        let child = if path.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", path, name)
+        };
+        diff_into(
+            odb,
+            old_children.get(name).copied(),
+            new_children.get(name).copied(),
+            &child,
+            changed,
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use super::object::{read_u32, Oid};
+use std::fs;
+use std::path::Path;
+
+const EXTENDED: u16 = 0x4000;
+const SKIP_WORKTREE: u16 = 0x4000;
+
+/// One path in the index, with the stat data git uses to tell whether the
+/// file in the work tree may have changed.
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    pub path: String,
+    pub oid: Oid,
+    pub mode: u32,
+    pub size: u32,
+    pub mtime: (u32, u32),
+    /// Non-zero for the sides of a merge conflict.
+    pub stage: u8,
+    /// Set for paths outside a sparse checkout, which have no file to check.
+    pub skip_worktree: bool,
+}
+
+/// Reads an index file in format version 2, 3 or 4.
+pub fn read_index(path: &Path) -> Option<Vec<IndexEntry>> {
+    let data = fs::read(path).ok()?;
+    if data.get(..4)? != b"DIRC" {
+        return None;
+    }
+    let version = read_u32(&data, 4)?;
+    if !(2..=4).contains(&version) {
+        return None;
+    }
+    let count = read_u32(&data, 8)? as usize;
+
+    // Every entry takes at least 62 bytes, which bounds a corrupt count.
+    let mut entries = Vec::with_capacity(count.min(data.len() / 62));
+    let mut pos = 12;
+    let mut previous = Vec::new();
+
+    for _ in 0..count {
+        let start = pos;
+        let mtime = (read_u32(&data, pos + 8)?, read_u32(&data, pos + 12)?);
+        let mode = read_u32(&data, pos + 24)?;
+        let size = read_u32(&data, pos + 36)?;
+        let oid = Oid::from_bytes(data.get(pos + 40..pos + 60)?)?;
+        let flags = u16::from_be_bytes(data.get(pos + 60..pos + 62)?.try_into().ok()?);
+        pos += 62;
+
+        let mut extended = 0;
+        if flags & EXTENDED != 0 && version >= 3 {
+            extended = u16::from_be_bytes(data.get(pos..pos + 2)?.try_into().ok()?);
+            pos += 2;
+        }
+
+        let path = if version == 4 {
+            // Paths drop a number of bytes from the end of the previous path
+            // and append a new suffix.
+            let mut byte = *data.get(pos)?;
+            pos += 1;
+            let mut strip = (byte & 0x7f) as usize;
+            while byte & 0x80 != 0 {
+                byte = *data.get(pos)?;
+                pos += 1;
+                strip = strip.checked_add(1)?.checked_mul(1 << 7)? | (byte & 0x7f) as usize;
+            }
+            let end = pos + data.get(pos..)?.iter().position(|&b| b == 0)?;
+            previous.truncate(previous.len().checked_sub(strip)?);
+            previous.extend_from_slice(&data[pos..end]);
+            pos = end + 1;
+            previous.clone()
+        } else {
+            let end = pos + data.get(pos..)?.iter().position(|&b| b == 0)?;
+            let path = data[pos..end].to_vec();
+            // Entries are padded with NULs to a multiple of eight bytes.
+            pos = start + ((end - start) / 8 + 1) * 8;
+            path
+        };
+
+        entries.push(IndexEntry {
+            path: String::from_utf8_lossy(&path).into_owned(),
+            oid,
+            mode,
+            size,
+            mtime,
+            stage: ((flags >> 12) & 3) as u8,
+            skip_worktree: extended & SKIP_WORKTREE != 0,
+        });
+    }
+
+    Some(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn entry(data: &mut Vec<u8>, mode: u32, oid: u8) {
+        let mut stat = [0u8; 62];
+        stat[24..28].copy_from_slice(&mode.to_be_bytes());
+        stat[40..60].copy_from_slice(&[oid; 20]);
+        data.extend_from_slice(&stat);
+    }
+
+    #[test]
+    fn reads_prefix_compressed_paths() {
+        let long = format!("dir/{}", "a".repeat(200));
+        let mut data = b"DIRC".to_vec();
+        data.extend_from_slice(&4u32.to_be_bytes());
+        data.extend_from_slice(&4u32.to_be_bytes());
+
+        entry(&mut data, 0o100644, 1);
+        data.push(0);
+        data.extend_from_slice(b"dir/a.txt\0");
+
+        // Strip "a.txt" from the previous path.
+        entry(&mut data, 0o100755, 2);
+        data.push(5);
+        data.extend_from_slice(b"b.txt\0");
+
+        entry(&mut data, 0o100644, 3);
+        data.push(5);
+        data.extend_from_slice(&long.as_bytes()[4..]);
+        data.push(0);
+
+        // A strip of 200 takes two bytes: ((0 + 1) << 7) | 72.
+        entry(&mut data, 0o100644, 4);
+        data.extend_from_slice(&[0x80, 72]);
+        data.extend_from_slice(b"c\0");
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&data).unwrap();
+        let entries = read_index(file.path()).unwrap();
+
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["dir/a.txt", "dir/b.txt", long.as_str(), "dir/c"]);
+        assert_eq!(entries[1].mode, 0o100755);
+        assert_eq!(entries[3].oid, Oid::from_bytes(&[4; 20]).unwrap());
+    }
+
+    #[test]
+    fn rejects_a_strip_longer_than_the_previous_path() {
+        let mut data = b"DIRC".to_vec();
+        data.extend_from_slice(&4u32.to_be_bytes());
+        data.extend_from_slice(&1u32.to_be_bytes());
+        entry(&mut data, 0o100644, 1);
+        data.push(1);
+        data.extend_from_slice(b"a\0");
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&data).unwrap();
+        assert!(read_index(file.path()).is_none());
+    }
+}
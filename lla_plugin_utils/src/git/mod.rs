@@ -0,0 +1,497 @@
+//! Reads git repositories in-process: HEAD, refs, the index, objects from
+//! loose files and packs, work tree status and per-file history. Nothing
+//! here needs the git binary.
+//!
+//! Repositories are opened once per work tree and shared, and each one
+//! computes its status, commit list and histories at most once.
+
+mod diff;
+mod history;
+mod index;
+mod object;
+mod status;
+
+pub use diff::line_changes;
+pub use history::History;
+pub use index::IndexEntry;
+pub use object::{Commit, ObjectKind, Oid, TreeEntry};
+pub use status::Status;
+
+use history::subtree;
+use object::ObjectDb;
+use parking_lot::Mutex;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+static REPOSITORIES: OnceLock<Mutex<HashMap<PathBuf, Arc<Repository>>>> = OnceLock::new();
+
+pub struct Repository {
+    work_dir: PathBuf,
+    /// Holds HEAD and the index, which are per work tree.
+    git_dir: PathBuf,
+    /// Holds objects, refs and config, which linked work trees share.
+    common_dir: PathBuf,
+    odb: ObjectDb,
+    status: OnceLock<Status>,
+    commits: OnceLock<Vec<Commit>>,
+    histories: Mutex<HashMap<String, Arc<History>>>,
+}
+
+impl Repository {
+    /// Finds the repository whose work tree contains `path`, reusing the one
+    /// already opened for that work tree.
+    pub fn discover(path: &Path) -> Option<Arc<Repository>> {
+        let path = path.canonicalize().ok()?;
+        let work_dir = path.ancestors().find(|dir| dir.join(".git").exists())?;
+
+        let mut repositories = REPOSITORIES.get_or_init(Default::default).lock();
+        if let Some(repository) = repositories.get(work_dir) {
+            return Some(repository.clone());
+        }
+        let repository = Arc::new(Self::open(work_dir)?);
+        repositories.insert(work_dir.to_path_buf(), repository.clone());
+        Some(repository)
+    }
+
+    fn open(work_dir: &Path) -> Option<Self> {
+        let dot_git = work_dir.join(".git");
+        let git_dir = if dot_git.is_file() {
+            // Linked work trees and submodules point at their git directory.
+            let link = fs::read_to_string(&dot_git).ok()?;
+            work_dir.join(link.strip_prefix("gitdir:")?.trim())
+        } else {
+            dot_git
+        };
+        if !git_dir.join("HEAD").is_file() {
+            return None;
+        }
+        let common_dir = fs::read_to_string(git_dir.join("commondir"))
+            .map(|dir| git_dir.join(dir.trim()))
+            .unwrap_or_else(|_| git_dir.clone());
+
+        let repository = Self {
+            work_dir: work_dir.to_path_buf(),
+            odb: ObjectDb::open(&common_dir.join("objects")),
+            git_dir,
+            common_dir,
+            status: OnceLock::new(),
+            commits: OnceLock::new(),
+            histories: Mutex::new(HashMap::new()),
+        };
+        // Only SHA-1 object ids are understood.
+        match repository.config_value("extensions", "objectformat") {
+            Some(format) if format != "sha1" => None,
+            _ => Some(repository),
+        }
+    }
+
+    pub fn work_dir(&self) -> &Path {
+        &self.work_dir
+    }
+
+    /// `path` relative to the work tree, with `/` separators as git writes
+    /// them. The work tree root itself is the empty string. Only the parent
+    /// directory is resolved, so a symlink stands for itself.
+    pub fn relative_path(&self, path: &Path) -> Option<String> {
+        let path = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+                parent.canonicalize().ok()?.join(name)
+            }
+            (Some(_), Some(name)) => std::env::current_dir().ok()?.join(name),
+            _ => path.canonicalize().ok()?,
+        };
+        path.strip_prefix(&self.work_dir).ok().map(git_path)
+    }
+
+    /// The checked out branch, or `None` when HEAD is detached.
+    pub fn branch(&self) -> Option<String> {
+        let head = fs::read_to_string(self.git_dir.join("HEAD")).ok()?;
+        head.trim()
+            .strip_prefix("ref: refs/heads/")
+            .map(str::to_string)
+    }
+
+    /// The commit HEAD points at, or `None` on a branch without commits.
+    pub fn head(&self) -> Option<Oid> {
+        self.resolve("HEAD")
+    }
+
+    pub fn head_commit(&self) -> Option<Commit> {
+        self.odb.commit(&self.head()?)
+    }
+
+    /// The commit the current branch tracks, from `branch.<name>.remote` and
+    /// `branch.<name>.merge`.
+    pub fn upstream(&self) -> Option<Oid> {
+        let section = format!("branch \"{}\"", self.branch()?);
+        let remote = self.config_value(&section, "remote")?;
+        let merge = self.config_value(&section, "merge")?;
+        if remote == "." {
+            return self.resolve(&merge);
+        }
+        let branch = merge.strip_prefix("refs/heads/").unwrap_or(&merge);
+        self.resolve(&format!("refs/remotes/{}/{}", remote, branch))
+    }
+
+    /// How many commits HEAD has that its upstream lacks, and the reverse.
+    pub fn ahead_behind(&self) -> Option<(usize, usize)> {
+        const OURS: u8 = 1;
+        const THEIRS: u8 = 2;
+
+        let (head, upstream) = (self.head()?, self.upstream()?);
+        let mut flags: HashMap<Oid, u8> = HashMap::new();
+        for (oid, flag) in [(head, OURS), (upstream, THEIRS)] {
+            *flags.entry(oid).or_insert(0) |= flag;
+        }
+        // Queued commits carry whether they were only reachable from one
+        // side when pushed; `pending` counts those still in the queue.
+        let mut queue = BinaryHeap::new();
+        let mut pending = 0;
+        for oid in [head, upstream] {
+            let one_sided = flags[&oid] != OURS | THEIRS;
+            pending += one_sided as usize;
+            queue.push((self.odb.commit(&oid)?.commit_time, oid, one_sided));
+        }
+
+        // Walk both histories newest first, spreading each side's flag to
+        // parents, until every commit left to visit is reachable from both.
+        while pending > 0 {
+            let (_, oid, one_sided) = match queue.pop() {
+                Some(next) => next,
+                None => break,
+            };
+            pending -= one_sided as usize;
+            let flag = flags[&oid];
+            let commit = match self.odb.commit(&oid) {
+                Some(commit) => commit,
+                None => continue,
+            };
+            for parent in commit.parents {
+                let parent_flags = flags.entry(parent).or_insert(0);
+                if *parent_flags | flag != *parent_flags {
+                    *parent_flags |= flag;
+                    if let Some(commit) = self.odb.commit(&parent) {
+                        let one_sided = *parent_flags != OURS | THEIRS;
+                        pending += one_sided as usize;
+                        queue.push((commit.commit_time, parent, one_sided));
+                    }
+                }
+            }
+        }
+
+        let ahead = flags.values().filter(|&&flag| flag == OURS).count();
+        let behind = flags.values().filter(|&&flag| flag == THEIRS).count();
+        Some((ahead, behind))
+    }
+
+    /// How many entries the stash holds, read from the reflog of
+    /// `refs/stash`.
+    pub fn stash_count(&self) -> usize {
+        fs::read_to_string(self.common_dir.join("logs/refs/stash"))
+            .map_or(0, |log| log.lines().filter(|line| !line.is_empty()).count())
+    }
+
+    /// The status of the work tree, computed on first use.
+    pub fn status(&self) -> &Status {
+        self.status.get_or_init(|| Status::load(self))
+    }
+
+    /// Every commit reachable from HEAD, newest first by commit time.
+    pub fn commits(&self) -> &[Commit] {
+        self.commits.get_or_init(|| self.walk().collect())
+    }
+
+    /// Walks the commits reachable from HEAD, newest first by commit time,
+    /// reading each one only when it is reached.
+    fn walk(&self) -> impl Iterator<Item = Commit> + '_ {
+        let mut seen = HashSet::new();
+        let mut queue = BinaryHeap::new();
+        if let Some(commit) = self.head_commit() {
+            seen.insert(commit.oid);
+            queue.push((commit.commit_time, commit.oid));
+        }
+        std::iter::from_fn(move || loop {
+            let (_, oid) = queue.pop()?;
+            let commit = match self.odb.commit(&oid) {
+                Some(commit) => commit,
+                None => continue,
+            };
+            for parent in &commit.parents {
+                if seen.insert(*parent) {
+                    if let Some(parent_commit) = self.odb.commit(parent) {
+                        queue.push((parent_commit.commit_time, *parent));
+                    }
+                }
+            }
+            return Some(commit);
+        })
+    }
+
+    /// The history of every path under `scope`, a directory relative to the
+    /// work tree. An empty scope covers the whole repository.
+    pub fn history(&self, scope: &str) -> Arc<History> {
+        if let Some(history) = self.histories.lock().get(scope) {
+            return history.clone();
+        }
+        let history = Arc::new(History::load(&self.odb, self.commits(), scope));
+        self.histories
+            .lock()
+            .insert(scope.to_string(), history.clone());
+        history
+    }
+
+    /// The history of the deepest directory holding all of `paths`, which
+    /// are relative to the work tree.
+    pub fn history_of<'a>(&self, paths: impl IntoIterator<Item = &'a str>) -> Arc<History> {
+        let mut scope: Option<&str> = None;
+        for path in paths {
+            let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+            scope = Some(match scope {
+                None => parent,
+                Some(scope) => common_dir(scope, parent),
+            });
+        }
+        self.history(scope.unwrap_or(""))
+    }
+
+    /// The last commit that changed `path`, relative to the work tree, by
+    /// the same rules as `History`. Like `git log -1 -- <path>`, the walk
+    /// stops at the first such commit rather than loading all of history.
+    pub fn last_commit(&self, path: &str) -> Option<Commit> {
+        self.walk().find(|commit| {
+            let own = subtree(&self.odb, &commit.tree, path);
+            if commit.parents.is_empty() {
+                return own.is_some();
+            }
+            commit.parents.iter().all(|parent| {
+                let entry = self
+                    .odb
+                    .commit(parent)
+                    .and_then(|parent| subtree(&self.odb, &parent.tree, path));
+                entry != own
+            })
+        })
+    }
+
+    /// Lines added and removed in the work tree since HEAD, like
+    /// `git diff --numstat HEAD`, summed over every changed file at or under
+    /// `path`. Untracked, ignored and binary files are not counted.
+    pub fn line_changes(&self, path: &str) -> (usize, usize) {
+        let head = self.head_commit().map(|commit| commit.tree);
+        self.status()
+            .under(path)
+            .filter(|(_, status)| {
+                status
+                    .chars()
+                    .any(|code| matches!(code, 'A' | 'M' | 'D' | 'U'))
+            })
+            .filter_map(|(file, _)| {
+                let old = head
+                    .and_then(|tree| subtree(&self.odb, &tree, file))
+                    .and_then(|(_, oid)| self.odb.read(&oid))
+                    .filter(|object| object.kind == ObjectKind::Blob)
+                    .map(|object| object.data.clone())
+                    .unwrap_or_default();
+                let new = self.work_tree_contents(file).unwrap_or_default();
+                line_changes(&old, &new)
+            })
+            .fold((0, 0), |(added, removed), (a, r)| (added + a, removed + r))
+    }
+
+    /// A file's contents as git would store them: the target of a symlink,
+    /// or the bytes of a regular file.
+    fn work_tree_contents(&self, path: &str) -> Option<Vec<u8>> {
+        let path = self.work_dir.join(path);
+        let metadata = fs::symlink_metadata(&path).ok()?;
+        if metadata.file_type().is_symlink() {
+            fs::read_link(&path)
+                .ok()
+                .map(|target| git_path(&target).into_bytes())
+        } else if metadata.is_file() {
+            fs::read(&path).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Reads a ref such as `HEAD` or `refs/heads/main`, following symbolic
+    /// refs, from loose ref files first and then from `packed-refs`.
+    fn resolve(&self, name: &str) -> Option<Oid> {
+        let mut name = name.to_string();
+        for _ in 0..10 {
+            let loose = fs::read_to_string(self.git_dir.join(&name))
+                .or_else(|_| fs::read_to_string(self.common_dir.join(&name)));
+            match loose {
+                Ok(content) => match content.trim().strip_prefix("ref:") {
+                    Some(target) => name = target.trim().to_string(),
+                    None => return Oid::from_hex(&content),
+                },
+                Err(_) => {
+                    let packed = fs::read_to_string(self.common_dir.join("packed-refs")).ok()?;
+                    return packed.lines().find_map(|line| {
+                        let (oid, reference) = line.split_once(' ')?;
+                        (reference.trim() == name)
+                            .then(|| Oid::from_hex(oid))
+                            .flatten()
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Reads a value from the repository's config file, where `section` is
+    /// written as in the file, such as `branch "main"`. Section names are
+    /// case-insensitive and subsection names are not, as in git. Includes and
+    /// the global config are not read.
+    fn config_value(&self, section: &str, key: &str) -> Option<String> {
+        let config = fs::read_to_string(self.common_dir.join("config")).ok()?;
+        let section = config_section(section);
+        let mut current = String::new();
+        let mut value = None;
+        for line in config.lines().map(str::trim) {
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = config_section(header);
+            } else if current == section {
+                if let Some((name, found)) = line.split_once('=') {
+                    if name.trim().eq_ignore_ascii_case(key) {
+                        value = Some(config_text(found).trim().trim_matches('"').to_string());
+                    }
+                }
+            }
+        }
+        value
+    }
+}
+
+/// A config section header with the section name lower-cased and the
+/// subsection, if any, left as written.
+fn config_section(header: &str) -> String {
+    let header = header.trim();
+    match header.split_once(char::is_whitespace) {
+        Some((name, subsection)) => format!("{} {}", name.to_lowercase(), subsection.trim()),
+        None => header.to_lowercase(),
+    }
+}
+
+/// A config value up to a `#` or `;` comment outside double quotes.
+fn config_text(value: &str) -> &str {
+    let mut quoted = false;
+    for (at, c) in value.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' | ';' if !quoted => return &value[..at],
+            _ => {}
+        }
+    }
+    value
+}
+
+/// The deepest directory containing both `/`-separated paths.
+fn common_dir<'a>(a: &'a str, b: &str) -> &'a str {
+    let mut len = 0;
+    for (x, y) in a.split('/').zip(b.split('/')) {
+        if x != y {
+            break;
+        }
+        len += x.len() + 1;
+    }
+    &a[..len.saturating_sub(1).min(a.len())]
+}
+
+/// Joins the components of a relative path with `/`.
+pub(crate) fn git_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Describes how long ago a commit was made the way `git log` does, such as
+/// `3 hours ago` or `2 weeks ago`.
+pub fn relative_time(time: i64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as i64);
+    let seconds = now - time;
+    let ago = |count: i64, unit: &str| {
+        format!(
+            "{} {}{} ago",
+            count,
+            unit,
+            if count == 1 { "" } else { "s" }
+        )
+    };
+
+    if seconds < 0 {
+        "in the future".to_string()
+    } else if seconds < 90 {
+        ago(seconds, "second")
+    } else if seconds < 90 * 60 {
+        ago((seconds + 30) / 60, "minute")
+    } else if seconds < 36 * 3600 {
+        ago((seconds + 1800) / 3600, "hour")
+    } else if seconds < 14 * 86400 {
+        ago((seconds + 43200) / 86400, "day")
+    } else if seconds < 70 * 86400 {
+        ago((seconds + 302400) / 604800, "week")
+    } else if seconds < 365 * 86400 {
+        ago((seconds + 1296000) / 2592000, "month")
+    } else {
+        ago((seconds + 15768000) / 31536000, "year")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignored_files_have_no_line_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        fs::write(dir.path().join("debug.log"), "a\nb\nc\n").unwrap();
+
+        let repo = Repository::discover(dir.path()).unwrap();
+        assert_eq!(repo.status().get("debug.log"), Some("!!"));
+        assert_eq!(repo.line_changes("debug.log"), (0, 0));
+        assert_eq!(repo.line_changes(""), (0, 0));
+    }
+
+    #[test]
+    fn reads_config_values() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".git/HEAD"), "ref: refs/heads/Main\n").unwrap();
+        fs::write(
+            dir.path().join(".git/config"),
+            concat!(
+                "[Branch \"Main\"]\n",
+                "\tremote = origin ; tracked remote\n",
+                "\tmerge = \"refs/heads/Main\" # upstream\n",
+                "[branch \"main\"]\n",
+                "\tremote = other\n",
+                "[core]\n",
+                "\tcomment = \"a;b#c\"\n",
+            ),
+        )
+        .unwrap();
+
+        let repo = Repository::discover(dir.path()).unwrap();
+        let remote = repo.config_value("branch \"Main\"", "remote");
+        assert_eq!(remote.as_deref(), Some("origin"));
+        let merge = repo.config_value("branch \"Main\"", "MERGE");
+        assert_eq!(merge.as_deref(), Some("refs/heads/Main"));
+        let remote = repo.config_value("branch \"main\"", "remote");
+        assert_eq!(remote.as_deref(), Some("other"));
+        let comment = repo.config_value("CORE", "comment");
+        assert_eq!(comment.as_deref(), Some("a;b#c"));
+    }
+}
@@ -0,0 +1,628 @@
+use flate2::read::ZlibDecoder;
+use parking_lot::Mutex;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Objects kept in memory at once; the cache is dropped when it fills up.
+const CACHE_LIMIT: usize = 4096;
+
+/// The longest delta chain followed, which is also the most `git repack
+/// --depth` allows. Bounds chains that a corrupt pack makes circular.
+const MAX_DELTA_CHAIN: usize = 4095;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Oid([u8; 20]);
+
+impl Oid {
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(Oid)
+    }
+
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim().as_bytes();
+        if hex.len() != 40 {
+            return None;
+        }
+        let mut bytes = [0; 20];
+        for (byte, pair) in bytes.iter_mut().zip(hex.chunks(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+        }
+        Some(Oid(bytes))
+    }
+
+    /// The id git gives a file with these contents.
+    pub fn hash_blob(data: &[u8]) -> Self {
+        let mut hasher = Sha1::new();
+        hasher.update(format!("blob {}\0", data.len()));
+        hasher.update(data);
+        Oid(hasher.finalize().into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+
+    /// The abbreviated form git shows by default.
+    pub fn short(&self) -> String {
+        self.to_string()[..7].to_string()
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Oid({})", self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectKind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+impl ObjectKind {
+    fn from_pack_type(kind: u8) -> Option<Self> {
+        match kind {
+            1 => Some(ObjectKind::Commit),
+            2 => Some(ObjectKind::Tree),
+            3 => Some(ObjectKind::Blob),
+            4 => Some(ObjectKind::Tag),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"commit" => Some(ObjectKind::Commit),
+            b"tree" => Some(ObjectKind::Tree),
+            b"blob" => Some(ObjectKind::Blob),
+            b"tag" => Some(ObjectKind::Tag),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Object {
+    pub kind: ObjectKind,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TreeEntry {
+    pub mode: u32,
+    pub name: String,
+    pub oid: Oid,
+}
+
+impl TreeEntry {
+    pub fn is_tree(&self) -> bool {
+        self.mode == 0o40000
+    }
+}
+
+pub fn parse_tree(data: &[u8]) -> Vec<TreeEntry> {
+    let mut entries = Vec::new();
+    let mut rest = data;
+    while let Some(space) = rest.iter().position(|&b| b == b' ') {
+        let nul = match rest[space..].iter().position(|&b| b == 0) {
+            Some(nul) => space + nul,
+            None => break,
+        };
+        let oid = match rest.get(nul + 1..nul + 21).and_then(Oid::from_bytes) {
+            Some(oid) => oid,
+            None => break,
+        };
+        entries.push(TreeEntry {
+            mode: std::str::from_utf8(&rest[..space])
+                .ok()
+                .and_then(|mode| u32::from_str_radix(mode, 8).ok())
+                .unwrap_or(0),
+            name: String::from_utf8_lossy(&rest[space + 1..nul]).into_owned(),
+            oid,
+        });
+        rest = &rest[nul + 21..];
+    }
+    entries
+}
+
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub oid: Oid,
+    pub tree: Oid,
+    pub parents: Vec<Oid>,
+    pub author: String,
+    /// Seconds since the epoch.
+    pub author_time: i64,
+    pub commit_time: i64,
+    pub summary: String,
+}
+
+impl Commit {
+    pub fn parse(oid: Oid, data: &[u8]) -> Option<Self> {
+        let text = String::from_utf8_lossy(data);
+        let (headers, message) = text.split_once("\n\n").unwrap_or((&text, ""));
+        let mut commit = Commit {
+            oid,
+            tree: oid,
+            parents: Vec::new(),
+            author: String::new(),
+            author_time: 0,
+            commit_time: 0,
+            summary: message.lines().next().unwrap_or_default().to_string(),
+        };
+        let mut tree = None;
+
+        for line in headers.lines() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "tree" => tree = Oid::from_hex(value),
+                "parent" => commit.parents.extend(Oid::from_hex(value)),
+                "author" => {
+                    let (name, time) = parse_signature(value);
+                    commit.author = name;
+                    commit.author_time = time;
+                }
+                "committer" => commit.commit_time = parse_signature(value).1,
+                _ => {}
+            }
+        }
+
+        commit.tree = tree?;
+        Some(commit)
+    }
+}
+
+/// Splits `Name <email> 1700000000 +0100` into the name and the time.
+fn parse_signature(value: &str) -> (String, i64) {
+    let name = value.split(" <").next().unwrap_or(value).trim().to_string();
+    let time = value
+        .rsplit(' ')
+        .nth(1)
+        .and_then(|time| time.parse().ok())
+        .unwrap_or(0);
+    (name, time)
+}
+
+/// A version 2 pack index: the sorted object ids of a pack and where each
+/// object starts in it.
+struct PackIndex {
+    data: Vec<u8>,
+    count: usize,
+}
+
+impl PackIndex {
+    fn load(path: &Path) -> Option<Self> {
+        let data = fs::read(path).ok()?;
+        if data.len() < 8 + 1024
+            || data[..4] != [0xff, b't', b'O', b'c']
+            || read_u32(&data, 4)? != 2
+        {
+            return None;
+        }
+        // The fanout counts objects by first byte, so it never decreases and
+        // ends at the number of objects; the binary search relies on both.
+        let mut previous = 0;
+        for byte in 0..256 {
+            let fanout = read_u32(&data, 8 + byte * 4)?;
+            if fanout < previous {
+                return None;
+            }
+            previous = fanout;
+        }
+        let count = previous as usize;
+        if data.len() < 8 + 1024 + count.checked_mul(28)? {
+            return None;
+        }
+        Some(Self { data, count })
+    }
+
+    fn fanout(&self, byte: usize) -> usize {
+        read_u32(&self.data, 8 + byte * 4).unwrap_or(0) as usize
+    }
+
+    fn offset(&self, oid: &Oid) -> Option<u64> {
+        let first = oid.0[0] as usize;
+        let mut lo = if first == 0 {
+            0
+        } else {
+            self.fanout(first - 1)
+        };
+        let mut hi = self.fanout(first);
+        let ids = 8 + 1024;
+
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let id = self.data.get(ids + mid * 20..ids + mid * 20 + 20)?;
+            match id.cmp(&oid.0[..]) {
+                std::cmp::Ordering::Equal => {
+                    let offsets = ids + self.count * 24;
+                    let offset = read_u32(&self.data, offsets + mid * 4)?;
+                    if offset & 0x8000_0000 == 0 {
+                        return Some(offset as u64);
+                    }
+                    let large = offsets + self.count * 4 + (offset & 0x7fff_ffff) as usize * 8;
+                    let bytes = self.data.get(large..large + 8)?;
+                    return Some(u64::from_be_bytes(bytes.try_into().ok()?));
+                }
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+            }
+        }
+        None
+    }
+}
+
+pub(super) fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+struct Pack {
+    index: PackIndex,
+    file: Mutex<File>,
+}
+
+/// Where a packed object's contents come from.
+enum PackEntry {
+    Whole(ObjectKind, Vec<u8>),
+    /// A delta against the object at this offset in the same pack.
+    OffsetDelta(u64, Vec<u8>),
+    /// A delta against the object with this id.
+    RefDelta(Oid, Vec<u8>),
+}
+
+impl Pack {
+    fn entry(&self, offset: u64) -> io::Result<PackEntry> {
+        let mut file = self.file.lock();
+        file.seek(SeekFrom::Start(offset))?;
+        let mut reader = BufReader::new(&mut *file);
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid pack entry");
+        let mut byte = read_byte(&mut reader)?;
+        let kind = (byte >> 4) & 7;
+        let mut size = (byte & 15) as usize;
+        let mut shift = 4;
+        while byte & 0x80 != 0 {
+            byte = read_byte(&mut reader)?;
+            if shift >= usize::BITS {
+                return Err(invalid());
+            }
+            size |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+        }
+
+        let entry = match kind {
+            6 => {
+                let mut byte = read_byte(&mut reader)?;
+                let mut distance = (byte & 0x7f) as u64;
+                while byte & 0x80 != 0 {
+                    byte = read_byte(&mut reader)?;
+                    distance = distance
+                        .checked_add(1)
+                        .filter(|&distance| distance < 1 << 57)
+                        .ok_or_else(invalid)?
+                        << 7
+                        | (byte & 0x7f) as u64;
+                }
+                // A base must come earlier in the pack, or the chain never ends.
+                let base = offset
+                    .checked_sub(distance)
+                    .filter(|&base| base < offset)
+                    .ok_or_else(invalid)?;
+                PackEntry::OffsetDelta(base, inflate(&mut reader, size)?)
+            }
+            7 => {
+                let mut base = [0; 20];
+                reader.read_exact(&mut base)?;
+                PackEntry::RefDelta(Oid(base), inflate(&mut reader, size)?)
+            }
+            kind => {
+                let kind = ObjectKind::from_pack_type(kind).ok_or_else(invalid)?;
+                PackEntry::Whole(kind, inflate(&mut reader, size)?)
+            }
+        };
+        Ok(entry)
+    }
+}
+
+fn read_byte(reader: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn inflate(reader: &mut impl Read, size: usize) -> io::Result<Vec<u8>> {
+    // The size comes from the pack, so it only sizes the buffer up to a limit.
+    let mut data = Vec::with_capacity(size.min(1 << 20));
+    ZlibDecoder::new(reader)
+        .take(size as u64)
+        .read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Rebuilds an object from its base and a git delta.
+fn apply_delta(base: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+    fn size(delta: &[u8], pos: &mut usize) -> Option<usize> {
+        let mut size = 0;
+        let mut shift = 0;
+        loop {
+            let byte = *delta.get(*pos)?;
+            *pos += 1;
+            if shift >= usize::BITS {
+                return None;
+            }
+            size |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Some(size);
+            }
+        }
+    }
+
+    let mut pos = 0;
+    if size(delta, &mut pos)? != base.len() {
+        return None;
+    }
+    let mut result = Vec::with_capacity(size(delta, &mut pos)?.min(1 << 20));
+
+    while pos < delta.len() {
+        let op = delta[pos];
+        pos += 1;
+        if op & 0x80 != 0 {
+            let mut offset = 0;
+            let mut length = 0;
+            for i in 0..4 {
+                if op & (1 << i) != 0 {
+                    offset |= (*delta.get(pos)? as usize) << (i * 8);
+                    pos += 1;
+                }
+            }
+            for i in 0..3 {
+                if op & (0x10 << i) != 0 {
+                    length |= (*delta.get(pos)? as usize) << (i * 8);
+                    pos += 1;
+                }
+            }
+            if length == 0 {
+                length = 0x10000;
+            }
+            result.extend_from_slice(base.get(offset..offset.checked_add(length)?)?);
+        } else if op > 0 {
+            result.extend_from_slice(delta.get(pos..pos + op as usize)?);
+            pos += op as usize;
+        } else {
+            return None;
+        }
+    }
+    Some(result)
+}
+
+/// Reads objects from the loose object directories and packs of a
+/// repository, including those of its alternates.
+pub struct ObjectDb {
+    dirs: Vec<PathBuf>,
+    packs: Vec<Pack>,
+    cache: Mutex<HashMap<Oid, Arc<Object>>>,
+    packed_cache: Mutex<HashMap<(usize, u64), Arc<Object>>>,
+}
+
+impl ObjectDb {
+    pub fn open(objects_dir: &Path) -> Self {
+        let mut dirs = vec![objects_dir.to_path_buf()];
+        if let Ok(alternates) = fs::read_to_string(objects_dir.join("info").join("alternates")) {
+            for line in alternates.lines() {
+                let line = line.trim();
+                if !line.is_empty() && !line.starts_with('#') {
+                    dirs.push(objects_dir.join(line));
+                }
+            }
+        }
+
+        let mut packs = Vec::new();
+        for dir in &dirs {
+            let entries = match fs::read_dir(dir.join("pack")) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().map_or(true, |ext| ext != "idx") {
+                    continue;
+                }
+                let pack = (
+                    PackIndex::load(&path),
+                    File::open(path.with_extension("pack")),
+                );
+                if let (Some(index), Ok(file)) = pack {
+                    packs.push(Pack {
+                        index,
+                        file: Mutex::new(file),
+                    });
+                }
+            }
+        }
+
+        Self {
+            dirs,
+            packs,
+            cache: Mutex::new(HashMap::new()),
+            packed_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn read(&self, oid: &Oid) -> Option<Arc<Object>> {
+        self.read_chained(oid, 0)
+    }
+
+    /// Reads an object that is the base of `depth` deltas already being
+    /// followed.
+    fn read_chained(&self, oid: &Oid, depth: usize) -> Option<Arc<Object>> {
+        if let Some(object) = self.cache.lock().get(oid) {
+            return Some(object.clone());
+        }
+
+        let object = self.read_loose(oid).or_else(|| {
+            self.packs.iter().enumerate().find_map(|(pack, entry)| {
+                let offset = entry.index.offset(oid)?;
+                self.read_packed(pack, offset, depth)
+            })
+        })?;
+
+        let mut cache = self.cache.lock();
+        if cache.len() >= CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert(*oid, object.clone());
+        Some(object)
+    }
+
+    pub fn commit(&self, oid: &Oid) -> Option<Commit> {
+        let object = self.read(oid)?;
+        match object.kind {
+            ObjectKind::Commit => Commit::parse(*oid, &object.data),
+            _ => None,
+        }
+    }
+
+    pub fn tree(&self, oid: &Oid) -> Option<Vec<TreeEntry>> {
+        let object = self.read(oid)?;
+        match object.kind {
+            ObjectKind::Tree => Some(parse_tree(&object.data)),
+            _ => None,
+        }
+    }
+
+    fn read_loose(&self, oid: &Oid) -> Option<Arc<Object>> {
+        let hex = oid.to_string();
+        let data = self
+            .dirs
+            .iter()
+            .find_map(|dir| fs::read(dir.join(&hex[..2]).join(&hex[2..])).ok())?;
+        let mut inflated = Vec::new();
+        ZlibDecoder::new(&data[..])
+            .read_to_end(&mut inflated)
+            .ok()?;
+
+        let nul = inflated.iter().position(|&b| b == 0)?;
+        let kind = inflated[..nul].split(|&b| b == b' ').next()?;
+        Some(Arc::new(Object {
+            kind: ObjectKind::from_name(kind)?,
+            data: inflated[nul + 1..].to_vec(),
+        }))
+    }
+
+    /// Follows the delta chain of a packed object down to its base, then
+    /// applies the deltas back up. Intermediate results are cached since
+    /// many objects share a base. Chains longer than `MAX_DELTA_CHAIN`,
+    /// counting the `depth` deltas above this object, are rejected.
+    fn read_packed(&self, pack: usize, offset: u64, depth: usize) -> Option<Arc<Object>> {
+        let mut deltas = Vec::new();
+        let mut at = offset;
+        let mut base = loop {
+            if let Some(object) = self.packed_cache.lock().get(&(pack, at)) {
+                break object.clone();
+            }
+            if depth + deltas.len() > MAX_DELTA_CHAIN {
+                return None;
+            }
+            match self.packs[pack].entry(at).ok()? {
+                PackEntry::Whole(kind, data) => {
+                    let object = Arc::new(Object { kind, data });
+                    if !deltas.is_empty() {
+                        self.cache_packed(pack, at, object.clone());
+                    }
+                    break object;
+                }
+                PackEntry::OffsetDelta(base, delta) => {
+                    deltas.push((at, delta));
+                    at = base;
+                }
+                PackEntry::RefDelta(base, delta) => {
+                    deltas.push((at, delta));
+                    break self.read_chained(&base, depth + deltas.len())?;
+                }
+            }
+        };
+
+        while let Some((at, delta)) = deltas.pop() {
+            base = Arc::new(Object {
+                kind: base.kind,
+                data: apply_delta(&base.data, &delta)?,
+            });
+            self.cache_packed(pack, at, base.clone());
+        }
+        Some(base)
+    }
+
+    fn cache_packed(&self, pack: usize, offset: u64, object: Arc<Object>) {
+        let mut cache = self.packed_cache.lock();
+        if cache.len() >= CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert((pack, offset), object);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_delta_copies_and_inserts() {
+        let base = b"hello world";
+        let delta = [
+            11, // base size
+            12, // result size
+            0x91, 6, 5, // copy "world": offset 6, length 5
+            0x02, b',', b' ', // insert ", "
+            0x90, 5, // copy "hello": offset 0 omitted, length 5
+        ];
+        assert_eq!(apply_delta(base, &delta).unwrap(), b"world, hello");
+    }
+
+    #[test]
+    fn apply_delta_rejects_bad_deltas() {
+        let base = b"hello world";
+        // Base size does not match.
+        assert!(apply_delta(base, &[10, 5, 0x90, 5]).is_none());
+        // Copy past the end of the base.
+        assert!(apply_delta(base, &[11, 5, 0x91, 8, 5]).is_none());
+        // Insert longer than the delta.
+        assert!(apply_delta(base, &[11, 5, 0x05, b'a']).is_none());
+        // Opcode 0 is reserved.
+        assert!(apply_delta(base, &[11, 0, 0x00]).is_none());
+    }
+
+    #[test]
+    fn rejects_an_offset_delta_against_itself() {
+        use std::io::Write;
+
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"PACK\0\0\0\x02\0\0\0\x01").unwrap();
+        // An offset delta of size 5 whose base is 0 bytes back.
+        file.write_all(&[0x65, 0x00]).unwrap();
+        let pack = Pack {
+            index: PackIndex {
+                data: Vec::new(),
+                count: 0,
+            },
+            file: Mutex::new(file),
+        };
+        assert!(pack.entry(12).is_err());
+    }
+}
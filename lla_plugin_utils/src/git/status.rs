@@ -0,0 +1,264 @@
+use super::index::{read_index, IndexEntry};
+use super::object::{ObjectDb, Oid};
+use super::{git_path, Repository};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+const GITLINK: u32 = 0o160000;
+const SYMLINK: u32 = 0o120000;
+
+/// The status of every path git knows about in a work tree, as the two
+/// letter `XY` codes of `git status --porcelain=v2`: `X` compares the index
+/// with HEAD and `Y` the work tree with the index, with `.` for unchanged.
+/// Tracked, unmodified files map to `"."`, untracked files to `"??"` and
+/// ignored ones to `"!!"`.
+#[derive(Debug, Default)]
+pub struct Status {
+    entries: HashMap<String, String>,
+}
+
+impl Status {
+    pub fn entries(&self) -> &HashMap<String, String> {
+        &self.entries
+    }
+
+    /// The status of a path relative to the work tree root. Paths inside an
+    /// ignored directory or a nested repository take its status.
+    pub fn get(&self, path: &str) -> Option<&str> {
+        self.entries
+            .get(path)
+            .map(String::as_str)
+            .or_else(|| self.enclosing(path).map(|(_, status)| status))
+    }
+
+    /// The statuses of `path` and everything beneath it. An empty path
+    /// covers the whole work tree.
+    pub fn under<'a>(&'a self, path: &'a str) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        self.entries
+            .iter()
+            .filter_map(move |(entry, status)| {
+                let inside = path.is_empty()
+                    || entry == path
+                    || (entry.starts_with(path) && entry.as_bytes().get(path.len()) == Some(&b'/'));
+                inside.then_some((entry.as_str(), status.as_str()))
+            })
+            .chain(self.enclosing(path))
+    }
+
+    /// The directory above `path` that is reported as a whole, if any.
+    fn enclosing(&self, path: &str) -> Option<(&str, &str)> {
+        path.match_indices('/').find_map(|(at, _)| {
+            let (dir, status) = self.entries.get_key_value(&path[..at])?;
+            matches!(status.as_str(), "!!" | "??").then_some((dir.as_str(), status.as_str()))
+        })
+    }
+
+    pub(super) fn load(repo: &Repository) -> Self {
+        let index = read_index(&repo.git_dir.join("index")).unwrap_or_default();
+        let mut head = HashMap::new();
+        if let Some(commit) = repo.head().and_then(|oid| repo.odb.commit(&oid)) {
+            flatten_tree(&repo.odb, &commit.tree, "", &mut head);
+        }
+
+        let mut entries = HashMap::with_capacity(index.len());
+        let mut tracked = HashSet::with_capacity(index.len());
+        for entry in &index {
+            tracked.insert(entry.path.as_str());
+            if entry.stage != 0 {
+                entries.insert(entry.path.clone(), "UU".to_string());
+                continue;
+            }
+
+            let staged = match head.get(&entry.path) {
+                None => 'A',
+                Some(&(mode, oid)) if mode != entry.mode || oid != entry.oid => 'M',
+                Some(_) => '.',
+            };
+            let unstaged = worktree_change(&repo.work_dir, entry);
+            let status = if staged == '.' && unstaged == '.' {
+                ".".to_string()
+            } else {
+                format!("{}{}", staged, unstaged)
+            };
+            entries.insert(entry.path.clone(), status);
+        }
+
+        for path in head.keys() {
+            if !tracked.contains(path.as_str()) {
+                entries.insert(path.clone(), "D.".to_string());
+            }
+        }
+
+        for (path, status) in untracked(repo, &tracked) {
+            entries.insert(path, status.to_string());
+        }
+
+        Self { entries }
+    }
+}
+
+/// Maps every file in a tree to its mode and object id.
+pub(super) fn flatten_tree(
+    odb: &ObjectDb,
+    tree: &Oid,
+    prefix: &str,
+    files: &mut HashMap<String, (u32, Oid)>,
+) {
+    for entry in odb.tree(tree).unwrap_or_default() {
+        let path = if prefix.is_empty() {
+            entry.name.clone()
+        } else {
+            format!("{}/{}", prefix, entry.name)
+        };
+        if entry.is_tree() {
+            flatten_tree(odb, &entry.oid, &path, files);
+        } else {
+            files.insert(path, (entry.mode, entry.oid));
+        }
+    }
+}
+
+/// Compares a file with its index entry: `D` when it is gone, `M` when its
+/// contents or executable bit differ, `.` otherwise. The contents are only
+/// hashed when the size or modification time recorded in the index differ.
+fn worktree_change(work_dir: &Path, entry: &IndexEntry) -> char {
+    if entry.skip_worktree || entry.mode == GITLINK {
+        return '.';
+    }
+
+    let path = work_dir.join(&entry.path);
+    let metadata = match fs::symlink_metadata(&path) {
+        Ok(metadata) if !metadata.is_dir() => metadata,
+        _ => return 'D',
+    };
+
+    let is_symlink = metadata.file_type().is_symlink();
+    if is_symlink != (entry.mode == SYMLINK) || executable(&metadata) != (entry.mode & 0o111 != 0) {
+        return 'M';
+    }
+
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|time| (time.as_secs() as u32, time.subsec_nanos()));
+    if metadata.len() as u32 == entry.size && mtime == Some(entry.mtime) {
+        return '.';
+    }
+
+    let contents = if is_symlink {
+        fs::read_link(&path).map(|target| git_path(&target).into_bytes())
+    } else {
+        fs::read(&path)
+    };
+    match contents {
+        Ok(contents) if Oid::hash_blob(&contents) == entry.oid => '.',
+        _ => 'M',
+    }
+}
+
+#[cfg(unix)]
+fn executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Untracked paths, with `??`, and ignored ones, with `!!`. Like git, a
+/// nested repository or an ignored directory without tracked files inside is
+/// reported as a whole instead of file by file.
+fn untracked(repo: &Repository, tracked: &HashSet<&str>) -> Vec<(String, &'static str)> {
+    let mut tracked_dirs = HashSet::new();
+    for path in tracked {
+        tracked_dirs.extend(path.match_indices('/').map(|(at, _)| &path[..at]));
+    }
+
+    let mut excludes = Vec::new();
+    let (global, _) = GitignoreBuilder::new(&repo.work_dir).build_global();
+    excludes.push(global);
+    let mut info = GitignoreBuilder::new(&repo.work_dir);
+    info.add(repo.git_dir.join("info").join("exclude"));
+    excludes.extend(info.build().ok());
+
+    let mut found = Vec::new();
+    let mut walk = UntrackedWalk {
+        tracked,
+        tracked_dirs: &tracked_dirs,
+        found: &mut found,
+    };
+    walk.visit(&repo.work_dir, "", false, &mut excludes);
+    found
+}
+
+struct UntrackedWalk<'a> {
+    tracked: &'a HashSet<&'a str>,
+    tracked_dirs: &'a HashSet<&'a str>,
+    found: &'a mut Vec<(String, &'static str)>,
+}
+
+impl UntrackedWalk<'_> {
+    /// Visits the directory `dir`, at `prefix` in the work tree, with the
+    /// ignore rules that apply to it, innermost last.
+    fn visit(&mut self, dir: &Path, prefix: &str, ignored: bool, rules: &mut Vec<Gitignore>) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        let gitignore = dir.join(".gitignore");
+        let pushed = !ignored && gitignore.is_file();
+        if pushed {
+            rules.push(Gitignore::new(&gitignore).0);
+        }
+
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            if name == ".git" {
+                continue;
+            }
+            let path = entry.path();
+            let relative = if prefix.is_empty() {
+                name.to_string_lossy().into_owned()
+            } else {
+                format!("{}/{}", prefix, name.to_string_lossy())
+            };
+            let is_dir = entry.file_type().map_or(false, |kind| kind.is_dir());
+            let is_ignored = ignored
+                || rules
+                    .iter()
+                    .rev()
+                    .map(|rules| rules.matched(&path, is_dir))
+                    .find(|found| !found.is_none())
+                    .map_or(false, |found| found.is_ignore());
+
+            if !is_dir {
+                if !self.tracked.contains(relative.as_str()) {
+                    let status = if is_ignored { "!!" } else { "??" };
+                    self.found.push((relative, status));
+                }
+            } else if !self.tracked_dirs.contains(relative.as_str()) {
+                if is_ignored {
+                    self.found.push((relative, "!!"));
+                } else if path.join(".git").exists() {
+                    if !self.tracked.contains(relative.as_str()) {
+                        self.found.push((relative, "??"));
+                    }
+                } else {
+                    self.visit(&path, &relative, false, rules);
+                }
+            } else {
+                self.visit(&path, &relative, is_ignored, rules);
+            }
+        }
+
+        if pushed {
+            rules.pop();
+        }
+    }
+}
@@ -1,11 +1,15 @@
 pub mod actions;
 pub mod config;
 pub mod format;
+#[cfg(feature = "git")]
+pub mod git;
+#[cfg(feature = "syntax")]
 pub mod syntax;
 pub mod ui;
 
 pub use actions::{Action, ActionHelp, ActionRegistry};
 pub use config::{ConfigManager, PluginConfig};
+#[cfg(feature = "syntax")]
 pub use syntax::CodeHighlighter;
 pub use ui::{
     components::{BoxComponent, BoxStyle, HelpFormatter, KeyValue, List, Spinner},
@@ -69,24 +73,14 @@ pub trait ProtobufHandler {
                 Ok(PluginRequest::GetSupportedFormats)
             }
             Some(proto::plugin_message::Message::Decorate(entry)) => {
+                let path = entry.fs_path();
                 let metadata = entry
                     .metadata
-                    .map(|m| lla_plugin_interface::EntryMetadata {
-                        size: m.size,
-                        modified: m.modified,
-                        accessed: m.accessed,
-                        created: m.created,
-                        is_dir: m.is_dir,
-                        is_file: m.is_file,
-                        is_symlink: m.is_symlink,
-                        permissions: m.permissions,
-                        uid: m.uid,
-                        gid: m.gid,
-                    })
+                    .map(lla_plugin_interface::EntryMetadata::from)
                     .ok_or("Missing metadata in decorated entry")?;
 
                 let decorated = lla_plugin_interface::DecoratedEntry {
-                    path: std::path::PathBuf::from(entry.path),
+                    path,
                     metadata,
                     custom_fields: entry.custom_fields,
                 };
@@ -94,24 +88,14 @@ pub trait ProtobufHandler {
             }
             Some(proto::plugin_message::Message::FormatField(req)) => {
                 let entry = req.entry.ok_or("Missing entry in format field request")?;
+                let path = entry.fs_path();
                 let metadata = entry
                     .metadata
-                    .map(|m| lla_plugin_interface::EntryMetadata {
-                        size: m.size,
-                        modified: m.modified,
-                        accessed: m.accessed,
-                        created: m.created,
-                        is_dir: m.is_dir,
-                        is_file: m.is_file,
-                        is_symlink: m.is_symlink,
-                        permissions: m.permissions,
-                        uid: m.uid,
-                        gid: m.gid,
-                    })
+                    .map(lla_plugin_interface::EntryMetadata::from)
                     .ok_or("Missing metadata in decorated entry")?;
 
                 let decorated = lla_plugin_interface::DecoratedEntry {
-                    path: std::path::PathBuf::from(entry.path),
+                    path,
                     metadata,
                     custom_fields: entry.custom_fields,
                 };
@@ -140,25 +124,7 @@ pub trait ProtobufHandler {
                 })
             }
             PluginResponse::Decorated(entry) => {
-                let proto_metadata = proto::EntryMetadata {
-                    size: entry.metadata.size,
-                    modified: entry.metadata.modified,
-                    accessed: entry.metadata.accessed,
-                    created: entry.metadata.created,
-                    is_dir: entry.metadata.is_dir,
-                    is_file: entry.metadata.is_file,
-                    is_symlink: entry.metadata.is_symlink,
-                    permissions: entry.metadata.permissions,
-                    uid: entry.metadata.uid,
-                    gid: entry.metadata.gid,
-                };
-
-                let proto_entry = proto::DecoratedEntry {
-                    path: entry.path.to_string_lossy().to_string(),
-                    metadata: Some(proto_metadata),
-                    custom_fields: entry.custom_fields,
-                };
-                proto::plugin_message::Message::DecoratedResponse(proto_entry)
+                proto::plugin_message::Message::DecoratedResponse(entry.into())
             }
             PluginResponse::FormattedField(field) => {
                 proto::plugin_message::Message::FieldResponse(proto::FormattedFieldResponse {
@@ -1,4 +1,5 @@
 use colored::Colorize;
+use lla_plugin_interface::size::SizeStyle;
 use std::fmt::Display;
 
 #[derive(Clone, Copy)]
@@ -75,21 +76,9 @@ impl Display for TextBlock {
     }
 }
 
+/// Renders a byte count in IEC units (`4.0K`). Plugins are not told the
+/// `--size-style` lla runs with, so their sizes always use this style; call
+/// `SizeStyle::format` directly to pick another one.
 pub fn format_size(size: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-    const TB: u64 = GB * 1024;
-
-    if size >= TB {
-        format!("{:.2} TB", size as f64 / TB as f64)
-    } else if size >= GB {
-        format!("{:.2} GB", size as f64 / GB as f64)
-    } else if size >= MB {
-        format!("{:.2} MB", size as f64 / MB as f64)
-    } else if size >= KB {
-        format!("{:.2} KB", size as f64 / KB as f64)
-    } else {
-        format!("{} B", size)
-    }
+    SizeStyle::Iec.format(size)
 }
@@ -2,12 +2,13 @@ use lazy_static::lazy_static;
 use lla_plugin_interface::{Plugin, PluginRequest, PluginResponse};
 use lla_plugin_utils::{
     config::PluginConfig,
+    git::Repository,
     ui::components::{BoxComponent, BoxStyle, HelpFormatter, KeyValue, List, Spinner},
     ActionRegistry, BasePlugin, ConfigurablePlugin, ProtobufHandler,
 };
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::Path, process::Command};
+use std::{collections::HashMap, path::Path};
 
 lazy_static! {
     static ref SPINNER: RwLock<Spinner> = RwLock::new(Spinner::new());
@@ -109,53 +110,22 @@ impl GitStatusPlugin {
         plugin
     }
 
-    fn is_git_repo(path: &Path) -> bool {
-        let mut current_dir = Some(path);
-        while let Some(dir) = current_dir {
-            if dir.join(".git").exists() {
-                return true;
-            }
-            current_dir = dir.parent();
-        }
-        false
-    }
-
     fn get_git_info(path: &Path) -> Option<(String, String, String)> {
-        if !Self::is_git_repo(path) {
-            return None;
-        }
-
-        let path_str = path.to_string_lossy();
-        let parent = path.parent().unwrap_or(path);
-
-        let status_output = Command::new("git")
-            .args(["status", "--porcelain", "--ignored"])
-            .arg(&*path_str)
-            .current_dir(parent)
-            .output()
-            .ok()?;
-
-        let branch_output = Command::new("git")
-            .args(["rev-parse", "--abbrev-ref", "HEAD"])
-            .current_dir(parent)
-            .output()
-            .ok()?;
-
-        let commit_output = Command::new("git")
-            .args(["log", "-1", "--format=%h %s"])
-            .current_dir(parent)
-            .output()
-            .ok()?;
-
-        let status = String::from_utf8(status_output.stdout).ok()?;
-        let branch = String::from_utf8(branch_output.stdout)
-            .ok()?
-            .trim()
-            .to_string();
-        let commit = String::from_utf8(commit_output.stdout)
-            .ok()?
-            .trim()
-            .to_string();
+        let repo = Repository::discover(path)?;
+        let relative = repo.relative_path(path)?;
+
+        let status = repo
+            .status()
+            .under(&relative)
+            .filter(|(_, status)| *status != ".")
+            .map(|(_, status)| status)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let branch = repo.branch().unwrap_or_else(|| "HEAD".to_string());
+        let commit = repo
+            .head_commit()
+            .map(|commit| format!("{} {}", commit.oid.short(), commit.summary))
+            .unwrap_or_default();
 
         Some((status, branch, commit))
     }
@@ -174,11 +144,11 @@ impl GitStatusPlugin {
             let worktree_status = status_chars.get(1).copied().unwrap_or(' ');
 
             match (index_status, worktree_status) {
-                ('M', ' ') => {
+                ('M', '.') => {
                     staged += 1;
                     formatted_entries.push("staged");
                 }
-                (' ', 'M') => {
+                ('.', 'M') => {
                     modified += 1;
                     formatted_entries.push("modified");
                 }
@@ -187,11 +157,11 @@ impl GitStatusPlugin {
                     modified += 1;
                     formatted_entries.push("staged & modified");
                 }
-                ('A', ' ') => {
+                ('A', '.') => {
                     staged += 1;
                     formatted_entries.push("new file");
                 }
-                ('D', ' ') | (' ', 'D') => {
+                ('D', '.') | ('.', 'D') => {
                     modified += 1;
                     formatted_entries.push("deleted");
                 }
@@ -207,10 +177,11 @@ impl GitStatusPlugin {
                     conflicts += 1;
                     formatted_entries.push("conflict");
                 }
-                (' ', '?') => {
+                ('?', '?') => {
                     untracked += 1;
                     formatted_entries.push("untracked");
                 }
+                ('!', '!') => formatted_entries.push("ignored"),
                 _ => {}
             }
         }
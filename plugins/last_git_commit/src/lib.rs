@@ -2,12 +2,13 @@ use lazy_static::lazy_static;
 use lla_plugin_interface::{Plugin, PluginRequest, PluginResponse};
 use lla_plugin_utils::{
     config::PluginConfig,
+    git::{self, Repository},
     ui::components::{BoxComponent, BoxStyle, HelpFormatter, KeyValue, List, Spinner},
     ActionRegistry, BasePlugin, ConfigurablePlugin, ProtobufHandler,
 };
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::Path, process::Command};
+use std::{collections::HashMap, path::Path};
 
 lazy_static! {
     static ref SPINNER: RwLock<Spinner> = RwLock::new(Spinner::new());
@@ -104,23 +105,13 @@ impl LastGitCommitPlugin {
     }
 
     fn get_last_commit_info(path: &Path) -> Option<(String, String, String)> {
-        let output = Command::new("git")
-            .args(["log", "-1", "--format=%h|%an|%ar", "--", path.to_str()?])
-            .output()
-            .ok()?;
-
-        let output_str = String::from_utf8(output.stdout).ok()?;
-        let parts: Vec<&str> = output_str.trim().split('|').collect();
-
-        if parts.len() == 3 {
-            Some((
-                parts[0].to_string(),
-                parts[1].to_string(),
-                parts[2].to_string(),
-            ))
-        } else {
-            None
-        }
+        let repo = Repository::discover(path)?;
+        let commit = repo.last_commit(&repo.relative_path(path)?)?;
+        Some((
+            commit.oid.short(),
+            commit.author.clone(),
+            git::relative_time(commit.author_time),
+        ))
     }
 
     fn format_commit_info(
@@ -3,7 +3,10 @@ use lazy_static::lazy_static;
 use lla_plugin_interface::{Plugin, PluginRequest, PluginResponse};
 use lla_plugin_utils::{
     config::PluginConfig,
-    ui::components::{BoxComponent, BoxStyle, HelpFormatter, Spinner},
+    ui::{
+        components::{BoxComponent, BoxStyle, HelpFormatter, Spinner},
+        format_size,
+    },
     ActionRegistry, BasePlugin, ConfigurablePlugin, ProtobufHandler,
 };
 use parking_lot::RwLock;
@@ -107,22 +110,6 @@ impl FileSizeVisualizerPlugin {
         plugin
     }
 
-    fn format_size(size: u64) -> String {
-        const KB: u64 = 1024;
-        const MB: u64 = KB * 1024;
-        const GB: u64 = MB * 1024;
-        const TB: u64 = GB * 1024;
-
-        match size {
-            0 => "0 B".to_string(),
-            s if s < KB => format!("{} B", s),
-            s if s < MB => format!("{:.1} KB", s as f64 / KB as f64),
-            s if s < GB => format!("{:.1} MB", s as f64 / MB as f64),
-            s if s < TB => format!("{:.2} GB", s as f64 / GB as f64),
-            s => format!("{:.2} TB", s as f64 / TB as f64),
-        }
-    }
-
     fn size_to_bar(size: u64, max_size: u64, max_width: usize) -> String {
         let normalized_size = (size as f64 / max_size as f64 * max_width as f64).ceil() as usize;
         let width = cmp::min(normalized_size, max_width);
@@ -232,7 +219,7 @@ impl FileSizeVisualizerPlugin {
                                     "bright_magenta" => colored::Color::BrightMagenta,
                                     _ => colored::Color::White,
                                 }),
-                                Self::format_size(size).bright_yellow()
+                                format_size(size).bright_yellow()
                             ),
                             format!(
                                 "│ {}% of reference (1GB)",
@@ -254,7 +241,7 @@ impl FileSizeVisualizerPlugin {
                                 "bright_magenta" => colored::Color::BrightMagenta,
                                 _ => colored::Color::White,
                             }),
-                            Self::format_size(size).bright_yellow()
+                            format_size(size).bright_yellow()
                         )
                     }
                     _ => return None,